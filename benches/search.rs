@@ -0,0 +1,203 @@
+//! Benchmarks of the link extraction, of breadth-first searches through
+//! synthetic graphs served by a mock wiki, and of the reconstruction of
+//! paths from the state of a search
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    iter,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::json;
+use wiki_path::{extract_links, extract_links_streaming, BfsSearcher, BfsState};
+use wiremock::{matchers::any, Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Articles in the parent map of the path reconstruction benchmark
+const STATE_ARTICLES: usize = 10_000;
+
+/// Copies of the fixture article in the HTML of the large article, about
+/// 1 MB
+const LARGE_ARTICLE_COPIES: usize = 24;
+
+/// The system allocator, counting the bytes allocated so that the peak
+/// memory of link extraction can be compared
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Most bytes allocated at once by `f` on top of what already was
+fn peak_memory<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    black_box(f());
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+/// A complete tree of `branching` links per article over `depth` levels.
+/// Articles are numbered breadth-first from `0`, the root, so the children
+/// of `n` are `n * branching + 1` on.
+struct Tree {
+    branching: usize,
+    articles: usize,
+}
+
+impl Tree {
+    fn new(branching: usize, depth: u32) -> Self {
+        Self {
+            branching,
+            articles: (0..=depth).map(|level| branching.pow(level)).sum(),
+        }
+    }
+
+    fn children(&self, n: usize) -> impl Iterator<Item = usize> + '_ {
+        (n * self.branching + 1..=n * self.branching + self.branching)
+            .filter(|&child| child < self.articles)
+    }
+}
+
+impl Respond for Tree {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        if let Some(article) = req.url.path().strip_prefix("/wiki/") {
+            let Some(n) = article.parse().ok().filter(|&n| n < self.articles) else {
+                return ResponseTemplate::new(404);
+            };
+            let links: String = self
+                .children(n)
+                .map(|child| format!(r#"<a href="/wiki/{}">{}</a> "#, child, child))
+                .collect();
+            let html = format!(
+                r#"<html><body><div class="mw-parser-output"><p>{}</p></div></body></html>"#,
+                links
+            );
+            return ResponseTemplate::new(200).set_body_raw(html, "text/html");
+        }
+
+        // Every title looked up exists
+        let pages: Vec<_> = req
+            .url
+            .query_pairs()
+            .filter(|(param, _)| param == "titles")
+            .flat_map(|(_, titles)| {
+                titles
+                    .split('|')
+                    .map(|title| json!({ "title": title }))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "query": { "pages": pages } }))
+    }
+}
+
+fn link_extraction(c: &mut Criterion) {
+    let html = include_bytes!("fixtures/article.html");
+    c.bench_function("extract_links", |b| {
+        b.iter(|| extract_links(black_box(html)))
+    });
+    c.bench_function("extract_links_streaming", |b| {
+        b.iter(|| extract_links_streaming(black_box(&html[..])).unwrap())
+    });
+
+    // Criterion only times, the memory is printed alongside
+    let large = html.repeat(LARGE_ARTICLE_COPIES);
+    println!(
+        "Peak memory extracting the links of {} bytes of HTML: {} bytes, {} bytes streaming",
+        large.len(),
+        peak_memory(|| extract_links(&large)),
+        peak_memory(|| extract_links_streaming(large.as_slice()).unwrap()),
+    );
+}
+
+fn bfs(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("bfs");
+    group.sample_size(10);
+
+    for (branching, depth) in [(2, 6), (4, 4), (8, 3)] {
+        let tree = Tree::new(branching, depth);
+        // The last article of the deepest level, found last
+        let end = (tree.articles - 1).to_string();
+        let server = runtime.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(any()).respond_with(tree).mount(&server).await;
+            server
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("branching {}", branching), depth),
+            &end,
+            |b, end| {
+                b.to_async(&runtime).iter(|| async {
+                    BfsSearcher::builder(["0"], [end.as_str()])
+                        .base_url(server.uri())
+                        .request_wait(Duration::ZERO)
+                        .build()
+                        .unwrap()
+                        .search()
+                        .await
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn path_reconstruction(c: &mut Criterion) {
+    // A binary tree, each article reached from the one at half its index,
+    // past the sentinel at 0
+    let articles: Vec<_> = iter::once(String::new())
+        .chain((1..=STATE_ARTICLES).map(|idx| format!("Article_{}", idx)))
+        .collect();
+    let parents: serde_json::Map<_, _> = (2..=STATE_ARTICLES)
+        .map(|idx| (idx.to_string(), json!(idx / 2)))
+        .chain([("1".to_string(), json!(0))])
+        .collect();
+    let visited: serde_json::Map<_, _> = articles
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(idx, title)| (title.clone(), json!(idx)))
+        .collect();
+    let state: BfsState = serde_json::from_value(json!({
+        "start": [articles[1]],
+        "end": [],
+        "max_depth": 25,
+        "depth": 0,
+        "articles": articles,
+        "article_parent": parents,
+        "visited": visited,
+        "curr_idx": 0,
+        "next_level_len": 0,
+    }))
+    .unwrap();
+    let deepest = &articles[STATE_ARTICLES];
+
+    c.bench_function("paths_to", |b| {
+        b.iter(|| state.paths_to(black_box(deepest)))
+    });
+}
+
+criterion_group!(benches, link_extraction, bfs, path_reconstruction);
+criterion_main!(benches);
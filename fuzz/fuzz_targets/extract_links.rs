@@ -0,0 +1,10 @@
+//! Feeds arbitrary bytes to the link extraction, which parses HTML from
+//! the network
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|html: &[u8]| {
+    wiki_path::extract_links(html);
+});
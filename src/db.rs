@@ -0,0 +1,289 @@
+//! Breadth-first searches stored in SQLite with --db
+
+use std::path::Path;
+
+use rusqlite::OptionalExtension;
+
+use crate::{search::BfsState, Article};
+
+/// Columns of `articles` describing articles once expanded, added to
+/// databases made before them
+const ARTICLE_COLUMNS: &[(&str, &str)] = &[
+    ("byte_length", "INTEGER"),
+    ("categories", "TEXT"),
+    ("fetch_time_ms", "INTEGER"),
+    ("last_updated", "INTEGER"),
+];
+
+/// Search state stored in SQLite with --db. Articles are numbered as in
+/// `BfsState`, and `edges` links each article to its parent, `other_edges`
+/// to the other parents kept with --all. Expanded articles also get what
+/// `Article` tells about them, their categories one per line.
+pub(crate) struct SearchDb {
+    conn: rusqlite::Connection,
+    search_id: i64,
+    /// Articles already stored
+    saved_len: usize,
+    /// `curr_idx` when last stored, articles after it may have been renamed
+    /// by a redirect since
+    saved_curr_idx: usize,
+    /// Expanded articles already described
+    saved_expanded: usize,
+}
+
+impl SearchDb {
+    /// Opens the database, returning the state of an unfinished search from
+    /// `start` to `end` if there is one
+    pub(crate) fn open(
+        file: &Path,
+        start: &[String],
+        end: &[String],
+        lang: &str,
+        max_depth: u32,
+    ) -> rusqlite::Result<(Self, Option<BfsState>)> {
+        let conn = rusqlite::Connection::open(file)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS searches (
+                id INTEGER PRIMARY KEY,
+                start TEXT NOT NULL,
+                targets TEXT NOT NULL,
+                lang TEXT NOT NULL,
+                depth INTEGER NOT NULL,
+                curr_idx INTEGER NOT NULL,
+                next_level_len INTEGER NOT NULL,
+                finished INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS articles (
+                search_id INTEGER NOT NULL REFERENCES searches(id),
+                id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                byte_length INTEGER,
+                categories TEXT,
+                fetch_time_ms INTEGER,
+                last_updated INTEGER,
+                PRIMARY KEY (search_id, id)
+            );
+            CREATE TABLE IF NOT EXISTS edges (
+                search_id INTEGER NOT NULL REFERENCES searches(id),
+                parent_id INTEGER NOT NULL,
+                child_id INTEGER NOT NULL,
+                PRIMARY KEY (search_id, child_id)
+            );
+            CREATE TABLE IF NOT EXISTS other_edges (
+                search_id INTEGER NOT NULL REFERENCES searches(id),
+                parent_id INTEGER NOT NULL,
+                child_id INTEGER NOT NULL,
+                PRIMARY KEY (search_id, child_id, parent_id)
+            );
+            CREATE TABLE IF NOT EXISTS redirects (
+                search_id INTEGER NOT NULL REFERENCES searches(id),
+                title TEXT NOT NULL,
+                article_id INTEGER NOT NULL,
+                PRIMARY KEY (search_id, title)
+            );",
+        )?;
+
+        let columns = conn
+            .prepare("SELECT name FROM pragma_table_info('articles')")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (column, kind) in ARTICLE_COLUMNS {
+            if !columns.iter().any(|name| name == column) {
+                conn.execute(
+                    &format!("ALTER TABLE articles ADD COLUMN {} {}", column, kind),
+                    [],
+                )?;
+            }
+        }
+
+        let starts = start.join("\n");
+        let targets = end.join("\n");
+        let mut db = Self {
+            conn,
+            search_id: 0,
+            saved_len: 0,
+            saved_curr_idx: 0,
+            saved_expanded: 0,
+        };
+
+        let unfinished = db
+            .conn
+            .query_row(
+                "SELECT id, depth, curr_idx, next_level_len FROM searches
+                WHERE start = ?1 AND targets = ?2 AND lang = ?3 AND finished = 0
+                ORDER BY id DESC LIMIT 1",
+                (&starts, &targets, lang),
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, u32>(1)?,
+                        row.get::<_, usize>(2)?,
+                        row.get::<_, usize>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((search_id, depth, curr_idx, next_level_len)) = unfinished else {
+            db.conn.execute(
+                "INSERT INTO searches
+                (start, targets, lang, depth, curr_idx, next_level_len, finished)
+                VALUES (?1, ?2, ?3, 0, 0, 1, 0)",
+                (&starts, &targets, lang),
+            )?;
+            db.search_id = db.conn.last_insert_rowid();
+            return Ok((db, None));
+        };
+
+        let mut state = BfsState::new(start, end, max_depth);
+        state.depth = depth;
+        state.curr_idx = curr_idx;
+        state.next_level_len = next_level_len;
+        state.articles.truncate(1);
+        state.article_parent.clear();
+        state.visited.clear();
+
+        // Statements borrow the connection
+        {
+            let mut stmt = db
+                .conn
+                .prepare("SELECT title FROM articles WHERE search_id = ?1 ORDER BY id")?;
+            for title in stmt.query_map([search_id], |row| row.get::<_, String>(0))? {
+                let title = title?;
+                state.visited.insert(title.clone(), state.articles.len());
+                state.articles.push(title);
+            }
+
+            let mut stmt = db
+                .conn
+                .prepare("SELECT parent_id, child_id FROM edges WHERE search_id = ?1")?;
+            for edge in stmt.query_map([search_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (parent, child) = edge?;
+                state.article_parent.insert(child, parent);
+            }
+
+            let mut stmt = db
+                .conn
+                .prepare("SELECT parent_id, child_id FROM other_edges WHERE search_id = ?1")?;
+            for edge in stmt.query_map([search_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (parent, child) = edge?;
+                state.other_parents.entry(child).or_default().push(parent);
+            }
+
+            let mut stmt = db
+                .conn
+                .prepare("SELECT title, article_id FROM redirects WHERE search_id = ?1")?;
+            for redirect in stmt.query_map([search_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (title, idx) = redirect?;
+                state.visited.insert(title, idx);
+            }
+        }
+
+        for idx in 1..state.articles.len() {
+            state.article_parent.entry(idx).or_insert(0);
+        }
+
+        db.search_id = search_id;
+        db.saved_len = state.articles.len();
+        db.saved_curr_idx = state.curr_idx;
+
+        Ok((db, Some(state)))
+    }
+
+    /// Stores the articles found since the last save, and what was learned
+    /// about those of `expanded` since, in one transaction
+    pub(crate) fn save(
+        &mut self,
+        state: &BfsState,
+        finished: bool,
+        expanded: &[Article],
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+
+        {
+            // Renamed, but still the same article
+            let mut article = tx.prepare_cached(
+                "INSERT INTO articles (search_id, id, title) VALUES (?1, ?2, ?3)
+                ON CONFLICT (search_id, id) DO UPDATE SET title = excluded.title",
+            )?;
+            for idx in (self.saved_curr_idx + 1).max(1)..state.articles.len() {
+                article.execute((self.search_id, idx, &state.articles[idx]))?;
+            }
+
+            let mut described = tx.prepare_cached(
+                "UPDATE articles
+                SET byte_length = ?1, categories = ?2, fetch_time_ms = ?3, last_updated = ?4
+                WHERE search_id = ?5 AND id = ?6",
+            )?;
+            for article in expanded.iter().skip(self.saved_expanded) {
+                let Some(&idx) = state.visited.get(&article.title) else {
+                    continue;
+                };
+                described.execute((
+                    article.byte_length,
+                    article
+                        .categories
+                        .as_ref()
+                        .map(|categories| categories.join("\n")),
+                    article.fetch_time_ms,
+                    article.last_updated,
+                    self.search_id,
+                    idx,
+                ))?;
+            }
+
+            let mut edge = tx.prepare_cached(
+                "INSERT OR IGNORE INTO edges (search_id, parent_id, child_id) VALUES (?1, ?2, ?3)",
+            )?;
+            for idx in self.saved_len.max(1)..state.articles.len() {
+                // Start articles have no parent
+                let parent = state.article_parent[&idx];
+                if parent != 0 {
+                    edge.execute((self.search_id, parent, idx))?;
+                }
+            }
+
+            let mut other_edge = tx.prepare_cached(
+                "INSERT OR IGNORE INTO other_edges (search_id, parent_id, child_id)
+                VALUES (?1, ?2, ?3)",
+            )?;
+            for (&idx, parents) in &state.other_parents {
+                // Only articles of the last depth get other parents
+                if idx >= self.saved_len {
+                    for &parent in parents {
+                        other_edge.execute((self.search_id, parent, idx))?;
+                    }
+                }
+            }
+
+            let mut redirect = tx.prepare_cached(
+                "INSERT OR IGNORE INTO redirects (search_id, title, article_id)
+                VALUES (?1, ?2, ?3)",
+            )?;
+            for (title, &idx) in &state.visited {
+                if state.articles[idx] != *title {
+                    redirect.execute((self.search_id, title, idx))?;
+                }
+            }
+
+            tx.execute(
+                "UPDATE searches SET depth = ?1, curr_idx = ?2, next_level_len = ?3, finished = ?4
+                WHERE id = ?5",
+                (
+                    state.depth,
+                    state.curr_idx,
+                    state.next_level_len,
+                    finished,
+                    self.search_id,
+                ),
+            )?;
+        }
+
+        tx.commit()?;
+
+        self.saved_len = state.articles.len();
+        self.saved_curr_idx = state.curr_idx;
+        self.saved_expanded = expanded.len();
+        Ok(())
+    }
+}
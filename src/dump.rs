@@ -0,0 +1,384 @@
+//! Links read from a Wikipedia XML dump with --dump, indexed once into
+//! SQLite so that searches don't send any request
+
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use bzip2::read::MultiBzDecoder;
+use indicatif::{ProgressBar, ProgressStyle};
+use quick_xml::{escape::resolve_predefined_entity, events::Event, XmlVersion};
+use regex::Regex;
+use rusqlite::{OpenFlags, OptionalExtension};
+
+use crate::{
+    fetch::FetchError,
+    wiki::{normalize_title, Wiki, MAIN_NAMESPACE},
+    DEFAULT_LANG,
+};
+
+/// A `[[wikilink]]`, capturing its target without the section or label
+static WIKILINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[([^\[\]|#]*)(?:#[^\[\]|]*)?(?:\|[^\[\]]*)?\]\]").unwrap());
+
+/// Templates that mark a disambiguation page on the English Wikipedia
+static DISAMBIG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\{\{\s*(?:disambiguation|disambig|dab|hndis|geodis)\s*[|}]").unwrap()
+});
+
+/// Why a dump couldn't be indexed
+#[derive(Debug, thiserror::Error)]
+pub enum DumpError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The dump isn't the XML Wikipedia exports, or is cut short
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("{0}")]
+    Db(#[from] rusqlite::Error),
+}
+
+/// An article of the dump as it's read
+#[derive(Default)]
+struct DumpPage {
+    title: String,
+    ns: String,
+    redirect: Option<String>,
+    text: String,
+}
+
+/// Element of a page, or of the namespaces of the dump, whose text is
+/// being read
+#[derive(Clone, Copy)]
+enum Field {
+    Title,
+    Ns,
+    Text,
+    /// Name of the namespace of this ID
+    Namespace(i32),
+}
+
+/// Reads the articles of the dump `file`, a `pages-articles.xml.bz2` of
+/// https://dumps.wikimedia.org, and stores the targets of their wikilinks
+/// in the SQLite database `index`, replacing it. Pages outside the main
+/// namespace are left out, as are links to them, told apart by the names
+/// of the namespaces the dump lists. Returns the number of articles
+/// indexed, redirects aside.
+///
+/// The index is written next to `index` first and only takes its place
+/// once complete, so it's never left half built.
+pub fn index_dump(file: &Path, index: &Path, progress: bool) -> Result<usize, DumpError> {
+    let mut partial = OsString::from(index);
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    match fs::remove_file(&partial) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+
+    let mut conn = rusqlite::Connection::open(&partial)?;
+    // Nothing to recover if indexing stops halfway
+    conn.execute_batch(
+        "PRAGMA journal_mode = OFF;
+        PRAGMA synchronous = OFF;
+        CREATE TABLE pages (
+            id INTEGER PRIMARY KEY,
+            title TEXT NOT NULL UNIQUE,
+            redirect TEXT,
+            disambiguation INTEGER NOT NULL,
+            links TEXT NOT NULL
+        );",
+    )?;
+
+    let bar = if progress {
+        let style =
+            ProgressStyle::with_template("[{elapsed_precise}] {spinner} {pos} pages indexed")
+                .unwrap();
+        ProgressBar::new_spinner().with_style(style)
+    } else {
+        ProgressBar::hidden()
+    };
+
+    let mut articles = 0;
+    // Dumps without the names of their namespaces are taken to be English
+    let wiki = Wiki::new(DEFAULT_LANG);
+    let tx = conn.transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR REPLACE INTO pages (title, redirect, disambiguation, links)
+            VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        read_pages(file, &wiki, |page| {
+            let redirect = page.redirect.as_deref().map(normalize_title);
+            if redirect.is_none() {
+                articles += 1;
+            }
+            insert.execute((
+                normalize_title(&page.title),
+                redirect,
+                DISAMBIG_RE.is_match(&page.text),
+                wikilinks(&page.text, &wiki).join("\n"),
+            ))?;
+            bar.inc(1);
+            Ok(())
+        })?;
+    }
+    // Looked up to check a path with --verify-path
+    tx.execute_batch("CREATE INDEX pages_redirect ON pages (redirect);")?;
+    tx.commit()?;
+    bar.finish_and_clear();
+
+    drop(conn);
+    fs::rename(&partial, index)?;
+    Ok(articles)
+}
+
+/// Calls `on_page` with each page of the main namespace in the dump `file`,
+/// having set the names of the namespaces of `wiki` to those the dump lists
+/// before its pages
+fn read_pages(
+    file: &Path,
+    wiki: &Wiki,
+    mut on_page: impl FnMut(DumpPage) -> Result<(), DumpError>,
+) -> Result<(), DumpError> {
+    // Dumps are compressed in several streams, one after the other
+    let decoder = MultiBzDecoder::new(BufReader::new(File::open(file)?));
+    let mut reader = quick_xml::Reader::from_reader(BufReader::new(decoder));
+
+    let mut buf = Vec::new();
+    let mut page = DumpPage::default();
+    let mut namespace = String::new();
+    let mut namespaces = Vec::new();
+    let mut field = None;
+
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        let target = field.map(|field| match field {
+            Field::Title => &mut page.title,
+            Field::Ns => &mut page.ns,
+            Field::Text => &mut page.text,
+            Field::Namespace(_) => &mut namespace,
+        });
+
+        match event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                "page" => page = DumpPage::default(),
+                "title" => field = Some(Field::Title),
+                "ns" => field = Some(Field::Ns),
+                "text" => field = Some(Field::Text),
+                "namespace" => {
+                    let key = e.try_get_attribute("key").map_err(quick_xml::Error::from)?;
+                    field = key
+                        .and_then(|key| key.value.parse().ok())
+                        .map(Field::Namespace);
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == "redirect" => {
+                let title = e
+                    .try_get_attribute("title")
+                    .map_err(quick_xml::Error::from)?;
+                if let Some(title) = title {
+                    page.redirect = Some(title.normalized_value(XmlVersion::Implicit1_0)?.into());
+                }
+            }
+            Event::Text(text) => {
+                if let Some(target) = target {
+                    target.push_str(&text.xml10_content());
+                }
+            }
+            Event::CData(text) => {
+                if let Some(target) = target {
+                    target.push_str(&text.xml10_content());
+                }
+            }
+            // &lt; and the like come apart from the text around them
+            Event::GeneralRef(entity) => {
+                if let Some(target) = target {
+                    match entity.resolve_char_ref()? {
+                        Some(ch) => target.push(ch),
+                        None => target.push_str(resolve_predefined_entity(&entity).unwrap_or("")),
+                    }
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                "title" | "ns" | "text" => field = None,
+                "namespace" => {
+                    // The main namespace has no name
+                    if let Some(Field::Namespace(id)) = field.take() {
+                        if !namespace.is_empty() {
+                            namespaces.push((std::mem::take(&mut namespace), id));
+                        }
+                    }
+                }
+                "siteinfo" => wiki.set_namespaces(std::mem::take(&mut namespaces)),
+                "page" if page.ns == "0" => on_page(std::mem::take(&mut page))?,
+                _ => {}
+            },
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Targets of the wikilinks in `text`, in order and without repeats. Like
+/// those of the article HTML, links to other namespaces of `wiki` are left
+/// out.
+fn wikilinks(text: &str, wiki: &Wiki) -> Vec<String> {
+    let mut seen = HashSet::new();
+    WIKILINK_RE
+        .captures_iter(text)
+        .map(|link| normalize_title(&link[1]))
+        .filter(|title| !title.is_empty() && wiki.namespace(title) == Some(MAIN_NAMESPACE))
+        .filter(|title| seen.insert(title.clone()))
+        .collect()
+}
+
+/// An article of the index with its links, once redirects are followed
+pub(crate) struct IndexedPage {
+    pub(crate) title: String,
+    pub(crate) disambiguation: bool,
+    pub(crate) links: Vec<String>,
+}
+
+/// Index built by `index_dump`, shared by the clones of a `Fetcher`
+pub(crate) struct DumpIndex {
+    file: PathBuf,
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl DumpIndex {
+    pub(crate) fn open(file: &Path) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open_with_flags(file, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // Fails now if this isn't an index
+        conn.prepare("SELECT title, redirect, disambiguation, links FROM pages")?;
+
+        Ok(Self {
+            file: file.to_path_buf(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub(crate) fn file(&self) -> &Path {
+        &self.file
+    }
+
+    fn error(&self, err: rusqlite::Error) -> FetchError {
+        FetchError::Dump(self.file.clone(), err)
+    }
+
+    /// The article `title` names, or the one it redirects to, if it's in
+    /// the dump
+    pub(crate) fn page(&self, title: &str) -> Result<Option<IndexedPage>, FetchError> {
+        let conn = self.conn.lock().unwrap();
+        let lookup = |title: &str| {
+            conn.query_row(
+                "SELECT redirect, disambiguation, links FROM pages WHERE title = ?1",
+                [title],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()
+        };
+
+        let mut title = title.to_string();
+        let mut page = lookup(&title).map_err(|err| self.error(err))?;
+        // Wikipedia doesn't follow a redirect to a redirect either
+        if let Some((Some(target), _, _)) = page {
+            page = lookup(&target).map_err(|err| self.error(err))?;
+            title = target;
+        }
+
+        Ok(page.map(|(_, disambiguation, links)| IndexedPage {
+            title,
+            disambiguation,
+            links: links.lines().map(str::to_string).collect(),
+        }))
+    }
+
+    /// Title of the article `title` redirects to, or `title` itself, if
+    /// it's in the dump
+    pub(crate) fn resolve(&self, title: &str) -> Result<Option<String>, FetchError> {
+        let redirect = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT redirect FROM pages WHERE title = ?1",
+                [title],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .map_err(|err| self.error(err))?;
+        Ok(redirect.map(|redirect| redirect.unwrap_or_else(|| title.to_string())))
+    }
+
+    /// Titles that redirect to `title`
+    pub(crate) fn redirects_to(&self, title: &str) -> Result<Vec<String>, FetchError> {
+        let conn = self.conn.lock().unwrap();
+        let titles = conn
+            .prepare_cached("SELECT title FROM pages WHERE redirect = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map([title], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()
+            });
+        titles.map_err(|err| self.error(err))
+    }
+
+    /// Title of an article picked at random, if the dump has any
+    pub(crate) fn random(&self) -> Result<Option<String>, FetchError> {
+        let conn = self.conn.lock().unwrap();
+        let pick = || {
+            let max: Option<i64> =
+                conn.query_row("SELECT max(id) FROM pages", [], |row| row.get(0))?;
+            let Some(max) = max else {
+                return Ok(None);
+            };
+            // Redirects are skipped, making articles after many a bit
+            // likelier, and the first is taken past the last
+            let first_from = |id: i64| {
+                conn.query_row(
+                    "SELECT title FROM pages WHERE id >= ?1 AND redirect IS NULL
+                    ORDER BY id LIMIT 1",
+                    [id],
+                    |row| row.get(0),
+                )
+                .optional()
+            };
+            match first_from(fastrand::i64(1..=max))? {
+                Some(title) => Ok(Some(title)),
+                None => first_from(1),
+            }
+        };
+        pick().map_err(|err| self.error(err))
+    }
+
+    /// Up to `limit` articles whose title starts with the first word of
+    /// `title`, by lack of a search engine
+    pub(crate) fn suggestions(&self, title: &str, limit: usize) -> Result<Vec<String>, FetchError> {
+        let prefix = title.split('_').next().unwrap_or(title);
+        let conn = self.conn.lock().unwrap();
+        let titles = conn
+            .prepare_cached(
+                "SELECT title FROM pages
+                WHERE title >= ?1 AND title < ?1 || char(1114111) AND redirect IS NULL
+                ORDER BY title LIMIT ?2",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map((prefix, limit), |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()
+            });
+        titles.map_err(|err| self.error(err))
+    }
+}
@@ -0,0 +1,1724 @@
+//! Fetching links from Wikipedia, through the article HTML or the API, or
+//! from the index of a dump
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use jiff::ToSpan;
+use reqwest as rw;
+use scraper as sc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::{oneshot, Semaphore};
+use tracing::debug;
+
+use crate::{
+    dump::DumpIndex,
+    wiki::{
+        canonical_title, category_name, decode_title, document_categories, document_links,
+        in_edition, interwiki_links, normalize_title, short_description, soft_redirect_target,
+        split_edition, Wiki, DISAMBIG_SELECTOR, MAIN_NAMESPACE, MAX_SECTION_DEPTH,
+        MOBILE_HTML_PATH,
+    },
+    DEFAULT_MAX_RETRY_WAIT_SECS,
+};
+
+/// Wikimedia REST API endpoint for the daily views of an article
+const PAGEVIEWS_URL: &str = "https://wikimedia.org/api/rest_v1/metrics/pageviews/per-article";
+
+/// Days of views counted for the popularity of an article
+const PAGEVIEWS_DAYS: i64 = 30;
+
+/// Titles asked about in one API query, the most allowed without a bot flag
+pub(crate) const TITLES_PER_QUERY: usize = 50;
+
+/// Suggestions given for an article that doesn't exist
+const SUGGESTIONS: usize = 5;
+
+/// Width in pixels of the thumbnails of the lead images shown in the TUI
+const THUMBNAIL_PX: &str = "50";
+
+/// Wait before the first retry, doubled on each one after
+const RETRY_BASE_SECS: f64 = 1.0;
+
+/// Replication lag in seconds above which the API should refuse requests,
+/// as its etiquette asks of clients that aren't interactive
+const MAXLAG_SECS: &str = "5";
+
+/// Why a page or query result couldn't be fetched
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    #[error("{0}")]
+    Http(#[from] rw::Error),
+    /// The API answered with something else than the expected JSON
+    #[error("Invalid API response: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The API servers were still lagging behind once the retries ran out
+    #[error("API servers are busy: {0}")]
+    Lagged(String),
+    /// The index of a dump couldn't be read
+    #[error("{}: {}", .0.display(), .1)]
+    Dump(PathBuf, #[source] rusqlite::Error),
+}
+
+impl FetchError {
+    /// Whether Wikipedia refused the request for being sent too often or
+    /// while it's busy, even after the retries
+    pub(crate) fn is_rate_limit(&self) -> bool {
+        match self {
+            FetchError::Http(err) => err.status() == Some(rw::StatusCode::TOO_MANY_REQUESTS),
+            FetchError::Json(_) => false,
+            FetchError::Lagged(_) => true,
+            FetchError::Dump(..) => false,
+        }
+    }
+}
+
+/// HTTP requests sent and response bytes received
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Traffic {
+    pub requests: usize,
+    pub bytes: u64,
+}
+
+/// `Traffic` shared by the clones of a `Fetcher`
+#[derive(Default)]
+struct TrafficCounter {
+    requests: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+/// Articles kept in memory and the times they were used instead of being
+/// fetched again
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheStats {
+    pub articles: usize,
+    pub hits: u64,
+}
+
+/// Links of the articles fetched so far, shared by the clones of a
+/// `Fetcher` and so by the searches sharing its requests
+#[derive(Default)]
+struct MemoryCache {
+    links: Mutex<HashMap<String, Links>>,
+    hits: AtomicU64,
+}
+
+#[derive(Deserialize)]
+struct PageviewsResponse {
+    items: Vec<PageviewsItem>,
+}
+
+/// Views of an article on one day
+#[derive(Deserialize)]
+struct PageviewsItem {
+    views: u64,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    /// Parameters to send again to get the next batch of a result cut short
+    #[serde(rename = "continue")]
+    cont: Option<HashMap<String, ContinueValue>>,
+    query: Option<ApiQuery>,
+}
+
+/// A continuation parameter, sent as a string or a number depending on the
+/// query module
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ContinueValue {
+    String(String),
+    Number(serde_json::Number),
+}
+
+impl fmt::Display for ContinueValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContinueValue::String(value) => write!(f, "{}", value),
+            ContinueValue::Number(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// An API response refusing the request
+#[derive(Deserialize)]
+struct ApiErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    code: String,
+    #[serde(default)]
+    info: String,
+}
+
+#[derive(Deserialize)]
+struct SiteInfoResponse {
+    query: SiteInfo,
+}
+
+#[derive(Deserialize)]
+struct SiteInfo {
+    /// By ID
+    namespaces: HashMap<String, SiteNamespace>,
+    #[serde(default)]
+    namespacealiases: Vec<NamespaceAlias>,
+}
+
+#[derive(Deserialize)]
+struct SiteNamespace {
+    id: i32,
+    /// Localized, empty for the main namespace
+    name: String,
+    canonical: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NamespaceAlias {
+    id: i32,
+    alias: String,
+}
+
+/// What a lookup found under a title
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TitleStatus {
+    /// An article
+    Valid,
+    /// A redirect, to the article with this title
+    Redirect(String),
+    /// Neither, or a title Wikipedia doesn't accept
+    Missing,
+}
+
+#[derive(Deserialize)]
+struct ApiQuery {
+    pages: Vec<ApiPage>,
+    /// Titles asked about that were redirects, when following them
+    #[serde(default)]
+    redirects: Vec<ApiRedirect>,
+}
+
+#[derive(Deserialize)]
+struct ApiRedirect {
+    from: String,
+    to: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ApiPage {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    links: Vec<ApiLink>,
+    #[serde(default)]
+    linkshere: Vec<ApiLink>,
+    #[serde(default)]
+    redirects: Vec<ApiLink>,
+    #[serde(default)]
+    pageprops: HashMap<String, String>,
+    #[serde(default)]
+    categories: Vec<ApiLink>,
+    /// Bytes of the wikitext, with `prop=info`
+    #[serde(default)]
+    length: Option<usize>,
+    /// Lead image, with `prop=pageimages`
+    #[serde(default)]
+    thumbnail: Option<ApiThumbnail>,
+    #[serde(default)]
+    missing: bool,
+    #[serde(default)]
+    invalid: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ApiLink {
+    title: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ApiThumbnail {
+    source: String,
+}
+
+/// The lead image of an article, scaled down
+pub(crate) struct Thumbnail {
+    /// Title of the article, after the redirects
+    pub(crate) article: String,
+    pub(crate) url: String,
+    /// The image file, if it was downloaded
+    pub(crate) image: Option<Vec<u8>>,
+}
+
+/// An article linked from another, with its categories
+pub(crate) struct Linked {
+    pub(crate) title: String,
+    pub(crate) categories: HashSet<String>,
+    pub(crate) disambiguation: bool,
+}
+
+/// The articles an article links to, or is linked from
+#[derive(Clone)]
+pub(crate) struct Links {
+    /// Title the article was redirected to, if any
+    pub(crate) canonical: Option<String>,
+    pub(crate) disambiguation: bool,
+    pub(crate) titles: Vec<String>,
+    /// Byte offset of each of `titles` in the article HTML, 0 for
+    /// prioritized infobox links, empty when the links come from the API
+    /// or a dump
+    pub(crate) offsets: Vec<usize>,
+    /// Depth of the section each of `titles` is in, see `section_depth`,
+    /// empty when the links come from the API or a dump
+    pub(crate) section_depths: Vec<u8>,
+    /// Text of each of `titles` in the article HTML, empty when the links
+    /// come from the API or a dump
+    pub(crate) anchors: Vec<String>,
+    /// Bytes of the article HTML, if the links come from it
+    pub(crate) size: Option<usize>,
+    /// Those of `titles` in the See also section of the article HTML, empty
+    /// when the links come from the API or a dump
+    pub(crate) see_also: HashSet<String>,
+    /// Short description of the article in its HTML, if it has one
+    pub(crate) description: Option<String>,
+    /// Categories of the article, from its HTML or the API, `None` when
+    /// they weren't fetched
+    pub(crate) categories: Option<Vec<String>>,
+    /// Bytes of the wikitext of the article, if the links come from the
+    /// API
+    pub(crate) length: Option<usize>,
+    /// How long the article HTML took to be sent and read, if it wasn't
+    /// cached
+    pub(crate) fetch_time: Option<Duration>,
+}
+
+impl Links {
+    /// Reorders the links in a way that only depends on `seed`, `article`
+    /// and which links there are, not on where they are in the article.
+    /// Prioritized infobox links stay first.
+    fn shuffle(&mut self, seed: u64, article: &str) {
+        let mut order: Vec<usize> = (0..self.titles.len()).collect();
+        order.sort_by(|&a, &b| self.titles[a].cmp(&self.titles[b]));
+        fastrand::Rng::with_seed(seed ^ fnv1a(article.as_bytes())).shuffle(&mut order);
+        if !self.offsets.is_empty() {
+            order.sort_by_key(|&idx| self.offsets[idx] != 0);
+        }
+
+        self.titles = order.iter().map(|&idx| self.titles[idx].clone()).collect();
+        if !self.offsets.is_empty() {
+            self.offsets = order.iter().map(|&idx| self.offsets[idx]).collect();
+        }
+        if !self.section_depths.is_empty() {
+            self.section_depths = order.iter().map(|&idx| self.section_depths[idx]).collect();
+        }
+        if !self.anchors.is_empty() {
+            self.anchors = order.iter().map(|&idx| self.anchors[idx].clone()).collect();
+        }
+    }
+}
+
+/// When a cached page was fetched, stored next to it
+#[derive(Deserialize, Serialize)]
+struct CacheMeta {
+    /// Unix time in seconds
+    fetched: u64,
+    /// URL the page was served from
+    url: String,
+    #[serde(flatten)]
+    validators: Validators,
+}
+
+/// Headers the server sent with a page, sent back to ask whether it
+/// changed once its cached copy is older than the TTL
+#[derive(Default, Deserialize, Serialize)]
+struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_headers(headers: &rw::header::HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &rw::header::HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(rw::header::ETAG),
+            last_modified: header(rw::header::LAST_MODIFIED),
+        }
+    }
+
+    /// Makes `request` conditional, if there's anything to compare with
+    fn apply(&self, mut request: rw::RequestBuilder) -> rw::RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(rw::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(rw::header::IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// Pages saved on disk by --cache-dir. Each page is kept in a file named
+/// after its article, with a `.meta` file recording when it was fetched
+/// and the headers to check whether an article changed since.
+pub(crate) struct Cache {
+    pub(crate) dir: PathBuf,
+    pub(crate) ttl: Duration,
+}
+
+impl Cache {
+    fn path(&self, article: &str, ext: &str) -> PathBuf {
+        // Titles can contain slashes, which would make subdirectories
+        let name = article.replace('%', "%25").replace('/', "%2F");
+        self.dir.join(format!("{}.{}", name, ext))
+    }
+
+    /// Returns the URL and body of a page younger than the TTL
+    fn get(&self, article: &str, ext: &str) -> Option<(String, String)> {
+        self.read(article, ext)
+            .filter(|(meta, _)| self.is_fresh(meta))
+            .map(|(meta, body)| (meta.url, body))
+    }
+
+    /// Returns a page along with when it was fetched, however old
+    fn read(&self, article: &str, ext: &str) -> Option<(CacheMeta, String)> {
+        let meta = fs::read_to_string(self.path(article, &format!("{}.meta", ext))).ok()?;
+        let meta: CacheMeta = serde_json::from_str(&meta).ok()?;
+        let body = fs::read_to_string(self.path(article, ext)).ok()?;
+        Some((meta, body))
+    }
+
+    fn is_fresh(&self, meta: &CacheMeta) -> bool {
+        let age = SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(meta.fetched))
+            .unwrap_or_default();
+        age <= self.ttl
+    }
+
+    /// Saves a page. Files are renamed into place, so concurrent readers
+    /// and writers never see a partial page.
+    fn put(
+        &self,
+        article: &str,
+        ext: &str,
+        url: &str,
+        body: &str,
+        validators: Validators,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        self.write(&self.path(article, ext), body)?;
+        // Written last, a page only counts as cached once this exists
+        self.touch(
+            article,
+            ext,
+            CacheMeta {
+                fetched: 0,
+                url: url.to_string(),
+                validators,
+            },
+        )
+    }
+
+    /// Marks a page as fetched now, once the server said it didn't change
+    fn touch(&self, article: &str, ext: &str, mut meta: CacheMeta) -> io::Result<()> {
+        meta.fetched = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.write(
+            &self.path(article, &format!("{}.meta", ext)),
+            &serde_json::to_string(&meta)?,
+        )
+    }
+
+    fn write(&self, file: &Path, contents: &str) -> io::Result<()> {
+        static TMP_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let mut tmp = file.as_os_str().to_owned();
+        tmp.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, file)
+    }
+}
+
+/// HTTP client whose requests are spread over a fixed number of workers.
+/// Each worker waits at least `req_wait` between the requests it sends.
+#[derive(Clone)]
+pub(crate) struct Fetcher {
+    client: rw::Client,
+    workers: Arc<Semaphore>,
+    req_wait: Duration,
+    max_retries: u32,
+    /// Longest wait before retrying, Retry-After included
+    max_retry_wait: Duration,
+    api: bool,
+    pub(crate) wiki: Arc<Wiki>,
+    cache: Option<Arc<Cache>>,
+    memory: Option<Arc<MemoryCache>>,
+    traffic: Arc<TrafficCounter>,
+    /// Sections of the article HTML whose links are left out
+    skip_sections: Arc<[String]>,
+    prioritize_infobox: bool,
+    intro_only: bool,
+    /// Fetch the mobile HTML of articles from the REST API
+    mobile: bool,
+    /// Where links are taken from instead of Wikipedia
+    dump: Option<Arc<DumpIndex>>,
+    /// Threads the article HTML is parsed on, if not on those sending the
+    /// requests
+    parse_pool: Option<Arc<rayon::ThreadPool>>,
+    include_redirects: bool,
+    /// Seed the links of each article are shuffled with, if any
+    seed: Option<u64>,
+    /// Other editions whose articles are followed from language links, by
+    /// language code
+    interwiki: Arc<HashMap<String, Wiki>>,
+}
+
+impl Fetcher {
+    pub(crate) fn new(
+        client: rw::Client,
+        workers: usize,
+        req_wait: Duration,
+        max_retries: u32,
+        api: bool,
+        wiki: Wiki,
+        cache: Option<Cache>,
+    ) -> Self {
+        Self {
+            client,
+            workers: Arc::new(Semaphore::new(workers)),
+            req_wait,
+            max_retries,
+            max_retry_wait: Duration::from_secs_f64(DEFAULT_MAX_RETRY_WAIT_SECS),
+            api,
+            wiki: Arc::new(wiki),
+            cache: cache.map(Arc::new),
+            memory: None,
+            traffic: Arc::default(),
+            skip_sections: Arc::new([]),
+            prioritize_infobox: false,
+            intro_only: false,
+            mobile: false,
+            dump: None,
+            parse_pool: None,
+            include_redirects: false,
+            seed: None,
+            interwiki: Arc::default(),
+        }
+    }
+
+    /// Waits at most `wait` before retrying a request
+    pub(crate) fn max_retry_wait(mut self, wait: Duration) -> Self {
+        self.max_retry_wait = wait;
+        self
+    }
+
+    /// Leaves out the links in the sections titled one of `sections`, and
+    /// in their subsections. The API doesn't tell where links are, so this
+    /// only applies to article HTML.
+    pub(crate) fn skip_sections(mut self, sections: Vec<String>) -> Self {
+        self.skip_sections = sections.into();
+        self
+    }
+
+    /// Puts the links in the infobox of an article before the others, with
+    /// offset 0. Like `skip_sections`, only applies to article HTML.
+    pub(crate) fn prioritize_infobox(mut self, prioritize: bool) -> Self {
+        self.prioritize_infobox = prioritize;
+        self
+    }
+
+    /// Leaves out the links after the first section heading of an article,
+    /// keeping those of its introduction. Like `skip_sections`, only
+    /// applies to article HTML.
+    pub(crate) fn intro_only(mut self, intro_only: bool) -> Self {
+        self.intro_only = intro_only;
+        self
+    }
+
+    /// Fetches articles from the REST API as mobile HTML, which leaves out
+    /// the navigation and sidebars around the article text
+    pub(crate) fn mobile(mut self, mobile: bool) -> Self {
+        self.mobile = mobile;
+        self
+    }
+
+    /// Keeps the links of each article in memory once fetched, for as long
+    /// as the fetcher and its clones live
+    pub(crate) fn memory_cache(mut self, enabled: bool) -> Self {
+        self.memory = enabled.then(Arc::default);
+        self
+    }
+
+    /// Takes links from the index of a dump instead of fetching them, and
+    /// looks titles up there. Backlinks, categories and page views still
+    /// come from Wikipedia.
+    pub(crate) fn dump(mut self, index: DumpIndex) -> Self {
+        self.dump = Some(Arc::new(index));
+        self
+    }
+
+    /// Parses the HTML of articles on `pool` as they come, so that parsing
+    /// doesn't hold up the requests, nor wait on their rate limit
+    pub(crate) fn parse_pool(mut self, pool: rayon::ThreadPool) -> Self {
+        self.parse_pool = Some(Arc::new(pool));
+        self
+    }
+
+    /// Gives redirected articles their target as only link, so that they're
+    /// searched like any other article instead of being renamed to it
+    pub(crate) fn include_redirects(mut self, include: bool) -> Self {
+        self.include_redirects = include;
+        self
+    }
+
+    /// Shuffles the links of each article with `seed`, so that the same
+    /// seed orders them the same way however the article lays them out
+    pub(crate) fn seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Also follows the links to the articles of the Wikipedia editions of
+    /// `langs`, searching those as `in_edition` titles them. Like
+    /// `skip_sections`, only applies to article HTML.
+    pub(crate) fn interwiki(mut self, langs: &[String]) -> Self {
+        self.interwiki = Arc::new(
+            langs
+                .iter()
+                .filter(|lang| **lang != self.wiki.lang())
+                .map(|lang| (lang.clone(), Wiki::new(lang)))
+                .collect(),
+        );
+        self
+    }
+
+    /// The edition `article` is in and its title there
+    fn edition<'a>(&'a self, article: &'a str) -> (&'a Wiki, &'a str) {
+        split_edition(article)
+            .and_then(|(lang, title)| Some((self.interwiki.get(lang)?, title)))
+            .unwrap_or((&self.wiki, article))
+    }
+
+    /// Index links are taken from, if any
+    pub(crate) fn dump_index(&self) -> Option<&Path> {
+        self.dump.as_deref().map(DumpIndex::file)
+    }
+
+    /// Use of the memory cache so far, if it's enabled
+    pub(crate) fn cache_stats(&self) -> Option<CacheStats> {
+        self.memory.as_ref().map(|memory| CacheStats {
+            articles: memory.links.lock().unwrap().len(),
+            hits: memory.hits.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Requests sent and bytes received so far, retries and all
+    pub(crate) fn traffic(&self) -> Traffic {
+        Traffic {
+            requests: self.traffic.requests.load(Ordering::Relaxed),
+            bytes: self.traffic.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Runs `request` once a worker is free, returning how long it took
+    /// once sent
+    async fn rate_limited<F: Future>(&self, request: F) -> (F::Output, Duration) {
+        let permit = self
+            .workers
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("worker semaphore is never closed");
+        let sent = Instant::now();
+
+        let res = request.await;
+        let elapsed = sent.elapsed();
+
+        // Rate-limit: keep the worker busy until its wait is over
+        let req_wait = self.req_wait;
+        tokio::spawn(async move {
+            tokio::time::sleep_until((sent + req_wait).into()).await;
+            drop(permit);
+        });
+
+        (res, elapsed)
+    }
+
+    /// Sends `request` once a worker is free. While the server answers 429
+    /// or 5xx it's sent again after a growing wait, and once the retries
+    /// run out the status is returned as an error.
+    async fn send(&self, request: rw::RequestBuilder) -> rw::Result<rw::Response> {
+        Ok(self.send_timed(request).await?.0)
+    }
+
+    /// Like `send`, but also returns how long the attempts took, not
+    /// counting the waits for a worker or before retrying
+    async fn send_timed(
+        &self,
+        request: rw::RequestBuilder,
+    ) -> rw::Result<(rw::Response, Duration)> {
+        let mut attempt = 0;
+        let mut elapsed = Duration::ZERO;
+
+        loop {
+            let attempt_request = request
+                .try_clone()
+                .expect("GET requests have no streaming body");
+            let (res, took) = self.rate_limited(attempt_request.send()).await;
+            elapsed += took;
+            let res = res?;
+            self.traffic.requests.fetch_add(1, Ordering::Relaxed);
+
+            let status = res.status();
+            if status != rw::StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                return Ok((res, elapsed));
+            }
+            if attempt >= self.max_retries {
+                return res.error_for_status().map(|res| (res, elapsed));
+            }
+
+            tokio::time::sleep(retry_wait(attempt, &res, self.max_retry_wait)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Reads the body of `res`, counting its size
+    async fn text(&self, res: rw::Response) -> rw::Result<String> {
+        let body = res.text().await?;
+        self.traffic
+            .bytes
+            .fetch_add(body.len() as u64, Ordering::Relaxed);
+        Ok(body)
+    }
+
+    /// Sends an API `request` and parses the JSON it's answered with. While
+    /// the servers are lagging behind it's sent again once they say to.
+    async fn json<T: DeserializeOwned>(
+        &self,
+        request: rw::RequestBuilder,
+    ) -> Result<T, FetchError> {
+        let request = request.query(&[("maxlag", MAXLAG_SECS)]);
+        let mut attempt = 0;
+
+        loop {
+            let attempt_request = request
+                .try_clone()
+                .expect("GET requests have no streaming body");
+            let res = self.send(attempt_request).await?;
+            let wait = retry_after(&res);
+            let body = self.text(res).await?;
+
+            let lagged = match serde_json::from_str::<ApiErrorResponse>(&body) {
+                Ok(res) if res.error.code == "maxlag" => res.error.info,
+                _ => return Ok(serde_json::from_str(&body)?),
+            };
+            if attempt >= self.max_retries {
+                return Err(FetchError::Lagged(lagged));
+            }
+
+            let wait = wait
+                .unwrap_or_else(|| backoff(attempt))
+                .min(self.max_retry_wait);
+            debug!(
+                "API servers lagging, retrying in {}s: {}",
+                wait.as_secs_f64(),
+                lagged
+            );
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// Fetches the HTML of `article`, along with the URL it was served
+    /// from after following redirects and the time it took, if it wasn't
+    /// cached
+    async fn article(&self, article: &str) -> rw::Result<(rw::Url, String, Option<Duration>)> {
+        let (wiki, title) = self.edition(article);
+        let (url, ext) = if self.mobile {
+            (wiki.mobile_url(title), "mobile.html")
+        } else {
+            (wiki.url(title), "html")
+        };
+
+        // Asked again with its ETag and Last-Modified once over the TTL
+        let mut stale = None;
+        if let Some(cache) = &self.cache {
+            if let Some((meta, body)) = cache.read(article, ext) {
+                if let Ok(url) = rw::Url::parse(&meta.url) {
+                    if cache.is_fresh(&meta) {
+                        return Ok((url, body, None));
+                    }
+                    stale = Some((url, meta, body));
+                }
+            }
+        }
+
+        let mut request = self.client.get(url);
+        if let Some((_, meta, _)) = &stale {
+            request = meta.validators.apply(request);
+        }
+
+        let (res, mut elapsed) = self.send_timed(request).await?;
+        if res.status() == rw::StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some((url, meta, body))) = (&self.cache, stale) {
+                debug!("{} didn't change since it was cached", article);
+                let _ = cache.touch(article, ext, meta);
+                return Ok((url, body, Some(elapsed)));
+            }
+        }
+
+        let url = res.url().clone();
+        let success = res.status().is_success();
+        let validators = Validators::from_headers(res.headers());
+        let read = Instant::now();
+        let body = self.text(res).await?;
+        elapsed += read.elapsed();
+
+        if let Some(cache) = &self.cache {
+            if success {
+                // A page that can't be cached is simply fetched again
+                let _ = cache.put(article, ext, url.as_str(), &body, validators);
+            }
+        }
+
+        Ok((url, body, Some(elapsed)))
+    }
+
+    /// Kind of the cached responses of queries listing links, told apart by
+    /// the namespaces they're to when not only to articles
+    fn namespace_kind(&self, kind: &str) -> String {
+        match self.wiki.followed_namespaces() {
+            [MAIN_NAMESPACE] => kind.to_string(),
+            namespaces => {
+                let ids: Vec<_> = namespaces.iter().map(i32::to_string).collect();
+                format!("{}ns{}.", kind, ids.join("-"))
+            }
+        }
+    }
+
+    /// Runs `query` for `article`, or reuses its result from the cache.
+    /// `kind` tells apart the different queries made for an article.
+    async fn cached_query(
+        &self,
+        article: &str,
+        kind: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Vec<ApiPage>, FetchError> {
+        let ext = format!("{}json", kind);
+
+        if let Some(cache) = &self.cache {
+            if let Some((_, body)) = cache.get(article, &ext) {
+                if let Ok(pages) = serde_json::from_str(&body) {
+                    return Ok(pages);
+                }
+            }
+        }
+
+        let pages = self.query(params).await?;
+
+        if let Some(cache) = &self.cache {
+            if let Ok(body) = serde_json::to_string(&pages) {
+                let _ = cache.put(
+                    article,
+                    &ext,
+                    &self.wiki.api_url,
+                    &body,
+                    Validators::default(),
+                );
+            }
+        }
+
+        Ok(pages)
+    }
+
+    /// Runs an API query, following continuation tokens until every batch
+    /// of the result has been fetched
+    async fn query(&self, params: &[(&str, &str)]) -> Result<Vec<ApiPage>, FetchError> {
+        Ok(self.query_redirects(params).await?.0)
+    }
+
+    /// Like `query`, but also returns the redirects followed to the pages
+    async fn query_redirects(
+        &self,
+        params: &[(&str, &str)],
+    ) -> Result<(Vec<ApiPage>, Vec<ApiRedirect>), FetchError> {
+        let mut pages = Vec::new();
+        let mut redirects = Vec::new();
+        let mut cont = HashMap::new();
+
+        loop {
+            let request = self
+                .client
+                .get(&self.wiki.api_url)
+                .query(&[
+                    ("action", "query"),
+                    ("format", "json"),
+                    ("formatversion", "2"),
+                ])
+                .query(params)
+                .query(&cont);
+
+            let res: ApiResponse = self.json(request).await?;
+
+            if let Some(query) = res.query {
+                pages.extend(query.pages);
+                redirects.extend(query.redirects);
+            }
+
+            match res.cont {
+                Some(next) => {
+                    cont = next
+                        .into_iter()
+                        .map(|(param, value)| (param, value.to_string()))
+                        .collect();
+                }
+                None => return Ok((pages, redirects)),
+            }
+        }
+    }
+
+    /// Fetches the names of all the articles linked from `article`, or
+    /// takes them from the memory cache
+    pub(crate) async fn links(self, article: String) -> Result<Links, FetchError> {
+        let include_redirects = self.include_redirects;
+        let shuffle = self.seed.map(|seed| (seed, article.clone()));
+        let mut links = match self.memory.clone() {
+            Some(memory) => {
+                let cached = memory.links.lock().unwrap().get(&article).cloned();
+                match cached {
+                    Some(links) => {
+                        memory.hits.fetch_add(1, Ordering::Relaxed);
+                        Links {
+                            fetch_time: None,
+                            ..links
+                        }
+                    }
+                    None => {
+                        let links = self.fetch_links(article.clone()).await?;
+                        memory.links.lock().unwrap().insert(article, links.clone());
+                        links
+                    }
+                }
+            }
+            None => self.fetch_links(article).await?,
+        };
+
+        if let Some((seed, article)) = shuffle {
+            links.shuffle(seed, &article);
+        }
+
+        match links.canonical {
+            // The target is one link away, first in the page
+            Some(target) if include_redirects => Ok(Links {
+                canonical: None,
+                disambiguation: false,
+                titles: vec![target],
+                offsets: if links.offsets.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![0]
+                },
+                section_depths: if links.section_depths.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![0]
+                },
+                anchors: Vec::new(),
+                size: links.size,
+                see_also: HashSet::new(),
+                description: None,
+                categories: None,
+                length: None,
+                fetch_time: links.fetch_time,
+            }),
+            _ => Ok(links),
+        }
+    }
+
+    async fn fetch_links(self, article: String) -> Result<Links, FetchError> {
+        if let Some(dump) = &self.dump {
+            // Links to articles missing from the dump lead nowhere, like
+            // red links do
+            let Some(page) = dump.page(&article)? else {
+                return Ok(Links {
+                    canonical: None,
+                    disambiguation: false,
+                    titles: Vec::new(),
+                    offsets: Vec::new(),
+                    section_depths: Vec::new(),
+                    anchors: Vec::new(),
+                    size: None,
+                    see_also: HashSet::new(),
+                    description: None,
+                    categories: None,
+                    length: None,
+                    fetch_time: None,
+                });
+            };
+            return Ok(Links {
+                canonical: Some(page.title).filter(|title| *title != article),
+                disambiguation: page.disambiguation,
+                titles: page
+                    .links
+                    .into_iter()
+                    .filter(|name| !self.wiki.is_main_page(name))
+                    .collect(),
+                offsets: Vec::new(),
+                section_depths: Vec::new(),
+                anchors: Vec::new(),
+                size: None,
+                see_also: HashSet::new(),
+                description: None,
+                categories: None,
+                length: None,
+                fetch_time: None,
+            });
+        }
+
+        if !self.api {
+            let (links, soft_redirect) = self.clone().html_links(article.clone()).await?;
+            // Followed once, as MediaWiki follows redirects
+            let Some(target) = soft_redirect.filter(|target| *target != article) else {
+                return Ok(links);
+            };
+            debug!("{} is a soft redirect to {}", article, target);
+
+            let (target_links, _) = self.html_links(target.clone()).await?;
+            let fetch_time = match (links.fetch_time, target_links.fetch_time) {
+                (Some(redirect), Some(target)) => Some(redirect + target),
+                (redirect, target) => redirect.or(target),
+            };
+            return Ok(Links {
+                canonical: Some(target_links.canonical.clone().unwrap_or(target))
+                    .filter(|canonical| *canonical != article),
+                fetch_time,
+                ..target_links
+            });
+        }
+
+        let namespaces = self.wiki.namespace_param();
+        let pages = self
+            .cached_query(
+                &article,
+                &self.namespace_kind(""),
+                &[
+                    ("prop", "links|pageprops|info|categories"),
+                    ("titles", &article),
+                    ("redirects", "1"),
+                    ("ppprop", "disambiguation"),
+                    ("plnamespace", &namespaces),
+                    ("pllimit", "max"),
+                    ("clshow", "!hidden"),
+                    ("cllimit", "max"),
+                ],
+            )
+            .await?;
+
+        // Pages are split across the batches of a continued query
+        let length = pages.iter().find_map(|page| page.length);
+        let categories = pages
+            .iter()
+            .flat_map(|page| &page.categories)
+            .map(|category| category_name(&category.title))
+            .collect();
+        Ok(Links {
+            canonical: api_canonical_title(&pages, &article),
+            disambiguation: pages
+                .iter()
+                .any(|page| page.pageprops.contains_key("disambiguation")),
+            titles: pages
+                .into_iter()
+                .flat_map(|page| page.links)
+                .map(|link| normalize_title(&link.title))
+                .filter(|name| !self.wiki.is_main_page(name))
+                .collect(),
+            offsets: Vec::new(),
+            section_depths: Vec::new(),
+            anchors: Vec::new(),
+            size: None,
+            see_also: HashSet::new(),
+            description: None,
+            categories: Some(categories),
+            length,
+            fetch_time: None,
+        })
+    }
+
+    /// Links of `article` in its HTML, and the article it's a soft redirect
+    /// to if it is one
+    async fn html_links(self, article: String) -> Result<(Links, Option<String>), FetchError> {
+        let (url, body, fetch_time) = self.article(&article).await?;
+        let Some(pool) = self.parse_pool.clone() else {
+            let (links, soft_redirect) = self.parse_links(&article, &url, &body);
+            return Ok((
+                Links {
+                    fetch_time,
+                    ..links
+                },
+                soft_redirect,
+            ));
+        };
+
+        // Answered on the parsing thread, the search goes on meanwhile
+        let (tx, rx) = oneshot::channel();
+        pool.spawn(move || {
+            let _ = tx.send(self.parse_links(&article, &url, &body));
+        });
+        let (links, soft_redirect) = rx.await.expect("parsing thread panicked");
+        Ok((
+            Links {
+                fetch_time,
+                ..links
+            },
+            soft_redirect,
+        ))
+    }
+
+    /// Links of `article` in its HTML `body`, served from `url`, and the
+    /// target of the soft redirect it is, if any
+    fn parse_links(&self, article: &str, url: &rw::Url, body: &str) -> (Links, Option<String>) {
+        let document = sc::Html::parse_document(body);
+        // Titles in another edition than the one searched are told apart
+        let (wiki, _) = self.edition(article);
+        let retitle = |title: String| {
+            if wiki.lang() == self.wiki.lang() {
+                title
+            } else {
+                in_edition(wiki.lang(), &title)
+            }
+        };
+
+        let canonical = canonical_title(&document, wiki)
+            .or_else(|| {
+                // The REST API redirects to the article a title redirects to
+                let path = url.path();
+                path.strip_prefix(wiki.article_path())
+                    .or_else(|| path.strip_prefix(MOBILE_HTML_PATH))
+                    .map(|title| normalize_title(&decode_title(title)))
+            })
+            .map(retitle)
+            .filter(|title| *title != article);
+
+        let links = document_links(
+            &document,
+            body,
+            wiki,
+            &self.skip_sections,
+            self.prioritize_infobox,
+            self.intro_only,
+        );
+        let mut titles = Vec::with_capacity(links.len());
+        let mut offsets = Vec::with_capacity(links.len());
+        let mut section_depths = Vec::with_capacity(links.len());
+        let mut anchors = Vec::with_capacity(links.len());
+        let mut see_also = HashSet::new();
+        for link in links {
+            let title = retitle(link.title);
+            if link.see_also {
+                see_also.insert(title.clone());
+            }
+            titles.push(title);
+            offsets.push(link.offset);
+            section_depths.push(link.section_depth);
+            anchors.push(link.anchor);
+        }
+
+        // Language links are in the sidebar, after the article text
+        if !self.interwiki.is_empty() {
+            let editions: Vec<&Wiki> = self
+                .interwiki
+                .values()
+                .chain([&*self.wiki])
+                .filter(|edition| edition.lang() != wiki.lang())
+                .collect();
+            for (lang, title, anchor) in interwiki_links(&document, &editions) {
+                titles.push(if lang == self.wiki.lang() {
+                    title
+                } else {
+                    in_edition(&lang, &title)
+                });
+                offsets.push(body.len());
+                // Outside the article text, like its deepest sections
+                section_depths.push(MAX_SECTION_DEPTH);
+                anchors.push(anchor);
+            }
+        }
+
+        let links = Links {
+            canonical,
+            disambiguation: document.select(&DISAMBIG_SELECTOR).next().is_some(),
+            titles,
+            offsets,
+            section_depths,
+            anchors,
+            size: Some(body.len()),
+            see_also,
+            description: short_description(&document),
+            categories: document_categories(&document, wiki),
+            length: None,
+            // Set by the caller, which timed the request
+            fetch_time: None,
+        };
+        (links, soft_redirect_target(&document, wiki).map(retitle))
+    }
+
+    /// Returns those of `titles` that don't exist
+    pub(crate) async fn missing(&self, titles: &[String]) -> Result<Vec<String>, FetchError> {
+        let statuses = self.validate_titles(titles).await?;
+        Ok(titles
+            .iter()
+            .filter(|title| statuses.get(*title) == Some(&TitleStatus::Missing))
+            .cloned()
+            .collect())
+    }
+
+    /// Looks the names of the edition's namespaces up, unless they're known
+    /// already, so that links to their titles are told apart from articles
+    /// with a colon in their title. Dumps only have articles.
+    pub(crate) async fn load_namespaces(&self) -> Result<(), FetchError> {
+        if self.dump.is_some() || self.wiki.knows_namespaces() {
+            return Ok(());
+        }
+
+        let request = self.client.get(&self.wiki.api_url).query(&[
+            ("action", "query"),
+            ("format", "json"),
+            ("formatversion", "2"),
+            ("meta", "siteinfo"),
+            ("siprop", "namespaces|namespacealiases"),
+        ]);
+        let res: SiteInfoResponse = self.json(request).await?;
+
+        let SiteInfo {
+            namespaces,
+            namespacealiases,
+        } = res.query;
+        let names = namespaces
+            .into_values()
+            .flat_map(|namespace| {
+                [Some(namespace.name), namespace.canonical]
+                    .into_iter()
+                    .flatten()
+                    .filter(|name| !name.is_empty())
+                    .map(move |name| (name, namespace.id))
+            })
+            .chain(
+                namespacealiases
+                    .into_iter()
+                    .map(|alias| (alias.alias, alias.id)),
+            );
+        self.wiki.set_namespaces(names);
+        Ok(())
+    }
+
+    /// Looks up whether each of `titles` is an article, a redirect or
+    /// neither, asking about `TITLES_PER_QUERY` titles per request
+    pub(crate) async fn validate_titles(
+        &self,
+        titles: &[String],
+    ) -> Result<HashMap<String, TitleStatus>, FetchError> {
+        let mut statuses = HashMap::new();
+
+        if let Some(dump) = &self.dump {
+            for title in titles {
+                let status = match dump.resolve(title)? {
+                    None => TitleStatus::Missing,
+                    Some(target) if target == *title => TitleStatus::Valid,
+                    Some(target) => TitleStatus::Redirect(target),
+                };
+                statuses.insert(title.clone(), status);
+            }
+            return Ok(statuses);
+        }
+
+        for chunk in titles.chunks(TITLES_PER_QUERY) {
+            let (pages, redirects) = self
+                .query_redirects(&[("titles", &chunk.join("|")), ("redirects", "1")])
+                .await?;
+            let missing: HashSet<_> = pages
+                .iter()
+                .filter(|page| page.missing || page.invalid)
+                .map(|page| normalize_title(&page.title))
+                .collect();
+            let redirects: HashMap<_, _> = redirects
+                .iter()
+                .map(|redirect| {
+                    (
+                        normalize_title(&redirect.from),
+                        normalize_title(&redirect.to),
+                    )
+                })
+                .collect();
+
+            for requested in chunk {
+                let title = normalize_title(requested);
+                // Double redirects come one hop at a time, and may loop
+                let mut target = &title;
+                for _ in 0..redirects.len() {
+                    match redirects.get(target) {
+                        Some(next) => target = next,
+                        None => break,
+                    }
+                }
+                let status = if missing.contains(target) {
+                    TitleStatus::Missing
+                } else if *target == title {
+                    TitleStatus::Valid
+                } else {
+                    TitleStatus::Redirect(target.clone())
+                };
+                statuses.insert(requested.clone(), status);
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    /// Title of the article `title` redirects to, or `title` itself
+    pub(crate) async fn resolve_redirect(&self, title: &str) -> Result<String, FetchError> {
+        Ok(self
+            .resolve(title)
+            .await?
+            .unwrap_or_else(|| title.to_string()))
+    }
+
+    /// Like `resolve_redirect`, but `None` if there's no article `title`
+    pub(crate) async fn resolve(&self, title: &str) -> Result<Option<String>, FetchError> {
+        if let Some(dump) = &self.dump {
+            return dump.resolve(title);
+        }
+
+        let pages = self.query(&[("titles", title), ("redirects", "1")]).await?;
+
+        if pages.iter().any(|page| page.missing || page.invalid) {
+            return Ok(None);
+        }
+        Ok(Some(
+            api_canonical_title(&pages, title).unwrap_or_else(|| title.to_string()),
+        ))
+    }
+
+    /// Title of the random article Special:Random redirects to, if the page
+    /// it ends up on names one, or an article of the dump. Never cached.
+    pub(crate) async fn random(&self) -> Result<Option<String>, FetchError> {
+        if let Some(dump) = &self.dump {
+            return dump.random();
+        }
+
+        let res = self
+            .send(self.client.get(self.wiki.url("Special:Random")))
+            .await?
+            .error_for_status()?;
+        let url = res.url().clone();
+        let body = self.text(res).await?;
+
+        let title = canonical_title(&sc::Html::parse_document(&body), &self.wiki).or_else(|| {
+            url.path()
+                .strip_prefix(self.wiki.article_path())
+                .map(|title| normalize_title(&decode_title(title)))
+        });
+        Ok(title.filter(|title| self.wiki.namespace(title) == Some(MAIN_NAMESPACE)))
+    }
+
+    /// Whether `from` links to `to`, directly or through a redirect
+    pub(crate) async fn links_to(&self, from: &str, to: &str) -> Result<bool, FetchError> {
+        let links = self.clone().links(from.to_string()).await?;
+        if links.titles.iter().any(|title| title == to) {
+            return Ok(true);
+        }
+
+        if let Some(dump) = &self.dump {
+            let redirects = dump.redirects_to(to)?;
+            return Ok(links.titles.iter().any(|title| redirects.contains(title)));
+        }
+
+        let pages = self
+            .query(&[
+                ("prop", "redirects"),
+                ("titles", to),
+                ("rdprop", "title"),
+                ("rdnamespace", "0"),
+                ("rdlimit", "max"),
+            ])
+            .await?;
+        let redirects: HashSet<_> = pages
+            .into_iter()
+            .flat_map(|page| page.redirects)
+            .map(|link| normalize_title(&link.title))
+            .collect();
+
+        Ok(links.titles.iter().any(|title| redirects.contains(title)))
+    }
+
+    /// Titles of existing articles close to `title`
+    pub(crate) async fn suggestions(&self, title: &str) -> Result<Vec<String>, FetchError> {
+        if let Some(dump) = &self.dump {
+            return dump.suggestions(title, SUGGESTIONS);
+        }
+
+        let limit = SUGGESTIONS.to_string();
+        let request = self.client.get(&self.wiki.api_url).query(&[
+            ("action", "opensearch"),
+            ("format", "json"),
+            ("namespace", "0"),
+            ("limit", &limit),
+            ("search", title),
+        ]);
+
+        // [search, titles, descriptions, urls]
+        let (_, titles, _, _): (String, Vec<String>, Vec<String>, Vec<String>) =
+            self.json(request).await?;
+
+        Ok(titles.iter().map(|title| normalize_title(title)).collect())
+    }
+
+    /// Views of `article` by people (not bots) over the last
+    /// `PAGEVIEWS_DAYS` days, 0 if none are on record
+    pub(crate) async fn pageviews(self, article: String) -> Result<u64, FetchError> {
+        if let Some(cache) = &self.cache {
+            if let Some((_, body)) = cache.get(&article, "views") {
+                if let Ok(views) = body.parse() {
+                    return Ok(views);
+                }
+            }
+        }
+
+        // Page views are counted in UTC days
+        let today = jiff::Timestamp::now()
+            .to_zoned(jiff::tz::TimeZone::UTC)
+            .date();
+        let first = today.saturating_sub(PAGEVIEWS_DAYS.days());
+        let url = format!(
+            "{}/{}/all-access/user/{}/daily/{}/{}",
+            PAGEVIEWS_URL,
+            self.wiki.host(),
+            // Unlike in article URLs, slashes in the title must be escaped
+            percent_encoding::utf8_percent_encode(&article, percent_encoding::NON_ALPHANUMERIC),
+            first.strftime("%Y%m%d"),
+            today.strftime("%Y%m%d")
+        );
+
+        let res = self.send(self.client.get(&url)).await?;
+        let views = if res.status() == rw::StatusCode::NOT_FOUND {
+            0
+        } else {
+            let body = self.text(res.error_for_status()?).await?;
+            serde_json::from_str::<PageviewsResponse>(&body)?
+                .items
+                .iter()
+                .map(|item| item.views)
+                .sum()
+        };
+
+        if let Some(cache) = &self.cache {
+            let _ = cache.put(
+                &article,
+                "views",
+                &url,
+                &views.to_string(),
+                Validators::default(),
+            );
+        }
+
+        Ok(views)
+    }
+
+    /// Looks up the thumbnail of the lead image of `article`, `THUMBNAIL_PX`
+    /// wide, downloading it too if `download`. `None` if it has no image,
+    /// and always with a dump.
+    pub(crate) async fn thumbnail(
+        self,
+        article: String,
+        download: bool,
+    ) -> Result<Option<Thumbnail>, FetchError> {
+        if self.dump.is_some() {
+            return Ok(None);
+        }
+
+        let pages = self
+            .query(&[
+                ("prop", "pageimages"),
+                ("titles", &article),
+                ("piprop", "thumbnail"),
+                ("pithumbsize", THUMBNAIL_PX),
+                ("redirects", "1"),
+            ])
+            .await?;
+        let Some((article, url)) = pages.into_iter().find_map(|page| {
+            let url = page.thumbnail?.source;
+            Some((normalize_title(&page.title), url))
+        }) else {
+            return Ok(None);
+        };
+
+        let image = if download {
+            let res = self.send(self.client.get(&url)).await?;
+            let image = res.error_for_status()?.bytes().await?;
+            self.traffic
+                .bytes
+                .fetch_add(image.len() as u64, Ordering::Relaxed);
+            Some(image.to_vec())
+        } else {
+            None
+        };
+
+        Ok(Some(Thumbnail {
+            article,
+            url,
+            image,
+        }))
+    }
+
+    /// Fetches the categories of `article`
+    pub(crate) async fn categories(self, article: String) -> Result<HashSet<String>, FetchError> {
+        let pages = self
+            .cached_query(
+                &article,
+                "categories.",
+                &[
+                    ("prop", "categories"),
+                    ("titles", &article),
+                    ("redirects", "1"),
+                    ("cllimit", "max"),
+                ],
+            )
+            .await?;
+
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.categories)
+            .map(|category| category.title)
+            .collect())
+    }
+
+    /// Fetches the categories of each of `titles` that exists, by the names
+    /// `category_name` gives them, asking about `TITLES_PER_QUERY` titles
+    /// at a time. Redirects are followed, but the categories are given
+    /// under the titles asked about.
+    pub(crate) async fn categories_of(
+        &self,
+        titles: &[String],
+    ) -> Result<HashMap<String, HashSet<String>>, FetchError> {
+        let mut categories = HashMap::new();
+
+        for chunk in titles.chunks(TITLES_PER_QUERY) {
+            let (pages, redirects) = self
+                .query_redirects(&[
+                    ("prop", "categories"),
+                    ("titles", &chunk.join("|")),
+                    ("redirects", "1"),
+                    ("cllimit", "max"),
+                ])
+                .await?;
+
+            // Continued queries can return the same page again with more
+            // categories
+            let mut of_page: HashMap<String, HashSet<String>> = HashMap::new();
+            for page in pages {
+                if page.missing || page.invalid {
+                    continue;
+                }
+                of_page
+                    .entry(normalize_title(&page.title))
+                    .or_default()
+                    .extend(page.categories.iter().map(|c| category_name(&c.title)));
+            }
+            let targets: HashMap<_, _> = redirects
+                .into_iter()
+                .map(|redirect| {
+                    (
+                        normalize_title(&redirect.from),
+                        normalize_title(&redirect.to),
+                    )
+                })
+                .collect();
+
+            for title in chunk {
+                let page = targets.get(title).unwrap_or(title);
+                if let Some(of) = of_page.get(page) {
+                    categories.insert(title.clone(), of.clone());
+                }
+            }
+        }
+
+        Ok(categories)
+    }
+
+    /// Names of the subcategories of the category `name`, see
+    /// `category_name`
+    pub(crate) async fn subcategories(&self, name: &str) -> Result<Vec<String>, FetchError> {
+        // The English namespace name works on every edition
+        let title = format!("Category:{}", name);
+        let pages = self
+            .query(&[
+                ("generator", "categorymembers"),
+                ("gcmtitle", &title),
+                ("gcmtype", "subcat"),
+                ("gcmlimit", "max"),
+            ])
+            .await?;
+
+        Ok(pages
+            .iter()
+            .map(|page| category_name(&page.title))
+            .collect())
+    }
+
+    /// Fetches all the articles linked from `article` along with their
+    /// categories, in a single query
+    pub(crate) async fn linked(self, article: String) -> Result<Vec<Linked>, FetchError> {
+        let namespaces = self.wiki.namespace_param();
+        let pages = self
+            .cached_query(
+                &article,
+                &self.namespace_kind("linked."),
+                &[
+                    ("generator", "links"),
+                    ("titles", &article),
+                    ("redirects", "1"),
+                    ("gplnamespace", &namespaces),
+                    ("gpllimit", "max"),
+                    ("prop", "categories|pageprops"),
+                    ("ppprop", "disambiguation"),
+                    ("cllimit", "max"),
+                ],
+            )
+            .await?;
+
+        // Continued queries can return the same page again with more
+        // categories
+        let mut linked: Vec<Linked> = Vec::new();
+        let mut index = HashMap::new();
+
+        for page in pages {
+            let title = normalize_title(&page.title);
+            if page.missing || self.wiki.is_main_page(&title) {
+                continue;
+            }
+
+            let idx = *index.entry(title.clone()).or_insert_with(|| {
+                linked.push(Linked {
+                    title,
+                    categories: HashSet::new(),
+                    disambiguation: false,
+                });
+                linked.len() - 1
+            });
+
+            let entry = &mut linked[idx];
+            entry.disambiguation |= page.pageprops.contains_key("disambiguation");
+            entry
+                .categories
+                .extend(page.categories.into_iter().map(|category| category.title));
+        }
+
+        Ok(linked)
+    }
+
+    /// Fetches the names of all the articles linking to `article`
+    pub(crate) async fn backlinks(self, article: String) -> Result<Links, FetchError> {
+        let namespaces = self.wiki.namespace_param();
+        let pages = self
+            .cached_query(
+                &article,
+                &self.namespace_kind("backlinks."),
+                &[
+                    ("prop", "linkshere"),
+                    ("titles", &article),
+                    ("redirects", "1"),
+                    ("lhprop", "title"),
+                    ("lhnamespace", &namespaces),
+                    ("lhshow", "!redirect"),
+                    ("lhlimit", "max"),
+                ],
+            )
+            .await?;
+
+        Ok(Links {
+            canonical: api_canonical_title(&pages, &article),
+            disambiguation: false,
+            titles: pages
+                .into_iter()
+                .flat_map(|page| page.linkshere)
+                .map(|link| normalize_title(&link.title))
+                .collect(),
+            offsets: Vec::new(),
+            section_depths: Vec::new(),
+            anchors: Vec::new(),
+            size: None,
+            see_also: HashSet::new(),
+            description: None,
+            categories: None,
+            length: None,
+            fetch_time: None,
+        })
+    }
+}
+
+/// How long to wait before retrying a request after `attempt` retries: the
+/// server's Retry-After if it sent one, or an exponential backoff with
+/// jitter, up to `max`
+fn retry_wait(attempt: u32, res: &rw::Response, max: Duration) -> Duration {
+    let wait = match retry_after(res) {
+        Some(wait) if res.status() == rw::StatusCode::TOO_MANY_REQUESTS => wait,
+        _ => backoff(attempt),
+    };
+    wait.min(max)
+}
+
+/// The wait asked for by the Retry-After header of `res`, in seconds
+fn retry_after(res: &rw::Response) -> Option<Duration> {
+    res.headers()
+        .get(rw::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Exponential backoff with jitter after `attempt` retries
+fn backoff(attempt: u32) -> Duration {
+    let backoff = RETRY_BASE_SECS * 2f64.powi(attempt as i32);
+    let jitter = fastrand::f64() * RETRY_BASE_SECS;
+    Duration::from_secs_f64((backoff + jitter).min(DEFAULT_MAX_RETRY_WAIT_SECS))
+}
+
+/// 64-bit FNV-1a hash of `bytes`, which unlike the standard hasher is the
+/// same from one build to the next
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Title of the article a page was redirected to, if it differs from
+/// `article`
+fn api_canonical_title(pages: &[ApiPage], article: &str) -> Option<String> {
+    pages
+        .first()
+        .map(|page| normalize_title(&page.title))
+        .filter(|title| !title.is_empty() && title != article)
+}
@@ -0,0 +1,156 @@
+//! The links followed by a search, as a GraphViz DOT graph or Cytoscape.js
+//! elements
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    io::{self, Write},
+};
+
+use serde::Serialize;
+
+/// Links followed during a search, written out with --export-graph or
+/// --format cytoscape
+#[derive(Default)]
+pub struct Graph {
+    starts: BTreeSet<String>,
+    nodes: BTreeSet<String>,
+    edges: BTreeSet<(String, String)>,
+    path_nodes: HashSet<String>,
+    path_edges: HashSet<(String, String)>,
+}
+
+impl Graph {
+    pub(crate) fn add_start(&mut self, article: &str) {
+        self.starts.insert(article.to_string());
+        self.nodes.insert(article.to_string());
+    }
+
+    pub(crate) fn add_edge(&mut self, from: &str, to: &str) {
+        self.nodes.insert(from.to_string());
+        self.nodes.insert(to.to_string());
+        self.edges.insert((from.to_string(), to.to_string()));
+    }
+
+    pub(crate) fn add_path(&mut self, path: &[String]) {
+        self.path_nodes.extend(path.iter().cloned());
+        self.path_edges
+            .extend(path.windows(2).map(|hop| (hop[0].clone(), hop[1].clone())));
+    }
+
+    pub fn write_dot(&self, mut w: impl Write) -> io::Result<()> {
+        fn quote(article: &str) -> String {
+            format!("\"{}\"", article.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+
+        writeln!(w, "digraph wiki_path {{")?;
+
+        for node in &self.nodes {
+            if self.path_nodes.contains(node) {
+                writeln!(w, "    {} [color=red];", quote(node))?;
+            } else {
+                writeln!(w, "    {};", quote(node))?;
+            }
+        }
+
+        for edge @ (from, to) in &self.edges {
+            if self.path_edges.contains(edge) {
+                writeln!(w, "    {} -> {} [color=red];", quote(from), quote(to))?;
+            } else {
+                writeln!(w, "    {} -> {};", quote(from), quote(to))?;
+            }
+        }
+
+        writeln!(w, "}}")
+    }
+
+    /// Writes the graph as the JSON elements of a Cytoscape.js graph. The
+    /// depth of each article is the fewest links from a start article to
+    /// it in the graph, which is when a breadth-first search found it, and
+    /// null for those only reached backwards by a bidirectional search.
+    pub fn write_cytoscape(&self, mut w: impl Write) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct Elements<'a> {
+            elements: Groups<'a>,
+        }
+
+        #[derive(Serialize)]
+        struct Groups<'a> {
+            nodes: Vec<Element<NodeData<'a>>>,
+            edges: Vec<Element<EdgeData<'a>>>,
+        }
+
+        #[derive(Serialize)]
+        struct Element<T> {
+            data: T,
+        }
+
+        #[derive(Serialize)]
+        struct NodeData<'a> {
+            id: &'a str,
+            depth: Option<u32>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct EdgeData<'a> {
+            source: &'a str,
+            target: &'a str,
+            on_path: bool,
+        }
+
+        let depths = self.depths();
+        let elements = Elements {
+            elements: Groups {
+                nodes: self
+                    .nodes
+                    .iter()
+                    .map(|node| Element {
+                        data: NodeData {
+                            id: node,
+                            depth: depths.get(node.as_str()).copied(),
+                        },
+                    })
+                    .collect(),
+                edges: self
+                    .edges
+                    .iter()
+                    .map(|edge @ (from, to)| Element {
+                        data: EdgeData {
+                            source: from,
+                            target: to,
+                            on_path: self.path_edges.contains(edge),
+                        },
+                    })
+                    .collect(),
+            },
+        };
+
+        serde_json::to_writer(&mut w, &elements)?;
+        writeln!(w)
+    }
+
+    /// Fewest links from a start article to each article reached from one
+    fn depths(&self) -> HashMap<&str, u32> {
+        let mut links: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (from, to) in &self.edges {
+            links.entry(from).or_default().push(to);
+        }
+
+        let mut depths: HashMap<&str, u32> = self
+            .starts
+            .iter()
+            .map(|start| (start.as_str(), 0))
+            .collect();
+        let mut queue: VecDeque<&str> = self.starts.iter().map(String::as_str).collect();
+        while let Some(article) = queue.pop_front() {
+            let depth = depths[article] + 1;
+            for &link in links.get(article).into_iter().flatten() {
+                if !depths.contains_key(link) {
+                    depths.insert(link, depth);
+                    queue.push_back(link);
+                }
+            }
+        }
+        depths
+    }
+}
@@ -0,0 +1,1374 @@
+//! Finds paths between Wikipedia articles by following the links from one
+//! to the next.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), wiki_path::SearchError> {
+//! let mut searcher = wiki_path::BfsSearcher::builder(["Rust"], ["Iron"])
+//!     .max_depth(4)
+//!     .build()?;
+//!
+//! for path in searcher.search().await? {
+//!     println!("{:?}", path.articles);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod db;
+mod dump;
+mod fetch;
+mod graph;
+mod search;
+mod spill;
+mod stats;
+mod tui;
+mod wiki;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    future::Future,
+    io::{self, BufReader, BufWriter, Write},
+    mem,
+    ops::ControlFlow,
+    path::{Path as FilePath, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use regex::RegexSet;
+use reqwest as rw;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, warn};
+
+use crate::{
+    db::SearchDb,
+    dump::DumpIndex,
+    fetch::{Cache, Fetcher, Links},
+    search::{Forbidden, Search},
+    stats::Stats,
+};
+
+pub use crate::{
+    dump::{index_dump, DumpError},
+    fetch::{CacheStats, FetchError, TitleStatus, Traffic},
+    graph::Graph,
+    search::{Algorithm, BfsState, WeightFn},
+    wiki::{
+        decode_title, extract_links, extract_links_streaming, normalize_display, normalize_title,
+        parse_namespace, Language, Wiki, LANGUAGES, MAIN_NAMESPACE,
+    },
+};
+
+pub const DEFAULT_MAX_DEPTH: u32 = 25;
+
+pub const DEFAULT_LANG: &str = "en";
+
+/// Identifies the tool to Wikipedia, as its API etiquette asks
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "wiki-path/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/TommasoTricker/wiki-path; contact@example.com)"
+);
+
+pub const DEFAULT_TIMEOUT_SECS: f64 = 30.0;
+
+/// Version of the format of `PathFile`, raised when older versions can't
+/// read it anymore
+pub const PATH_FILE_VERSION: u32 = 1;
+
+/// Sections whose links are skipped unless told otherwise, as they lead
+/// away from the subject of the article
+pub const DEFAULT_SKIP_SECTIONS: &[&str] = &[
+    "See also",
+    "Notes",
+    "References",
+    "Further reading",
+    "External links",
+];
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Longest wait before retrying a request, whatever the server asks for
+pub const DEFAULT_MAX_RETRY_WAIT_SECS: f64 = 60.0;
+
+const REQ_WAIT_SECS: f32 = 0.5;
+
+/// Called with each path as soon as it's found
+type OnPath = dyn FnMut(&Path) + Send;
+
+/// Called with each article and its depth as its links are fetched
+type OnExpand = dyn FnMut(&str, u32) + Send;
+
+/// Called with everything that happens during a search, see
+/// `BfsSearcherBuilder::on_event`
+type OnEvent = dyn FnMut(SearchEvent<'_>) -> ControlFlow<()> + Send;
+
+/// Something that happened during a search
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum SearchEvent<'a> {
+    /// An article was queued for the first time, `depth` links away from
+    /// the start. Not reported by depth-first searches, which don't queue
+    /// articles, nor by the backward search of `bidirectional`.
+    Discovered { article: &'a str, depth: u32 },
+    /// The links of an article are being fetched, or have been. `parent`
+    /// is the article it was first reached from, `None` for the start
+    /// articles, and `fetch_time` how long its links took to fetch, `None`
+    /// if that wasn't timed, as with the API.
+    Expanded {
+        article: &'a str,
+        depth: u32,
+        parent: Option<&'a str>,
+        fetch_time: Option<Duration>,
+    },
+    /// Every article `depth` links away from the start has been expanded,
+    /// `articles` having been found so far. Only reported by `Algorithm::Bfs`,
+    /// neither `bidirectional` nor `weighted`.
+    LevelCompleted { depth: u32, articles: usize },
+    /// A path was found
+    PathFound(&'a Path),
+    /// An article couldn't be fetched, or a checkpoint saved, and the
+    /// search went on without it
+    Error(&'a str),
+}
+
+/// Limit that stopped a search before it was over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// `BfsSearcherBuilder::max_articles` articles were queued
+    Articles,
+    /// The search ran for longer than `BfsSearcherBuilder::time_limit`
+    Time,
+    /// The callback of `BfsSearcherBuilder::on_event` asked to stop
+    Stopped,
+}
+
+/// A path found by a search
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Path {
+    /// Titles of the articles from the start to the end, each linking to
+    /// the next
+    pub articles: Vec<String>,
+    /// Time from the start of the search until the path was found
+    pub elapsed: Duration,
+    /// Requests made by the searcher until the path was found
+    pub traffic: Traffic,
+    /// Articles in each depth of a breadth-first search so far (going
+    /// forward when bidirectional), empty with the other algorithms
+    pub frontier_sizes: Vec<usize>,
+    /// Sum of the weights of the links, with `Algorithm::Dijkstra`
+    #[serde(default)]
+    pub weight: Option<f64>,
+}
+
+/// What a search learned about an article it expanded, `None` for what
+/// wasn't fetched
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Article {
+    pub title: String,
+    /// Bytes of the article HTML, or of its wikitext with `api`
+    pub byte_length: Option<usize>,
+    /// Categories the article is in, but the hidden ones, named as in
+    /// `BfsSearcherBuilder::require_categories`
+    pub categories: Option<Vec<String>>,
+    /// How long the article HTML took to fetch, if it wasn't cached
+    pub fetch_time_ms: Option<u64>,
+    /// Unix time in seconds when the article was expanded
+    pub last_updated: Option<u64>,
+}
+
+impl Article {
+    pub(crate) fn new(title: &str, links: &Links) -> Self {
+        Self {
+            title: title.to_string(),
+            byte_length: links.size.or(links.length),
+            categories: links.categories.clone(),
+            fetch_time_ms: links.fetch_time.map(|time| time.as_millis() as u64),
+            last_updated: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since| since.as_secs()),
+        }
+    }
+}
+
+/// A path saved to a file, to be compared with those found later or
+/// checked again
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PathFile {
+    /// `PATH_FILE_VERSION` when the file was written
+    pub version: u32,
+    /// Language code of the Wikipedia edition the path was found in
+    pub lang: String,
+    /// Unix time in seconds when the path was saved
+    pub saved: u64,
+    pub path: Path,
+}
+
+impl PathFile {
+    pub fn new(path: Path, lang: impl Into<String>) -> Self {
+        Self {
+            version: PATH_FILE_VERSION,
+            lang: lang.into(),
+            saved: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            path,
+        }
+    }
+
+    /// Reads a path saved by `save`, failing on files from a newer version
+    pub fn load(file: &FilePath) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(file)?);
+        let saved: Self = serde_json::from_reader(reader)?;
+        if saved.version > PATH_FILE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "saved by a newer version of wiki-path (format {}, this one reads up to {})",
+                    saved.version, PATH_FILE_VERSION
+                ),
+            ));
+        }
+        Ok(saved)
+    }
+
+    pub fn save(&self, file: &FilePath) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(file)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()
+    }
+}
+
+/// Why a searcher couldn't be built or a search didn't find a path.
+/// Articles that can't be fetched during a search are logged and skipped
+/// instead, unless Wikipedia is refusing requests.
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    /// The HTTP client couldn't be set up, or the start and end articles
+    /// couldn't be looked up
+    #[error("{0}")]
+    HttpError(#[from] rw::Error),
+    /// The API answered the lookup of the start and end articles with
+    /// something else than the expected JSON
+    #[error("Invalid API response: {0}")]
+    ParseError(String),
+    /// Every depth up to `BfsSearcherBuilder::max_depth` was searched
+    #[error("No path found within the maximum depth")]
+    DepthExceeded,
+    /// None of the start articles exist
+    #[error("article '{}' not found", normalize_display(.0))]
+    ArticleNotFound(String),
+    /// Wikipedia kept answering that it was sent too many requests, or
+    /// that its servers were busy, once the retries ran out
+    #[error("Wikipedia is refusing requests, try again later")]
+    RateLimitExceeded,
+    /// The search was stopped from the TUI
+    #[error("Search interrupted")]
+    Interrupted,
+    #[error("Invalid proxy URL: {0}")]
+    InvalidProxy(String),
+    /// The threads of `BfsSearcherBuilder::parallel_parse` couldn't be
+    /// started
+    #[error("Couldn't start the parsing threads: {0}")]
+    ParseThreads(#[from] rayon::ThreadPoolBuildError),
+    /// The database of `BfsSearcherBuilder::db` couldn't be opened, or
+    /// the index of `BfsSearcherBuilder::dump` couldn't be read
+    #[error("{}: {}", .0.display(), .1)]
+    Db(PathBuf, #[source] rusqlite::Error),
+    /// One of `BfsSearcherBuilder::skip_patterns` or `allow_patterns`
+    /// isn't a valid regular expression
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    /// The articles moved to disk by `BfsSearcherBuilder::max_memory`
+    /// couldn't be written or read back
+    #[error("Couldn't keep the articles on disk: {0}")]
+    Spill(#[source] io::Error),
+}
+
+impl From<FetchError> for SearchError {
+    fn from(err: FetchError) -> Self {
+        match err {
+            _ if err.is_rate_limit() => SearchError::RateLimitExceeded,
+            FetchError::Http(err) => SearchError::HttpError(err),
+            FetchError::Json(err) => SearchError::ParseError(err.to_string()),
+            FetchError::Lagged(_) => SearchError::RateLimitExceeded,
+            FetchError::Dump(file, err) => SearchError::Db(file, err),
+        }
+    }
+}
+
+/// Everything set through `BfsSearcherBuilder`
+struct Options {
+    start: Vec<String>,
+    end: Vec<String>,
+    via: Vec<String>,
+    forbidden: HashSet<String>,
+    skip_patterns: Vec<String>,
+    allow_patterns: Vec<String>,
+    max_depth: u32,
+    max_articles: Option<usize>,
+    time_limit: Option<Duration>,
+    all: bool,
+    algorithm: Algorithm,
+    bidirectional: bool,
+    weighted: bool,
+    weight_fns: Vec<WeightFn>,
+    suboptimal_ok: bool,
+    transposition_table: bool,
+    prevalidate: bool,
+    max_memory: Option<usize>,
+    level_limit: Option<usize>,
+    min_length: Option<usize>,
+    popularity_weight: Option<f64>,
+    no_disambig: bool,
+    max_fan_out: Option<usize>,
+    min_article_size: Option<usize>,
+    include_redirects: bool,
+    seed: Option<u64>,
+    /// Names given by `category_name`
+    categories: Vec<String>,
+    category_depth: u32,
+    skip_sections: Vec<String>,
+    prioritize_infobox: bool,
+    prioritize_see_also: bool,
+    intro_only: bool,
+    mobile_api: bool,
+    interwiki: Vec<String>,
+    lang: String,
+    base_url: Option<String>,
+    wiki_prefix: Option<String>,
+    namespaces: Vec<i32>,
+    api: bool,
+    workers: usize,
+    parse_threads: Option<usize>,
+    req_wait: Duration,
+    max_retries: u32,
+    max_retry_wait: Duration,
+    user_agent: String,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    cache: Option<(PathBuf, Duration)>,
+    memory_cache: bool,
+    dump: Option<PathBuf>,
+    checkpoint: Option<PathBuf>,
+    db: Option<PathBuf>,
+    graph: bool,
+    progress: bool,
+    stats: bool,
+    tui: bool,
+}
+
+/// Searches for paths from any of the start articles to any of the end
+/// ones. Breadth-first unless another `Algorithm` is chosen; see
+/// `BfsSearcher::builder`.
+pub struct BfsSearcher {
+    c: Options,
+    fetcher: Fetcher,
+    /// `forbidden`, `skip_patterns` and `allow_patterns` compiled
+    forbidden: Forbidden,
+    resume: Option<BfsState>,
+    on_path: Option<Box<OnPath>>,
+    on_expand: Option<Box<OnExpand>>,
+    on_event: Option<Box<OnEvent>>,
+    graph: Option<Graph>,
+    stopped_at: Option<Path>,
+    limit: Option<Limit>,
+    frontier_sizes: Vec<usize>,
+    peak_memory: usize,
+    articles: HashMap<String, Article>,
+}
+
+impl BfsSearcher {
+    /// Starts configuring a search from `start` to `end`. Titles are
+    /// expected as Wikipedia writes them in URLs, see `normalize_title`.
+    pub fn builder<S: Into<String>, E: Into<String>>(
+        start: impl IntoIterator<Item = S>,
+        end: impl IntoIterator<Item = E>,
+    ) -> BfsSearcherBuilder {
+        BfsSearcherBuilder {
+            c: Options {
+                start: start.into_iter().map(Into::into).collect(),
+                end: end.into_iter().map(Into::into).collect(),
+                via: Vec::new(),
+                forbidden: HashSet::new(),
+                skip_patterns: Vec::new(),
+                allow_patterns: Vec::new(),
+                max_depth: DEFAULT_MAX_DEPTH,
+                max_articles: None,
+                time_limit: None,
+                all: false,
+                algorithm: Algorithm::Bfs,
+                bidirectional: false,
+                weighted: false,
+                weight_fns: Vec::new(),
+                suboptimal_ok: false,
+                transposition_table: false,
+                prevalidate: false,
+                max_memory: None,
+                level_limit: None,
+                min_length: None,
+                popularity_weight: None,
+                no_disambig: false,
+                max_fan_out: None,
+                min_article_size: None,
+                include_redirects: false,
+                seed: None,
+                categories: Vec::new(),
+                category_depth: 0,
+                skip_sections: DEFAULT_SKIP_SECTIONS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                prioritize_infobox: false,
+                prioritize_see_also: false,
+                intro_only: false,
+                mobile_api: false,
+                interwiki: Vec::new(),
+                lang: DEFAULT_LANG.to_string(),
+                base_url: None,
+                wiki_prefix: None,
+                namespaces: vec![MAIN_NAMESPACE],
+                api: false,
+                workers: 1,
+                parse_threads: None,
+                req_wait: Duration::from_secs_f32(REQ_WAIT_SECS),
+                max_retries: DEFAULT_MAX_RETRIES,
+                max_retry_wait: Duration::from_secs_f64(DEFAULT_MAX_RETRY_WAIT_SECS),
+                user_agent: DEFAULT_USER_AGENT.to_string(),
+                timeout: Some(Duration::from_secs_f64(DEFAULT_TIMEOUT_SECS)),
+                proxy: None,
+                cache: None,
+                memory_cache: false,
+                dump: None,
+                checkpoint: None,
+                db: None,
+                graph: false,
+                progress: false,
+                stats: false,
+                tui: false,
+            },
+            resume: None,
+            on_path: None,
+            on_expand: None,
+            on_event: None,
+            fetcher: None,
+        }
+    }
+
+    /// Returns those of `titles` that don't exist. The future doesn't
+    /// borrow the searcher, so it can be sent to another thread even though
+    /// the callbacks can't be shared.
+    pub fn missing(
+        &self,
+        titles: &[String],
+    ) -> impl Future<Output = Result<Vec<String>, FetchError>> + Send + 'static {
+        let fetcher = self.fetcher.clone();
+        let titles = titles.to_vec();
+        async move { fetcher.missing(&titles).await }
+    }
+
+    /// Whether each of `titles` is an article, a redirect or missing, in as
+    /// few requests as the API allows
+    pub async fn validate_titles(
+        &self,
+        titles: &[String],
+    ) -> Result<HashMap<String, TitleStatus>, FetchError> {
+        self.fetcher.validate_titles(titles).await
+    }
+
+    /// Title of the article `title` redirects to, or `title` itself
+    pub async fn resolve_redirect(&self, title: &str) -> Result<String, FetchError> {
+        self.fetcher.resolve_redirect(title).await
+    }
+
+    /// Title of a random article, as picked by Special:Random or from the
+    /// dump
+    pub async fn random_article(&self) -> Result<String, SearchError> {
+        self.fetcher
+            .random()
+            .await?
+            .ok_or_else(|| match self.dump() {
+                Some(file) => {
+                    SearchError::ParseError(format!("{} has no articles", file.display()))
+                }
+                None => {
+                    SearchError::ParseError("Special:Random didn't lead to an article".to_string())
+                }
+            })
+    }
+
+    /// Fetches the articles of a path again, from the cache if there's one,
+    /// and checks that each links to the next, directly or through a
+    /// redirect. Returns the index of the first that doesn't, if any.
+    pub async fn verify_path(&self, articles: &[String]) -> Result<Option<usize>, FetchError> {
+        for (i, hop) in articles.windows(2).enumerate() {
+            if !self.fetcher.links_to(&hop[0], &hop[1]).await? {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Titles of the articles linking to `title`, as the reverse half of a
+    /// bidirectional search finds them
+    pub async fn backlinks(&self, title: &str) -> Result<Vec<String>, FetchError> {
+        Ok(self
+            .fetcher
+            .clone()
+            .backlinks(title.to_string())
+            .await?
+            .titles)
+    }
+
+    /// Titles of existing articles close to `title`
+    pub async fn suggestions(&self, title: &str) -> Result<Vec<String>, FetchError> {
+        self.fetcher.suggestions(title).await
+    }
+
+    /// Runs the search, returning the paths found. Redirects are resolved
+    /// first (but for the start articles with `include_redirects`), so that
+    /// the start and end articles are known by the titles the search finds
+    /// them under. Articles that can't be fetched are
+    /// logged as warnings and skipped. Finding no path is an error, unless
+    /// a limit stopped the search first: see `limit_reached`.
+    pub async fn search(&mut self) -> Result<Vec<Path>, SearchError> {
+        let c = &self.c;
+        let mut resume = self.resume.take();
+
+        let mut start = c.start.clone();
+        let mut end = c.end.clone();
+        let mut via = c.via.clone();
+
+        // Without the names of the namespaces, links to titles with a colon
+        // are left out like those to other namespaces
+        if let Err(err) = self.fetcher.load_namespaces().await {
+            warn!("Failed to look the namespaces up: {}", err);
+        }
+
+        // A resumed search goes on with the titles it was saved with
+        if resume.is_none() {
+            let mut start_exists = start.is_empty();
+            let starts = start.len();
+
+            let titles: Vec<_> = start.iter().chain(&end).chain(&via).cloned().collect();
+            let statuses = self.fetcher.validate_titles(&titles).await?;
+
+            let titles = start.iter_mut().chain(&mut end).chain(&mut via);
+            for (i, title) in titles.enumerate() {
+                let resolved = match statuses.get(title) {
+                    Some(TitleStatus::Valid) => None,
+                    Some(TitleStatus::Redirect(target)) => Some(target.clone()),
+                    // Missing end articles and waypoints are left to the
+                    // caller, who may know better
+                    _ => continue,
+                };
+                if i < starts {
+                    start_exists = true;
+                }
+                // A redirect to start from is one link from its target
+                if c.include_redirects && i < starts {
+                    continue;
+                }
+                if let Some(resolved) = resolved {
+                    debug!(
+                        "{} redirects to {}",
+                        normalize_display(title),
+                        normalize_display(&resolved)
+                    );
+                    *title = resolved;
+                }
+            }
+
+            if !start_exists {
+                return Err(SearchError::ArticleNotFound(start[0].clone()));
+            }
+        }
+
+        let mut categories: HashSet<String> = c.categories.iter().cloned().collect();
+        let mut level = c.categories.clone();
+        for _ in 0..c.category_depth {
+            let mut next = Vec::new();
+            for category in &level {
+                for subcategory in self.fetcher.subcategories(category).await? {
+                    if categories.insert(subcategory.clone()) {
+                        next.push(subcategory);
+                    }
+                }
+            }
+            level = next;
+        }
+        if !c.categories.is_empty() {
+            debug!("Searching through {} categories", categories.len());
+        }
+
+        let mut db = None;
+        if let Some(file) = &c.db {
+            let (search_db, state) = SearchDb::open(file, &start, &end, &c.lang, c.max_depth)
+                .map_err(|err| SearchError::Db(file.clone(), err))?;
+            db = Some(search_db);
+            resume = state;
+        }
+
+        let forbidden = self
+            .forbidden
+            .clone()
+            .exempt(start.iter().chain(&end).chain(&via));
+        let mut search = Search {
+            c,
+            start,
+            end,
+            via,
+            fetcher: self.fetcher.clone(),
+            start_time: Instant::now(),
+            graph: c.graph.then(Graph::default),
+            progress: new_progress_bar(c),
+            forbidden,
+            categories,
+            in_categories: HashMap::new(),
+            see_also: HashSet::new(),
+            fetch_times: Vec::new(),
+            articles: Vec::new(),
+            db,
+            stats: c.stats.then(Stats::default),
+            tui: c.tui.then(tui::Tui::start),
+            aborted: None,
+            limit: None,
+            error: None,
+            frontier_sizes: Vec::new(),
+            anchors: HashMap::new(),
+            pushed: 0,
+            segment: false,
+            found: Vec::new(),
+            peak_memory: 0,
+            on_path: self.on_path.as_deref_mut(),
+            on_expand: self.on_expand.as_deref_mut(),
+            on_event: self.on_event.as_deref_mut(),
+        };
+
+        if search.via.is_empty() {
+            search.run(resume).await;
+        } else {
+            search.via().await;
+        }
+
+        search.progress.finish_and_clear();
+        search.log_fetch_times();
+
+        if let Some(tui) = search.tui.take() {
+            if let Err(err) = tui.stop() {
+                error!("{}", err);
+            }
+        }
+
+        self.graph = search.graph.take();
+        self.stopped_at = search.aborted.take();
+        self.limit = search.limit;
+        self.frontier_sizes = mem::take(&mut search.frontier_sizes);
+        self.peak_memory = search.peak_memory;
+        self.articles = search
+            .articles
+            .drain(..)
+            .map(|article| (article.title.clone(), article))
+            .collect();
+
+        if self.stopped_at.is_some() {
+            return Err(SearchError::Interrupted);
+        }
+        if let Some(err) = search.error.take() {
+            return Err(err);
+        }
+        // Stopped by a limit before going through every depth
+        if search.found.is_empty() && self.limit.is_none() {
+            return Err(SearchError::DepthExceeded);
+        }
+        Ok(mem::take(&mut search.found))
+    }
+
+    /// The Wikipedia edition searched
+    pub fn wiki(&self) -> &Wiki {
+        &self.fetcher.wiki
+    }
+
+    /// Requests made by the searcher so far, including those of `missing`
+    /// and `suggestions`
+    pub fn traffic(&self) -> Traffic {
+        self.fetcher.traffic()
+    }
+
+    /// Most bytes the articles kept by the last search took at once,
+    /// estimated from their titles. Only measured by `Algorithm::Bfs`,
+    /// `bidirectional` included, and `Algorithm::Iddfs`.
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory
+    }
+
+    /// The index links are taken from, if `dump` was set
+    pub fn dump(&self) -> Option<&std::path::Path> {
+        self.fetcher.dump_index()
+    }
+
+    /// Use of the memory cache so far, if `memory_cache` was enabled
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.fetcher.cache_stats()
+    }
+
+    /// The articles and links explored by the last search, if `graph` was
+    /// enabled
+    pub fn graph(&self) -> Option<&Graph> {
+        self.graph.as_ref()
+    }
+
+    /// What the last search learned about the articles it expanded, by
+    /// title, like the length of those on the paths it found
+    pub fn articles(&self) -> &HashMap<String, Article> {
+        &self.articles
+    }
+
+    /// Path to the last article expanded, if the last search was stopped
+    /// from the TUI
+    pub fn stopped_at(&self) -> Option<&Path> {
+        self.stopped_at.as_ref()
+    }
+
+    /// The limit that stopped the last search, if it didn't finish
+    pub fn limit_reached(&self) -> Option<Limit> {
+        self.limit
+    }
+
+    /// Articles in each depth the last search went through, if it was a
+    /// breadth-first search: the start articles, those they link to, and
+    /// so on
+    pub fn frontier_sizes(&self) -> &[usize] {
+        &self.frontier_sizes
+    }
+}
+
+/// Options of a `BfsSearcher`
+pub struct BfsSearcherBuilder {
+    c: Options,
+    resume: Option<BfsState>,
+    on_path: Option<Box<OnPath>>,
+    on_expand: Option<Box<OnExpand>>,
+    on_event: Option<Box<OnEvent>>,
+    fetcher: Option<Fetcher>,
+}
+
+impl BfsSearcherBuilder {
+    /// Depth of the farthest articles whose links are followed, the start
+    /// being at depth 0: paths have up to one link more
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.c.max_depth = depth;
+        self
+    }
+
+    /// Stop once `max` articles have been queued, keeping the paths found
+    /// until then
+    pub fn max_articles(mut self, max: usize) -> Self {
+        self.c.max_articles = Some(max);
+        self
+    }
+
+    /// Stop once the search has run for `limit`, keeping the paths found
+    /// until then
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.c.time_limit = Some(limit);
+        self
+    }
+
+    /// Find all the shortest paths instead of stopping at the first one.
+    /// Other algorithms than `Algorithm::Bfs` find a path to each target.
+    pub fn all(mut self, all: bool) -> Self {
+        self.c.all = all;
+        self
+    }
+
+    /// Only report paths of at least `hops` links, searching on past
+    /// shorter ones, which don't count as reaching the target. With `all`,
+    /// every shortest path that long is found. Only used by
+    /// `Algorithm::Bfs`, neither `bidirectional` nor `weighted`.
+    pub fn min_length(mut self, hops: usize) -> Self {
+        self.c.min_length = Some(hops);
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.c.algorithm = algorithm;
+        self
+    }
+
+    /// Also search backwards from the end using backlinks and meet in the
+    /// middle. Only used by `Algorithm::Bfs`.
+    pub fn bidirectional(mut self, bidirectional: bool) -> Self {
+        self.c.bidirectional = bidirectional;
+        self
+    }
+
+    /// Of the shortest paths, find the one whose links appear earliest in
+    /// their articles, using Dijkstra's algorithm on the offsets of the
+    /// links in the HTML. Only used by `Algorithm::Bfs`, and not with `api`
+    /// or `dump`.
+    pub fn weighted(mut self, weighted: bool) -> Self {
+        self.c.weighted = weighted;
+        self
+    }
+
+    /// What following a link costs with `Algorithm::Dijkstra`, the weights
+    /// of each multiplied. `WeightFn::Uniform` if none are given.
+    pub fn weight_fns(mut self, weight_fns: impl IntoIterator<Item = WeightFn>) -> Self {
+        self.c.weight_fns = weight_fns.into_iter().collect();
+        self
+    }
+
+    /// Let the A* heuristic outweigh path length: fewer requests, but the
+    /// path found may not be the shortest
+    pub fn suboptimal_ok(mut self, suboptimal_ok: bool) -> Self {
+        self.c.suboptimal_ok = suboptimal_ok;
+        self
+    }
+
+    /// Remember how close to the start each article was expanded during an
+    /// iteration of `Algorithm::Iddfs`, so that it isn't expanded again
+    /// further away. Requests come closer to those of a breadth-first
+    /// search, for memory that grows with the articles expanded.
+    pub fn transposition_table(mut self, enabled: bool) -> Self {
+        self.c.transposition_table = enabled;
+        self
+    }
+
+    /// Look the articles of each depth up `TITLES_PER_QUERY` at a time
+    /// before expanding them, and skip those that don't exist instead of
+    /// fetching them. Worth it with `api`, whose links include missing
+    /// articles. Only used by `Algorithm::Bfs`, neither `bidirectional`
+    /// nor `weighted`.
+    pub fn prevalidate(mut self, prevalidate: bool) -> Self {
+        self.c.prevalidate = prevalidate;
+        self
+    }
+
+    /// Move the articles found to a temporary file once they take more than
+    /// `bytes` of memory, reading them back to expand them and to build the
+    /// paths found. Each article on disk still takes a few dozen bytes in
+    /// memory, for the hash of its title, and what `BfsSearcher::articles`
+    /// returns is kept there too. Only used by `Algorithm::Bfs`, neither
+    /// `bidirectional` nor `weighted`, and not with `checkpoint`, `db`,
+    /// `tui` or `prioritize_see_also`, which need every article in memory.
+    pub fn max_memory(mut self, bytes: usize) -> Self {
+        self.c.max_memory = Some(bytes);
+        self
+    }
+
+    /// Expand at most `limit` articles of each depth, the first ones found
+    /// or, with `seed`, a random sample of them. The others stay visited,
+    /// so they aren't found again, but their links aren't followed. Only
+    /// used by `Algorithm::Bfs`, neither `bidirectional` nor `weighted`.
+    pub fn level_limit(mut self, limit: usize) -> Self {
+        self.c.level_limit = Some(limit);
+        self
+    }
+
+    /// Let A* prefer the articles people visit most, by dividing their
+    /// priority by `1 + weight * log10(1 + views)`, views being counted
+    /// over the last 30 days with the Wikimedia pageviews API. The paths
+    /// found are more like those people would take, but not the shortest,
+    /// and every candidate article costs a request.
+    pub fn popularity_weight(mut self, weight: f64) -> Self {
+        self.c.popularity_weight = Some(weight);
+        self
+    }
+
+    /// Pass through these articles, in order, on the way to the end
+    pub fn via<S: Into<String>>(mut self, via: impl IntoIterator<Item = S>) -> Self {
+        self.c.via.extend(via.into_iter().map(Into::into));
+        self
+    }
+
+    /// Never pass through these articles
+    pub fn forbidden<S: Into<String>>(mut self, forbidden: impl IntoIterator<Item = S>) -> Self {
+        self.c
+            .forbidden
+            .extend(forbidden.into_iter().map(Into::into));
+        self
+    }
+
+    /// Never enter articles whose titles, as `normalize_title` writes them,
+    /// match any of the regular expressions `patterns`, like `^List_of_`.
+    /// `build` fails if one is invalid.
+    pub fn skip_patterns<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.c
+            .skip_patterns
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only enter articles whose titles match at least one of the regular
+    /// expressions `patterns`, like `skip_patterns` those of the others.
+    /// The start and end articles and waypoints always are. Paths may not
+    /// be found anymore; a warning is logged when a depth of a
+    /// breadth-first search had no link to follow.
+    pub fn allow_patterns<S: Into<String>>(
+        mut self,
+        patterns: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.c
+            .allow_patterns
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Don't follow the links of disambiguation pages
+    pub fn no_disambig(mut self, no_disambig: bool) -> Self {
+        self.c.no_disambig = no_disambig;
+        self
+    }
+
+    /// Don't follow the links of articles with more than `max` of them,
+    /// such as those of countries or wars that link to most of their
+    /// field. Unlike `forbidden`, the articles can still end a path, and
+    /// the start articles are always followed. The backward search of
+    /// `bidirectional` doesn't know how many links the articles it finds
+    /// have, so paths may still go through them there.
+    pub fn max_fan_out(mut self, max: usize) -> Self {
+        self.c.max_fan_out = Some(max);
+        self
+    }
+
+    /// Don't follow the links of articles whose HTML is smaller than `bytes`,
+    /// which are likely stubs. They're still entered, and can end a path.
+    /// Only applies to article HTML, so not with `api`, `dump` or
+    /// `Algorithm::Astar`.
+    pub fn min_article_size(mut self, bytes: usize) -> Self {
+        self.c.min_article_size = Some(bytes);
+        self
+    }
+
+    /// Keep redirects in paths as articles of their own, linking only to
+    /// their target. By default an article found to be a redirect when
+    /// fetched is replaced by its target, so that following it doesn't
+    /// count as a link. Not used by the backward search of `bidirectional`.
+    pub fn include_redirects(mut self, include: bool) -> Self {
+        self.c.include_redirects = include;
+        self
+    }
+
+    /// Only enter articles in at least one of `categories`, named with or
+    /// without their `Category:` prefix, or in their subcategories down to
+    /// `category_depth`. The start and end articles always are. Finding
+    /// out costs a query for every 50 articles linked to, but for
+    /// `Algorithm::Astar` whose links come with their categories. Not used
+    /// with `dump`.
+    pub fn require_categories<S: Into<String>>(
+        mut self,
+        categories: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.c
+            .categories
+            .extend(categories.into_iter().map(|category| {
+                let title = normalize_title(&category.into());
+                match title.strip_prefix("Category:") {
+                    Some(name) => normalize_title(name),
+                    None => title,
+                }
+            }));
+        self
+    }
+
+    /// Levels of subcategories of `require_categories` that articles can be
+    /// in instead, 0 (the default) for only the categories given. They're
+    /// fetched when the search starts, with a query for each category.
+    pub fn category_depth(mut self, depth: u32) -> Self {
+        self.c.category_depth = depth;
+        self
+    }
+
+    /// Don't follow the links in the sections with these titles, nor in
+    /// their subsections, `DEFAULT_SKIP_SECTIONS` by default. An empty list
+    /// follows them all. Not used with `api` or `dump`, which don't tell
+    /// where the links are.
+    pub fn skip_sections<S: Into<String>>(mut self, sections: impl IntoIterator<Item = S>) -> Self {
+        self.c.skip_sections = sections.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only follow the links of the introduction of an article, before its
+    /// first section: far fewer, and closer to its topic, but a path may
+    /// not be found through them. Not used with `api` or `dump`.
+    pub fn intro_only(mut self, intro_only: bool) -> Self {
+        self.c.intro_only = intro_only;
+        self
+    }
+
+    /// Fetch articles as the mobile HTML of the REST API, a fraction of the
+    /// size of the article pages, with the same links. Not used with
+    /// `api` or `dump`.
+    pub fn mobile_api(mut self, mobile_api: bool) -> Self {
+        self.c.mobile_api = mobile_api;
+        self
+    }
+
+    /// Shuffle the links of each article with `seed` before following
+    /// them, instead of taking them in the order of the article. The same
+    /// seed finds the same path however the links are laid out, as long as
+    /// the article links to the same articles.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.c.seed = Some(seed);
+        self
+    }
+
+    /// Also follow the language links to the articles of the Wikipedia
+    /// editions of `langs`, and the links in those articles. Paths title
+    /// them with the language code first, like `[de]Philosophie`. Not used
+    /// with `api` or `dump`, and the other editions are searched on
+    /// Wikipedia even with `base_url`.
+    pub fn interwiki<S: Into<String>>(mut self, langs: impl IntoIterator<Item = S>) -> Self {
+        self.c.interwiki = langs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Language code of the Wikipedia edition to search
+    pub fn lang(mut self, lang: impl Into<String>) -> Self {
+        self.c.lang = lang.into();
+        self
+    }
+
+    /// Search the wiki at `url`, like a mirror or another MediaWiki
+    /// installation, instead of the Wikipedia edition of `lang`. It must
+    /// serve the API at `/w/api.php`, as Wikipedia does, and articles under
+    /// `/wiki/` unless `wiki_prefix` says otherwise.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.c.base_url = Some(url.into());
+        self
+    }
+
+    /// Path the wiki serves articles under, before their title, like `/w/`
+    /// or `/index.php/`; `/wiki/` by default, as on Wikipedia
+    pub fn wiki_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.c.wiki_prefix = Some(prefix.into());
+        self
+    }
+
+    /// IDs of the namespaces to follow links to, see `parse_namespace`;
+    /// only the articles of `MAIN_NAMESPACE` by default. A title is in a
+    /// namespace when its name comes before the first colon, so articles
+    /// like `Doctor_Who:_The_Movie` are still followed. Not used with
+    /// `dump`, which only has articles.
+    pub fn namespaces(mut self, namespaces: impl IntoIterator<Item = i32>) -> Self {
+        self.c.namespaces = namespaces.into_iter().collect();
+        self
+    }
+
+    /// Get links from the MediaWiki API instead of the article HTML
+    pub fn api(mut self, api: bool) -> Self {
+        self.c.api = api;
+        self
+    }
+
+    /// Number of concurrent requests, 1 by default
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.c.workers = workers.max(1);
+        self
+    }
+
+    /// Parse the HTML of up to `threads` articles at once, on threads of
+    /// their own rather than those sending the requests. Only helps when
+    /// several `workers` keep articles coming. Not used with `api` or
+    /// `dump`, which have no HTML to parse.
+    pub fn parallel_parse(mut self, threads: usize) -> Self {
+        self.c.parse_threads = Some(threads.max(1));
+        self
+    }
+
+    /// Least time each worker waits between two requests
+    pub fn request_wait(mut self, wait: Duration) -> Self {
+        self.c.req_wait = wait;
+        self
+    }
+
+    /// Retry a request up to `retries` times when the server is overloaded
+    /// (429 or 5xx), waiting longer each time
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.c.max_retries = retries;
+        self
+    }
+
+    /// Wait at most `wait` before retrying a request, even when the server
+    /// asks for longer with Retry-After
+    pub fn max_retry_wait(mut self, wait: Duration) -> Self {
+        self.c.max_retry_wait = wait;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.c.user_agent = user_agent.into();
+        self
+    }
+
+    /// Give up on a request after `timeout`, or never with `None`
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.c.timeout = timeout;
+        self
+    }
+
+    /// Send requests through the proxy at `url` instead of the ones from
+    /// the environment
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.c.proxy = Some(url.into());
+        self
+    }
+
+    /// Save fetched pages in `dir` and reuse those younger than `ttl`.
+    /// Older articles are asked for again with the ETag and Last-Modified
+    /// they were served with, and kept if Wikipedia answers they didn't
+    /// change. Each edition, or the host of `base_url`, has its own
+    /// subdirectory.
+    pub fn cache(mut self, dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        self.c.cache = Some((dir.into(), ttl));
+        self
+    }
+
+    /// Keep the links of the articles fetched in memory, so that later
+    /// searches sharing the requests of this one don't fetch them again
+    pub fn memory_cache(mut self, enabled: bool) -> Self {
+        self.c.memory_cache = enabled;
+        self
+    }
+
+    /// Take links from `index`, built from a Wikipedia XML dump by
+    /// `index_dump`, instead of fetching them, and look titles up there.
+    /// Nothing is sent to Wikipedia, except by `bidirectional`,
+    /// `Algorithm::Astar` and `popularity_weight`, which need backlinks,
+    /// categories and page views the dump doesn't have.
+    pub fn dump(mut self, index: impl Into<PathBuf>) -> Self {
+        self.c.dump = Some(index.into());
+        self
+    }
+
+    /// Save the state of a breadth-first search to `file` after each depth
+    pub fn checkpoint(mut self, file: impl Into<PathBuf>) -> Self {
+        self.c.checkpoint = Some(file.into());
+        self
+    }
+
+    /// Continue the search saved in `state`, with its start, end and
+    /// maximum depth
+    pub fn resume(mut self, state: BfsState) -> Self {
+        self.c.start = state.start.clone();
+        self.c.end = state.end.clone();
+        self.c.max_depth = state.max_depth;
+        self.resume = Some(state);
+        self
+    }
+
+    /// Store a breadth-first search in the SQLite database `file` after
+    /// each depth, and resume an unfinished search for the same articles
+    /// stored there
+    pub fn db(mut self, file: impl Into<PathBuf>) -> Self {
+        self.c.db = Some(file.into());
+        self
+    }
+
+    /// Record the articles and links explored, see `BfsSearcher::graph`
+    pub fn graph(mut self, graph: bool) -> Self {
+        self.c.graph = graph;
+        self
+    }
+
+    /// Show a progress bar on stderr
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.c.progress = progress;
+        self
+    }
+
+    /// Print statistics for each depth of a breadth-first search to stderr
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.c.stats = stats;
+        self
+    }
+
+    /// Show a breadth-first search in a full-terminal interface, where q
+    /// stops the search and s saves a checkpoint. The thumbnails of the
+    /// lead images of the articles are looked up alongside their links,
+    /// one more request each, except with `dump`.
+    pub fn tui(mut self, tui: bool) -> Self {
+        self.c.tui = tui;
+        self
+    }
+
+    /// Follow the links in the infobox of an article before its other
+    /// links, and with `weighted` count them as the closest. Not used with
+    /// `api` or `dump`.
+    pub fn prioritize_infobox(mut self, prioritize: bool) -> Self {
+        self.c.prioritize_infobox = prioritize;
+        self
+    }
+
+    /// Expand the articles first linked to from a See also section before
+    /// the others of their depth, following those links even if the section
+    /// is in `skip_sections`. Only used by `Algorithm::Bfs`, neither
+    /// `bidirectional` nor `weighted`, and not with `api` or `dump`.
+    pub fn prioritize_see_also(mut self, prioritize: bool) -> Self {
+        self.c.prioritize_see_also = prioritize;
+        self
+    }
+
+    /// Send requests through the same workers as `searcher`, so that
+    /// searches running one after the other or alongside share its rate
+    /// limit, traffic count and memory cache. Its client, wiki, caches,
+    /// dump, `api`, `mobile_api`, `interwiki`, `skip_sections`,
+    /// `prioritize_infobox`, `intro_only`, `include_redirects`, `seed` and
+    /// `parallel_parse` settings are used instead of those set here.
+    pub fn share_requests(mut self, searcher: &BfsSearcher) -> Self {
+        self.fetcher = Some(searcher.fetcher.clone());
+        self
+    }
+
+    /// Call `on_path` with each path as soon as it's found
+    pub fn on_path(mut self, on_path: impl FnMut(&Path) + Send + 'static) -> Self {
+        self.on_path = Some(Box::new(on_path));
+        self
+    }
+
+    /// Call `on_expand` with each article and its depth as its links are
+    /// fetched, the start articles being at depth 0
+    pub fn on_expand(mut self, on_expand: impl FnMut(&str, u32) + Send + 'static) -> Self {
+        self.on_expand = Some(Box::new(on_expand));
+        self
+    }
+
+    /// Call `on_event` with each `SearchEvent` as it happens. Returning
+    /// `ControlFlow::Break` stops the search with `Limit::Stopped`, keeping
+    /// the paths found until then.
+    pub fn on_event(
+        mut self,
+        on_event: impl FnMut(SearchEvent<'_>) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    pub fn build(self) -> Result<BfsSearcher, SearchError> {
+        let c = self.c;
+
+        let fetcher = match self.fetcher {
+            Some(fetcher) => fetcher,
+            None => {
+                let wiki = match &c.base_url {
+                    Some(url) => Wiki::with_base_url(&c.lang, url),
+                    None => Wiki::new(&c.lang),
+                };
+                let wiki = match &c.wiki_prefix {
+                    Some(prefix) => wiki.with_article_path(prefix),
+                    None => wiki,
+                }
+                .with_namespaces(&c.namespaces);
+                // Editions and other wikis have different articles with the
+                // same title
+                let cache_subdir = match &c.base_url {
+                    Some(_) => wiki.host().replace(':', "_"),
+                    None => c.lang.clone(),
+                };
+                let fetcher = Fetcher::new(
+                    build_client(&c)?,
+                    c.workers,
+                    c.req_wait,
+                    c.max_retries,
+                    c.api,
+                    wiki,
+                    c.cache.as_ref().map(|(dir, ttl)| Cache {
+                        dir: dir.join(&cache_subdir),
+                        ttl: *ttl,
+                    }),
+                )
+                .max_retry_wait(c.max_retry_wait)
+                .skip_sections(
+                    c.skip_sections
+                        .iter()
+                        .filter(|section| {
+                            !(c.prioritize_see_also && section.eq_ignore_ascii_case("See also"))
+                        })
+                        .cloned()
+                        .collect(),
+                )
+                .prioritize_infobox(c.prioritize_infobox)
+                .intro_only(c.intro_only)
+                .mobile(c.mobile_api)
+                .interwiki(&c.interwiki)
+                .memory_cache(c.memory_cache)
+                .include_redirects(c.include_redirects)
+                .seed(c.seed);
+                let fetcher = match c.parse_threads {
+                    Some(threads) => fetcher.parse_pool(
+                        rayon::ThreadPoolBuilder::new()
+                            .num_threads(threads)
+                            .thread_name(|i| format!("parse-{}", i))
+                            .build()?,
+                    ),
+                    None => fetcher,
+                };
+
+                match &c.dump {
+                    Some(file) => fetcher.dump(
+                        DumpIndex::open(file).map_err(|err| SearchError::Db(file.clone(), err))?,
+                    ),
+                    None => fetcher,
+                }
+            }
+        };
+
+        let patterns = (!c.skip_patterns.is_empty())
+            .then(|| RegexSet::new(&c.skip_patterns))
+            .transpose()?;
+        let allowed = (!c.allow_patterns.is_empty())
+            .then(|| RegexSet::new(&c.allow_patterns))
+            .transpose()?;
+        let forbidden = Forbidden::new(c.forbidden.clone(), patterns, allowed);
+
+        Ok(BfsSearcher {
+            c,
+            fetcher,
+            forbidden,
+            resume: self.resume,
+            on_path: self.on_path,
+            on_expand: self.on_expand,
+            on_event: self.on_event,
+            graph: None,
+            stopped_at: None,
+            limit: None,
+            frontier_sizes: Vec::new(),
+            peak_memory: 0,
+            articles: HashMap::new(),
+        })
+    }
+}
+
+fn build_client(c: &Options) -> Result<rw::Client, SearchError> {
+    let mut builder = rw::Client::builder().user_agent(&c.user_agent);
+
+    // Timed out requests fail like any other, so the article is skipped
+    if let Some(timeout) = c.timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    // Replaces the proxies from the environment
+    if let Some(proxy) = &c.proxy {
+        let proxy = rw::Proxy::all(proxy).map_err(|_| SearchError::InvalidProxy(proxy.clone()))?;
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn new_progress_bar(c: &Options) -> ProgressBar {
+    if !c.progress || c.tui {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template(
+        "[{elapsed_precise}] {prefix}: {bar:30} {pos}/{len} articles | {msg}",
+    )
+    .unwrap();
+
+    ProgressBar::new(0).with_style(style)
+}
@@ -1,147 +1,2609 @@
 use std::{
-    collections::HashMap,
-    thread,
+    collections::{BTreeMap, HashSet},
+    env, fmt,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, LineWriter, Write},
+    mem,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
     time::{Duration, Instant},
 };
 
-use clap::{self, Parser};
-use jiff;
-use reqwest as rw;
-use scraper as sc;
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing,
+};
+use clap::{self, CommandFactory, FromArgMatches, ValueEnum};
+use futures_util::{stream, StreamExt};
+use ratatui::crossterm::terminal;
+use rustyline::{error::ReadlineError, DefaultEditor};
+use serde::{Deserialize, Serialize};
+use tokio::{signal, task};
+use tracing::{debug, error, level_filters::LevelFilter, warn};
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt};
+use wiki_path::{
+    decode_title, index_dump, normalize_display, normalize_title, parse_namespace, Algorithm,
+    BfsSearcher, BfsSearcherBuilder, BfsState, Limit, PathFile, SearchError, SearchEvent, WeightFn,
+    Wiki, DEFAULT_LANG, DEFAULT_MAX_DEPTH, DEFAULT_MAX_RETRIES, DEFAULT_MAX_RETRY_WAIT_SECS,
+    DEFAULT_SKIP_SECTIONS, DEFAULT_TIMEOUT_SECS, DEFAULT_USER_AGENT, LANGUAGES,
+};
+
+const DEFAULT_WORKERS: usize = 4;
+
+const DEFAULT_CACHE_TTL_HOURS: f64 = 24.0;
+
+/// The file of --output-file, once opened
+static OUTPUT_FILE: OnceLock<Mutex<LineWriter<File>>> = OnceLock::new();
+
+/// Where the results go: the file of --output-file if one was given,
+/// otherwise stdout. Logs, progress and stats stay on stderr.
+struct Output;
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match OUTPUT_FILE.get() {
+            Some(file) => file.lock().unwrap().write(buf),
+            None => io::stdout().write(buf),
+        }
+    }
 
-const DEFAULT_MAX_DEPTH: u32 = 25;
+    fn flush(&mut self) -> io::Result<()> {
+        match OUTPUT_FILE.get() {
+            Some(file) => file.lock().unwrap().flush(),
+            None => io::stdout().flush(),
+        }
+    }
+}
 
-const REQ_WAIT_SECS: f32 = 0.5;
+/// Like `println!`, but to `Output`, each line written at once
+macro_rules! outln {
+    () => {
+        outln!("")
+    };
+    ($($arg:tt)*) => {{
+        let line = format!("{}\n", format_args!($($arg)*));
+        let _ = Output.write_all(line.as_bytes());
+    }};
+}
 
 #[derive(clap::Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true,
+    after_help = EXIT_CODES_HELP
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Articles to start from, separated by commas; all of them are
+    /// searched together and the path begins with the one it came from
+    #[arg(
+        required_unless_present_any = ["list_langs", "resume", "print_config", "batch", "interactive", "random_start"],
+        default_value = "",
+        hide_default_value = true
+    )]
     start: String,
-    end: String,
+    /// Articles to search for, separated by commas; the search stops at
+    /// whichever is found first. Commas in titles can be written as %2C.
+    #[arg(
+        required_unless_present_any = ["list_langs", "resume", "print_config", "batch", "interactive", "random_start", "random_end"],
+        num_args = 1..,
+        value_delimiter = ','
+    )]
+    end: Vec<String>,
 
-    /// Print article name and depth for each searched article
+    /// Log each searched article with its short description and depth,
+    /// along with other debug output. With --format json, also print a JSON
+    /// line for each, with the article it was reached from.
     #[arg(short, long)]
     verbose: bool,
 
+    /// Print nothing but the paths found and errors
+    #[arg(short, long, conflicts_with_all = ["verbose", "stats", "tui"])]
+    quiet: bool,
+
+    /// Print only the length of each path found, and exit with the length
+    /// of the shortest (up to 125); implies --quiet
+    #[arg(long, conflicts_with_all = ["verbose", "stats", "tui"])]
+    count_only: bool,
+
     /// Maximum depth to search
     #[arg(short = 'd', long, value_name = "DEPTH", default_value_t = DEFAULT_MAX_DEPTH)]
     max_depth: u32,
 
-    /// Find all paths up to DEPTH
+    /// Stop the search once N articles have been queued
+    #[arg(long, value_name = "N")]
+    max_articles: Option<usize>,
+
+    /// Stop the search after SECS seconds
+    #[arg(long, value_name = "SECS")]
+    time_limit: Option<f64>,
+
+    /// Find all the shortest paths (with other algorithms than bfs, a path
+    /// to each of END)
     #[arg(short, long)]
     all: bool,
+
+    /// Skip the paths of fewer than N links (2 skips direct links from
+    /// START) and go on to longer ones (only with --algorithm bfs)
+    #[arg(long, value_name = "N", conflicts_with_all = ["bidirectional", "weighted"])]
+    min_length: Option<usize>,
+
+    /// Fetch the articles of each depth concurrently
+    #[arg(long = "async")]
+    concurrent: bool,
+
+    /// Number of concurrent requests when using --async
+    #[arg(short, long, value_name = "N", default_value_t = DEFAULT_WORKERS)]
+    workers: usize,
+
+    /// Parse the HTML of up to N articles at once on threads of their own,
+    /// while requests go on (useful with --async)
+    #[arg(long, value_name = "N", conflicts_with_all = ["api", "dump"])]
+    parallel_parse: Option<usize>,
+
+    /// Also search backwards from END using backlinks and meet in the middle
+    #[arg(short, long)]
+    bidirectional: bool,
+
+    /// Get links from the MediaWiki API instead of the article HTML
+    #[arg(long)]
+    api: bool,
+
+    /// Fetch the mobile HTML of articles from the REST API, a fraction of
+    /// the size of the article pages (not with --api or --dump)
+    #[arg(long, conflicts_with_all = ["api", "dump"])]
+    mobile_api: bool,
+
+    /// Also follow the language links to the Wikipedia editions of CODES,
+    /// separated by commas, and the links of their articles, which paths
+    /// show as [de]Philosophie (not with --api, --dump, --bidirectional,
+    /// --require-category or --base-url)
+    #[arg(
+        long,
+        value_name = "CODES",
+        value_delimiter = ',',
+        conflicts_with_all = ["api", "dump", "bidirectional", "require_category", "base_url"]
+    )]
+    interwiki: Vec<String>,
+
+    /// Take links from the Wikipedia XML dump FILE (a pages-articles.xml.bz2)
+    /// instead of Wikipedia, indexing it first unless an index newer than it
+    /// exists
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "api",
+            "bidirectional",
+            "weighted",
+            "prioritize_infobox",
+            "popularity_weight",
+            "require_category",
+        ]
+    )]
+    dump: Option<PathBuf>,
+
+    /// Where the index of --dump is kept, by default next to the dump as
+    /// FILE.index.sqlite
+    #[arg(long, value_name = "INDEX", requires = "dump")]
+    dump_index: Option<PathBuf>,
+
+    /// Of the shortest paths, find the one whose links appear earliest in
+    /// their articles
+    #[arg(
+        long,
+        conflicts_with_all = ["api", "bidirectional", "checkpoint", "resume", "db", "tui"]
+    )]
+    weighted: bool,
+
+    /// Output format of the paths found
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Write the paths found to FILE instead of stdout, replacing it
+    #[arg(long, value_name = "FILE", conflicts_with = "interactive")]
+    output_file: Option<PathBuf>,
+
+    /// Add to the file of --output-file instead of replacing it
+    #[arg(long, requires = "output_file")]
+    append: bool,
+
+    /// Events written with --format ndjson, separated by commas
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Event::Start, Event::Expand, Event::Found]
+    )]
+    events: Vec<Event>,
+
+    /// Write the explored articles and links to FILE as a GraphViz DOT graph
+    #[arg(long, value_name = "FILE")]
+    export_graph: Option<PathBuf>,
+
+    /// Language code of the Wikipedia edition to search
+    #[arg(short, long, value_name = "CODE", default_value = DEFAULT_LANG)]
+    lang: String,
+
+    /// Search the wiki at URL instead of Wikipedia, like a mirror or another
+    /// MediaWiki installation serving its API at /w/api.php
+    #[arg(long, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Path the wiki serves articles under, before their title
+    /// [default: /wiki/]
+    #[arg(long, value_name = "PREFIX")]
+    wiki_prefix: Option<String>,
+
+    /// Namespace to follow links to, by canonical name or ID, like Category
+    /// or 14 (can be repeated; 0, the articles, by default). Main or 0 adds
+    /// the articles back.
+    #[arg(long, value_name = "NS", conflicts_with = "dump")]
+    namespace: Vec<String>,
+
+    /// Print the supported language codes
+    #[arg(long)]
+    list_langs: bool,
+
+    /// Don't follow the links of disambiguation pages
+    #[arg(long)]
+    no_disambig: bool,
+
+    /// Don't follow the links of articles with more than N of them, like
+    /// those of countries and wars
+    #[arg(long, value_name = "N")]
+    max_fan_out: Option<usize>,
+
+    /// Don't follow the links of articles whose HTML is smaller than BYTES,
+    /// likely stubs (not with --api or --dump)
+    #[arg(long, value_name = "BYTES", conflicts_with_all = ["api", "dump"])]
+    min_article_size: Option<usize>,
+
+    /// Keep redirects in paths as articles linking to their target, instead
+    /// of replacing them by it so that they don't count as a link
+    #[arg(long)]
+    include_redirects: bool,
+
+    /// Follow the links of each article in an order shuffled with SEED
+    /// instead of that of the article, so that edits moving links around
+    /// don't change the path found
+    #[arg(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Only enter articles in the category CATEGORY (can be repeated, to
+    /// allow any of them), besides the start and end; costs a request for
+    /// every 50 articles linked to
+    #[arg(long, value_name = "CATEGORY")]
+    require_category: Vec<String>,
+
+    /// Also allow the subcategories of --require-category, down to N levels
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "require_category"
+    )]
+    category_depth: u32,
+
+    /// Don't follow the links in these sections of an article, separated by
+    /// commas (not with --api or --dump)
+    #[arg(
+        long,
+        value_name = "TITLES",
+        value_delimiter = ',',
+        default_values = DEFAULT_SKIP_SECTIONS
+    )]
+    skip_sections: Vec<String>,
+
+    /// Follow the links in every section of an article
+    #[arg(long, conflicts_with = "skip_sections")]
+    all_sections: bool,
+
+    /// Only follow the links in the introduction of an article, before its
+    /// first section (not with --api or --dump)
+    #[arg(long, conflicts_with_all = ["api", "dump"])]
+    intro_only: bool,
+
+    /// Follow the links in an article's infobox before its other links
+    /// (with --weighted, count them as the closest; not with --api)
+    #[arg(long, conflicts_with = "api")]
+    prioritize_infobox: bool,
+
+    /// Expand the articles linked to from a "See also" section before the
+    /// others of their depth, following those links even with
+    /// --skip-sections (breadth-first search only; not with --api or --dump)
+    #[arg(long, conflicts_with_all = ["api", "dump", "bidirectional", "weighted"])]
+    prioritize_see_also: bool,
+
+    /// Don't show a progress bar
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Check that each article of the paths found links to the next, and
+    /// search again between those that don't; paths are printed once
+    /// checked
+    #[arg(long, conflicts_with_all = ["batch", "interactive"])]
+    verify_path: bool,
+
+    /// Once the search is over, draw how many articles each depth of a
+    /// breadth-first search had
+    #[arg(long)]
+    histogram: bool,
+
+    /// Once the search is over, count the paths found of each length, as
+    /// "path_length_distribution" with --format json or ndjson.
+    /// Breadth-first paths are all as short, so lengths only vary with
+    /// other algorithms or several END.
+    #[arg(
+        long,
+        requires = "all",
+        conflicts_with_all = ["batch", "compare", "count_only"]
+    )]
+    depth_histogram: bool,
+
+    /// Save the search state to FILE after each depth
+    #[arg(long, value_name = "FILE", conflicts_with = "bidirectional")]
+    checkpoint: Option<PathBuf>,
+
+    /// Resume the search saved in FILE with --checkpoint
+    #[arg(long, value_name = "FILE", conflicts_with = "bidirectional")]
+    resume: Option<PathBuf>,
+
+    /// Never pass through ARTICLE (can be repeated)
+    #[arg(long, value_name = "ARTICLE")]
+    forbidden: Vec<String>,
+
+    /// Never pass through the articles whose titles, with underscores for
+    /// spaces, match the regular expression REGEX, like "^List_of" (can be
+    /// repeated)
+    #[arg(long, value_name = "REGEX")]
+    skip_pattern: Vec<String>,
+
+    /// Only pass through the articles whose titles match REGEX, or any of
+    /// them when repeated; paths may become impossible to find
+    #[arg(long, value_name = "REGEX")]
+    allow_pattern: Vec<String>,
+
+    /// Never pass through the articles listed in FILE, one per line
+    #[arg(long, value_name = "FILE")]
+    forbidden_file: Option<PathBuf>,
+
+    /// Search strategy
+    #[arg(long, value_enum, default_value_t = Algorithm::Bfs)]
+    algorithm: Algorithm,
+
+    /// What following a link costs with --algorithm dijkstra (can be
+    /// repeated, multiplying the weights; uniform by default)
+    #[arg(long, value_enum, value_name = "NAME")]
+    weight_fn: Vec<WeightFn>,
+
+    /// Let the A* heuristic outweigh path length: fewer requests, but the
+    /// path found may not be the shortest
+    #[arg(long)]
+    suboptimal_ok: bool,
+
+    /// Remember the articles expanded in each iteration of IDDFS and skip
+    /// them further from START, for memory that grows with the articles
+    /// expanded (only with --algorithm iddfs)
+    #[arg(long)]
+    transposition_table: bool,
+
+    /// Look the articles of each depth up 50 at a time before expanding
+    /// them, skipping those that don't exist (worth it with --api, only
+    /// with --algorithm bfs)
+    #[arg(long, conflicts_with_all = ["bidirectional", "weighted"])]
+    prevalidate: bool,
+
+    /// Move the articles found to a temporary file once they take more than
+    /// MB megabytes of memory, reading them back as needed; the hash of each
+    /// title stays in memory (only with --algorithm bfs)
+    #[arg(
+        long,
+        value_name = "MB",
+        conflicts_with_all = ["bidirectional", "weighted", "checkpoint", "db", "tui", "prioritize_see_also"]
+    )]
+    max_memory: Option<usize>,
+
+    /// Expand at most N articles of each depth, the first ones found or,
+    /// with --seed, a random sample, warning about those left out (only
+    /// with --algorithm bfs)
+    #[arg(
+        long,
+        value_name = "N",
+        visible_alias = "article-limit-per-level",
+        conflicts_with_all = ["bidirectional", "weighted"]
+    )]
+    level_limit: Option<usize>,
+
+    /// Let A* prefer articles with many page views, WEIGHT setting how
+    /// much: the path found is more like one people would take, but not
+    /// the shortest, and every candidate article costs a request
+    #[arg(long, value_name = "WEIGHT")]
+    popularity_weight: Option<f64>,
+
+    /// Pass through ARTICLE on the way to END (can be repeated, in order)
+    #[arg(
+        long,
+        value_name = "ARTICLE",
+        conflicts_with_all = ["all", "checkpoint", "resume", "db"]
+    )]
+    via: Vec<String>,
+
+    /// Store the search in the SQLite database FILE after each depth, and
+    /// resume an unfinished search for the same articles stored there. The
+    /// length, categories and fetch time of the articles expanded are kept
+    /// too.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["bidirectional", "resume"])]
+    db: Option<PathBuf>,
+
+    /// Search with bfs, iddfs and bidirectional bfs in turn, then print how
+    /// long each path is and what it cost; with --cache-dir, later searches
+    /// reuse the pages cached by the first
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "algorithm",
+            "bidirectional",
+            "weighted",
+            "via",
+            "batch",
+            "interactive",
+            "tui",
+            "checkpoint",
+            "resume",
+            "db",
+            "format",
+            "count_only",
+            "verify_path",
+            "export_graph",
+        ]
+    )]
+    compare: bool,
+
+    /// Search each pair of articles in FILE, given as START<tab>END on each
+    /// line, then print a summary of the results
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["start", "end", "resume", "tui", "export_graph"]
+    )]
+    batch: Option<PathBuf>,
+
+    /// Read START END pairs at a prompt and search each, reusing the
+    /// connections, rate limit and fetched links (type help for commands)
+    #[arg(
+        long,
+        conflicts_with_all = ["start", "end", "batch", "resume", "checkpoint", "tui", "export_graph"]
+    )]
+    interactive: bool,
+
+    /// Start from a random article, printed before the search begins; only
+    /// END is given
+    #[arg(long, conflicts_with_all = ["resume", "batch", "interactive"])]
+    random_start: bool,
+
+    /// Search for a random article, printed before the search begins; only
+    /// START is given
+    #[arg(long, conflicts_with_all = ["resume", "batch", "interactive"])]
+    random_end: bool,
+
+    /// Search N pairs of --batch at once, sharing the workers
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "batch")]
+    batch_parallel: usize,
+
+    /// Read default options from FILE instead of
+    /// ~/.config/wiki-path/config.toml
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Show the search in a full-terminal interface instead of printing
+    /// progress (q stops the search, s saves a checkpoint), with the lead
+    /// image of the selected article on terminals that draw iTerm2 inline
+    /// images, like iTerm2 and WezTerm, and its URL on others
+    #[arg(long, conflicts_with_all = ["verbose", "bidirectional", "via"])]
+    tui: bool,
+
+    /// User-Agent header sent with every request
+    #[arg(long, value_name = "STRING", default_value = DEFAULT_USER_AGENT)]
+    user_agent: String,
+
+    /// Give up on a request after SECS seconds, 0 to wait forever
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT_SECS)]
+    timeout: f64,
+
+    /// Search even if END or a waypoint doesn't exist
+    #[arg(long)]
+    force: bool,
+
+    /// Don't check that START, END and the waypoints exist before searching
+    #[arg(long)]
+    skip_precheck: bool,
+
+    /// Retry a request up to N times when the server is overloaded (429 or
+    /// 5xx), waiting longer each time
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+
+    /// Wait at most SECS seconds before retrying a request, even if the
+    /// server asks for longer
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_MAX_RETRY_WAIT_SECS)]
+    max_retry_wait: f64,
+
+    /// Send requests through the proxy at URL (http://, https:// or
+    /// socks5://, credentials as user:pass@host). Without it, HTTPS_PROXY
+    /// and ALL_PROXY are used if set.
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Print statistics for each depth of a breadth-first search to stderr
+    #[arg(long)]
+    stats: bool,
+
+    /// Save fetched pages in DIR and reuse them on later runs
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Refetch pages cached more than HOURS ago, unless the server answers
+    /// that they didn't change
+    #[arg(long, value_name = "HOURS", default_value_t = DEFAULT_CACHE_TTL_HOURS)]
+    cache_ttl: f64,
+
+    /// Print the options in effect as a config file
+    #[arg(long)]
+    print_config: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Print a shell completion script
+    #[command(after_help = COMPLETIONS_HELP)]
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+    },
+    /// Answer searches over HTTP, sharing the requests and fetched links
+    /// between them (the other options apply to every search)
+    #[command(after_help = SERVE_HELP)]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = DEFAULT_SERVE_HOST)]
+        host: String,
+        /// Port to listen on
+        #[arg(short, long, default_value_t = DEFAULT_SERVE_PORT)]
+        port: u16,
+    },
+    /// Search like without a subcommand, and save the shortest path found
+    /// to a file for load-path
+    SavePath {
+        /// File to save the path to, as JSON
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// Articles to start from, separated by commas
+        start: String,
+        /// Articles to search for, separated by commas
+        #[arg(required = true, num_args = 1.., value_delimiter = ',')]
+        end: Vec<String>,
+    },
+    /// Print a path saved by save-path, and check that each of its articles
+    /// still links to the next (with --verify-path, searching again between
+    /// those that don't)
+    LoadPath {
+        /// File the path was saved to
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+        /// Only print the path
+        #[arg(long)]
+        no_verify: bool,
+    },
 }
 
-fn main() {
-    let c = Cli::parse();
+const DEFAULT_SERVE_HOST: &str = "127.0.0.1";
 
-    let start_time = Instant::now();
+const DEFAULT_SERVE_PORT: u16 = 8080;
 
-    let req_wait = Duration::from_secs_f32(REQ_WAIT_SECS);
-    let mut prev_req = Instant::now()
-        .checked_sub(req_wait)
-        .unwrap_or_else(|| Instant::now());
+const SERVE_HELP: &str = "\
+Endpoints:
+  POST /search       Search from {\"start\": \"...\", \"end\": \"...\", \"max_depth\": 10}
+                     (max_depth optional, at most --max-depth), answering the
+                     shortest path like --format json, or {\"error\": \"...\"}
+  GET /health        {\"status\": \"ok\"}
+  GET /cache/stats   Articles kept in memory, the times they were reused, and
+                     the requests sent by all searches";
 
-    let mut articles = vec![String::new(), c.start];
-    let mut article_parent = HashMap::from([(1, 0)]);
+const COMPLETIONS_HELP: &str = "\
+Installing:
+  bash        wiki-path completions bash > ~/.local/share/bash-completion/completions/wiki-path
+  zsh         wiki-path completions zsh > ~/.zsh/completions/_wiki-path
+              (with ~/.zsh/completions in your fpath)
+  fish        wiki-path completions fish > ~/.config/fish/completions/wiki-path.fish
+  powershell  wiki-path completions powershell >> $PROFILE
+  elvish      wiki-path completions elvish >> ~/.config/elvish/rc.elv";
 
-    let mut curr_idx = 0;
-    let mut level_len;
-    let mut next_level_len = 1;
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    A path was found
+  1    No path was found, or the search couldn't run
+  2    --max-articles was reached
+  3    --time-limit was reached
+  4    Wikipedia kept refusing requests
+  130  The search was stopped from the TUI
+With --count-only, a search that finds a path exits with its length instead.";
 
-    for depth in 0..(c.max_depth + 1) {
-        level_len = next_level_len;
-        next_level_len = 0;
+const REPL_PROMPT: &str = "wiki-path> ";
 
-        let end_idx = curr_idx + level_len;
-        while curr_idx < end_idx {
-            curr_idx += 1;
+const REPL_HELP: &str = "\
+START END     Search from START to END; either may list several articles
+              separated by commas, and titles with spaces can be quoted
+help          Show this help
+config        Show the options in effect
+cache stats   Show the articles kept in memory and the requests sent
+quit          Leave (also Ctrl-D)
 
-            let article = &articles[curr_idx];
+Ctrl-C stops a search without leaving.
+";
 
-            if c.verbose {
-                println!("{} {}", article, depth);
+/// Declares `Config` with a field for each listed `Cli` option. Options
+/// given on the command line take precedence over the config file.
+macro_rules! config {
+    (
+        values { $($(#[$meta:meta])* $field:ident: $ty:ty,)* }
+        optional { $($(#[$opt_meta:meta])* $opt_field:ident: $opt_ty:ty,)* }
+    ) => {
+        /// Default options read from a TOML file
+        #[derive(Deserialize, Serialize, Default)]
+        #[serde(deny_unknown_fields, rename_all = "kebab-case")]
+        struct Config {
+            $(
+                $(#[$meta])*
+                #[serde(skip_serializing_if = "Option::is_none")]
+                $field: Option<$ty>,
+            )*
+            $(
+                $(#[$opt_meta])*
+                #[serde(skip_serializing_if = "Option::is_none")]
+                $opt_field: Option<$opt_ty>,
+            )*
+        }
+
+        impl Config {
+            fn from_cli(c: &Cli) -> Self {
+                Config {
+                    $($field: Some(c.$field.clone()),)*
+                    $($opt_field: c.$opt_field.clone(),)*
+                }
+            }
+
+            fn apply(self, c: &mut Cli, matches: &clap::ArgMatches) {
+                let on_command_line =
+                    |id| matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine);
+
+                $(
+                    if let Some(value) = self.$field {
+                        if !on_command_line(stringify!($field)) {
+                            c.$field = value;
+                        }
+                    }
+                )*
+                $(
+                    if self.$opt_field.is_some() && !on_command_line(stringify!($opt_field)) {
+                        c.$opt_field = self.$opt_field;
+                    }
+                )*
             }
+        }
+    };
+}
+
+config! {
+    values {
+        verbose: bool,
+        quiet: bool,
+        max_depth: u32,
+        all: bool,
+        #[serde(rename = "async")]
+        concurrent: bool,
+        workers: usize,
+        bidirectional: bool,
+        api: bool,
+        mobile_api: bool,
+        interwiki: Vec<String>,
+        weighted: bool,
+        format: Format,
+        events: Vec<Event>,
+        lang: String,
+        no_disambig: bool,
+        include_redirects: bool,
+        require_category: Vec<String>,
+        category_depth: u32,
+        skip_sections: Vec<String>,
+        all_sections: bool,
+        intro_only: bool,
+        prioritize_infobox: bool,
+        prioritize_see_also: bool,
+        no_progress: bool,
+        stats: bool,
+        histogram: bool,
+        depth_histogram: bool,
+        tui: bool,
+        forbidden: Vec<String>,
+        skip_pattern: Vec<String>,
+        allow_pattern: Vec<String>,
+        algorithm: Algorithm,
+        weight_fn: Vec<WeightFn>,
+        namespace: Vec<String>,
+        suboptimal_ok: bool,
+        transposition_table: bool,
+        prevalidate: bool,
+        cache_ttl: f64,
+        user_agent: String,
+        timeout: f64,
+        max_retries: u32,
+        max_retry_wait: f64,
+    }
+    optional {
+        max_articles: usize,
+        max_memory: usize,
+        level_limit: usize,
+        min_length: usize,
+        time_limit: f64,
+        parallel_parse: usize,
+        max_fan_out: usize,
+        min_article_size: usize,
+        seed: u64,
+        popularity_weight: f64,
+        proxy: String,
+        base_url: String,
+        wiki_prefix: String,
+        cache_dir: PathBuf,
+        dump: PathBuf,
+        dump_index: PathBuf,
+        export_graph: PathBuf,
+        forbidden_file: PathBuf,
+    }
+}
 
-            // Build request
-            let url = format!("https://en.wikipedia.org/wiki/{}", article);
+impl Config {
+    /// Location of the config file when --config isn't given
+    fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(config_home.join("wiki-path").join("config.toml"))
+    }
 
-            let client = rw::blocking::Client::new();
-            let request = client.get(&url);
+    /// Reads the config file at `path`, or at the default location if it
+    /// exists
+    fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Config::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
 
-            // Rate-limit
-            let elapsed = prev_req.elapsed();
-            if elapsed < req_wait {
-                thread::sleep(req_wait - elapsed);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Config::default())
             }
-            prev_req = Instant::now();
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        };
 
-            // Send request
-            let res = match request.send() {
-                Ok(res) => res,
-                Err(err) => {
-                    eprintln!("{}", err);
-                    continue;
-                }
-            };
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
 
-            let body = match res.text() {
-                Ok(body) => body,
-                Err(err) => {
-                    eprintln!("{}", err);
-                    continue;
-                }
+#[derive(clap::ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    /// Human-readable text
+    Text,
+    /// One JSON object per line
+    Json,
+    /// One JSON object per line for each event of the search, see --events
+    Ndjson,
+    /// Markdown list linking to each article
+    Markdown,
+    /// Cytoscape.js elements of the articles and links explored, printed
+    /// once the search is over, with the links of the paths marked onPath
+    Cytoscape,
+}
+
+#[derive(clap::ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Event {
+    /// The search starts from an article
+    Start,
+    /// The links of an article are fetched
+    Expand,
+    /// A path is found
+    Found,
+}
+
+/// A line of --format ndjson. These fields are kept as they are so that
+/// consumers don't break:
+///
+/// - `{"event":"start","article":"Philosophy","depth":0}`
+/// - `{"event":"expand","article":"Aristotle","depth":1}`
+/// - `{"event":"found","path":["Philosophy",...],"length":3,"elapsed_ms":500}`
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum EventOutput<'a> {
+    Start {
+        article: &'a str,
+        depth: u32,
+    },
+    Expand {
+        article: &'a str,
+        depth: u32,
+    },
+    Found {
+        path: &'a [String],
+        length: usize,
+        elapsed_ms: u128,
+    },
+}
+
+impl EventOutput<'_> {
+    fn print(&self) {
+        outln!("{}", serde_json::to_string(self).unwrap());
+    }
+}
+
+/// A line of --verbose --format json, for each article expanded:
+/// `{"article":"Philosophy","depth":3,"parent":"Aristotle","fetch_ms":210}`.
+/// `parent` is null for the start articles, and `fetch_ms` for the links
+/// that weren't fetched from the article HTML.
+#[derive(Serialize)]
+struct ExpandOutput<'a> {
+    article: &'a str,
+    depth: u32,
+    parent: Option<&'a str>,
+    fetch_ms: Option<u128>,
+}
+
+#[derive(Serialize)]
+struct PathOutput<'a> {
+    path: &'a [String],
+    length: usize,
+    elapsed_ms: u128,
+    stats: TrafficOutput,
+    frontier_sizes: &'a [usize],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct TrafficOutput {
+    requests: usize,
+    bytes: u64,
+}
+
+/// `bytes` in the largest unit that keeps it above 1
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn print_path(path: &wiki_path::Path, format: Format, wiki: &Wiki) {
+    let elapsed = path.elapsed;
+    let elapsed_sdur = jiff::SignedDuration::from_secs_f64(elapsed.as_secs_f64());
+
+    match format {
+        Format::Text => {
+            let titles: Vec<_> = path.articles.iter().map(|a| normalize_display(a)).collect();
+            outln!("Path: {:?}", titles);
+            outln!("Length: {}", path.articles.len());
+            if let Some(weight) = path.weight {
+                outln!("Weight: {}", weight);
+            }
+            outln!("Took {elapsed_sdur:#}");
+        }
+        Format::Markdown => {
+            for (i, article) in path.articles.iter().enumerate() {
+                outln!(
+                    "{}. [{}]({})",
+                    i + 1,
+                    normalize_display(article),
+                    wiki.url(article)
+                );
+            }
+            // Also keeps the lists of --all apart
+            outln!();
+            match path.weight {
+                Some(weight) => outln!(
+                    "Length: {}, weight: {}, took {elapsed_sdur:#}",
+                    path.articles.len(),
+                    weight
+                ),
+                None => outln!("Length: {}, took {elapsed_sdur:#}", path.articles.len()),
+            }
+            outln!();
+        }
+        Format::Json => {
+            let output = PathOutput {
+                path: &path.articles,
+                length: path.articles.len(),
+                elapsed_ms: elapsed.as_millis(),
+                stats: TrafficOutput {
+                    requests: path.traffic.requests,
+                    bytes: path.traffic.bytes,
+                },
+                frontier_sizes: &path.frontier_sizes,
+                weight: path.weight,
             };
+            outln!("{}", serde_json::to_string(&output).unwrap());
+        }
+        Format::Ndjson => EventOutput::Found {
+            path: &path.articles,
+            length: path.articles.len(),
+            elapsed_ms: elapsed.as_millis(),
+        }
+        .print(),
+        // Paths are marked in the graph instead
+        Format::Cytoscape => {}
+    }
+}
 
-            let document = sc::Html::parse_document(&body);
-            let selector = sc::Selector::parse("a[href]").unwrap();
+/// Prints the length of `path` alone for --count-only, as an object in the
+/// JSON formats
+fn print_length(path: &wiki_path::Path, format: Format) {
+    let length = path.articles.len();
+    match format {
+        Format::Json | Format::Ndjson | Format::Cytoscape => {
+            outln!("{}", serde_json::json!({ "length": length }))
+        }
+        Format::Text | Format::Markdown => outln!("{}", length),
+    }
+}
 
-            for element in document.select(&selector) {
-                if let Some(href) = element.value().attr("href") {
-                    if let Some(mut name) = href.strip_prefix("/wiki/") {
-                        // Remove #fragments
-                        if let Some(idx) = name.find('#') {
-                            name = &name[..idx];
-                        }
-                        // Exclude "Main_Page" or Special: / Talk: etc
-                        if name != "Main_Page" && !name.contains(':') {
-                            let new_article = name.to_string();
+/// Whether the events of --format ndjson include `event`. Paths are always
+/// printed in the other formats, but for --format cytoscape which prints a
+/// graph instead.
+fn shows(c: &Cli, event: Event) -> bool {
+    match c.format {
+        Format::Ndjson => c.events.contains(&event),
+        Format::Cytoscape => false,
+        _ => true,
+    }
+}
 
-                            if !articles.contains(&new_article) {
-                                articles.push(new_article);
-                                article_parent.insert(articles.len() - 1, curr_idx);
+/// The wiki searched, with --base-url and --wiki-prefix
+fn wiki(c: &Cli) -> Wiki {
+    let wiki = match &c.base_url {
+        Some(url) => Wiki::with_base_url(&c.lang, url),
+        None => Wiki::new(&c.lang),
+    };
+    match &c.wiki_prefix {
+        Some(prefix) => wiki.with_article_path(prefix),
+        None => wiki,
+    }
+}
 
-                                next_level_len += 1;
+/// Prints the paths found by the searcher as they come, and with --format
+/// ndjson each article expanded
+fn with_printers(c: &Cli, mut builder: BfsSearcherBuilder) -> BfsSearcherBuilder {
+    let format = c.format;
 
-                                if name == c.end {
-                                    let elapsed = start_time.elapsed();
+    // Checked paths are printed once the search is over
+    if c.count_only && !c.verify_path {
+        builder = builder.on_path(move |path| print_length(path, format));
+    } else if shows(c, Event::Found) && !c.verify_path {
+        let wiki = wiki(c);
+        builder = builder.on_path(move |path| print_path(path, format, &wiki));
+    }
+    if format == Format::Ndjson && c.events.contains(&Event::Expand) {
+        builder =
+            builder.on_expand(|article, depth| EventOutput::Expand { article, depth }.print());
+    }
+    if format == Format::Json && c.verbose {
+        builder = builder.on_event(|event| {
+            if let SearchEvent::Expanded {
+                article,
+                depth,
+                parent,
+                fetch_time,
+            } = event
+            {
+                let line = ExpandOutput {
+                    article,
+                    depth,
+                    parent,
+                    fetch_ms: fetch_time.map(|time| time.as_millis()),
+                };
+                outln!("{}", serde_json::to_string(&line).unwrap());
+            }
+            ControlFlow::Continue(())
+        });
+    }
+    builder
+}
+
+/// Prints the start event of --format ndjson for each of `start`
+fn print_start(c: &Cli, start: &[String]) {
+    if c.format == Format::Ndjson && c.events.contains(&Event::Start) {
+        for article in start {
+            EventOutput::Start { article, depth: 0 }.print();
+        }
+    }
+}
+
+/// A search listed in a --batch file
+struct Pair {
+    /// Line of the file, from 1
+    line: usize,
+    start: Vec<String>,
+    end: Vec<String>,
+}
 
-                                    let mut path = Vec::new();
+/// How the search of a `Pair` went, for the summary of a batch
+struct PairResult {
+    pair: String,
+    /// Length of the shortest path found
+    length: Option<usize>,
+    elapsed: Duration,
+}
 
-                                    let mut current = articles.len() - 1;
-                                    while current != 0 {
-                                        path.push(&articles[current]);
-                                        current = article_parent[&current];
-                                    }
+/// Parses the "START<tab>END" lines of a --batch file, skipping blank lines
+/// and those starting with #. Both may list several articles separated by
+/// commas, like on the command line.
+fn read_batch(file: &Path) -> Result<Vec<Pair>, String> {
+    let text = fs::read_to_string(file).map_err(|err| format!("{}: {}", file.display(), err))?;
 
-                                    path.reverse();
+    let mut pairs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
 
-                                    println!("Path: {:?}", path);
-                                    println!("Length: {}", path.len());
+        let pair = line
+            .split_once('\t')
+            .map(|(start, end)| (parse_titles(start), parse_titles(end)))
+            .filter(|(start, end)| !start.is_empty() && !end.is_empty());
+        let Some((start, end)) = pair else {
+            return Err(format!(
+                "{}:{}: expected START<tab>END",
+                file.display(),
+                i + 1
+            ));
+        };
 
-                                    let elapsed_sdur =
-                                        jiff::SignedDuration::from_secs_f64(elapsed.as_secs_f64());
-                                    println!("Took {elapsed_sdur:#}");
+        pairs.push(Pair {
+            line: i + 1,
+            start,
+            end,
+        });
+    }
 
-                                    if !c.all {
-                                        return;
-                                    }
-                                }
-                            }
-                        }
+    Ok(pairs)
+}
+
+/// Splits a list of articles separated by commas, as typed on the command
+/// line or copied from a URL
+fn parse_titles(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .map(|title| normalize_title(&decode_title(title)))
+        .collect()
+}
+
+/// Searches each of `pairs`, --batch-parallel at a time, and prints a
+/// summary once they're all done. Returns false if a pair has no path.
+async fn run_batch(c: &Cli, pairs: Vec<Pair>, forbidden: &HashSet<String>) -> bool {
+    // Only sends requests for the searches of the pairs, so that they share
+    // its workers and rate limit
+    let shared = match searcher_builder(
+        c,
+        Vec::<String>::new(),
+        Vec::<String>::new(),
+        HashSet::new(),
+    )
+    .build()
+    {
+        Ok(searcher) => searcher,
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let results: Vec<_> = stream::iter(pairs)
+        .map(|pair| search_pair(c, &shared, forbidden, pair))
+        .buffered(c.batch_parallel.max(1))
+        .collect()
+        .await;
+
+    if !c.quiet {
+        print_batch_summary(&results);
+    }
+
+    results.iter().all(|result| result.length.is_some())
+}
+
+async fn search_pair(
+    c: &Cli,
+    shared: &BfsSearcher,
+    forbidden: &HashSet<String>,
+    pair: Pair,
+) -> PairResult {
+    let names = |titles: &[String]| {
+        let titles: Vec<_> = titles
+            .iter()
+            .map(|title| normalize_display(title))
+            .collect();
+        titles.join(",")
+    };
+    let name = format!("{} -> {}", names(&pair.start), names(&pair.end));
+    let builder = searcher_builder(c, pair.start.clone(), pair.end.clone(), forbidden.clone())
+        .share_requests(shared);
+    let mut builder = with_printers(c, builder);
+    // Progress bars of searches running alongside would draw over each other
+    if c.batch_parallel > 1 {
+        builder = builder.progress(false);
+    }
+    // Each pair has its own checkpoint, which a rerun of the batch resumes
+    if let Some(file) = &c.checkpoint {
+        let mut name = file.as_os_str().to_owned();
+        name.push(format!(".{}", pair.line));
+        let file = PathBuf::from(name);
+
+        if let Ok(state) = BfsState::load(&file) {
+            if state.start == pair.start && state.end == pair.end {
+                builder = builder.resume(state);
+            }
+        }
+        builder = builder.checkpoint(file);
+    }
+
+    let started = Instant::now();
+    let mut result = PairResult {
+        pair: name,
+        length: None,
+        elapsed: Duration::ZERO,
+    };
+
+    let mut searcher = match builder.build() {
+        Ok(searcher) => searcher,
+        Err(err) => {
+            error!("{}: {}", result.pair, err);
+            return result;
+        }
+    };
+
+    if c.skip_precheck || preflight(&searcher, &pair.start, &pair.end, &c.via).await || c.force {
+        print_start(c, &pair.start);
+        match searcher.search().await {
+            Ok(paths) => result.length = paths.iter().map(|path| path.articles.len()).min(),
+            Err(err) => error!("{}: {}", result.pair, err),
+        }
+    }
+
+    match searcher.limit_reached() {
+        Some(Limit::Articles) => warn!("{}: Search limit reached", result.pair),
+        Some(Limit::Time) => warn!("{}: Time limit reached", result.pair),
+        // Searches have no `on_event`
+        Some(Limit::Stopped) | None => {}
+    }
+
+    result.elapsed = started.elapsed();
+    result
+}
+
+/// Runs the searches typed at the prompt of --interactive until `quit` or
+/// the end of input. They all send their requests through one searcher,
+/// which keeps the links fetched in memory.
+async fn run_repl(c: &Cli, forbidden: &HashSet<String>) -> Result<(), String> {
+    let shared = searcher_builder(
+        c,
+        Vec::<String>::new(),
+        Vec::<String>::new(),
+        HashSet::new(),
+    )
+    .memory_cache(true)
+    .build()
+    .map_err(|err| err.to_string())?;
+    let mut editor = DefaultEditor::new().map_err(|err| err.to_string())?;
+
+    if !c.quiet {
+        eprintln!("Type START END to search, help for the other commands");
+    }
+
+    loop {
+        let line = match task::block_in_place(|| editor.readline(REPL_PROMPT)) {
+            Ok(line) => line,
+            // At the prompt, Ctrl-C only clears the line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(err) => return Err(err.to_string()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        match line {
+            "quit" | "exit" => return Ok(()),
+            "help" => print!("{}", REPL_HELP),
+            "config" => match toml::to_string(&Config::from_cli(c)) {
+                Ok(config) => print!("{}", config),
+                Err(err) => error!("{}", err),
+            },
+            "cache stats" => {
+                let stats = shared.cache_stats().unwrap_or_default();
+                let traffic = shared.traffic();
+                println!(
+                    "{} articles in memory, used {} times",
+                    stats.articles, stats.hits
+                );
+                println!(
+                    "{} HTTP requests, {} downloaded",
+                    traffic.requests,
+                    format_bytes(traffic.bytes)
+                );
+            }
+            _ => match split_repl_line(line) {
+                Some((start, end)) => {
+                    tokio::select! {
+                        () = repl_search(c, &shared, forbidden, start, end) => {}
+                        _ = signal::ctrl_c() => eprintln!("Search interrupted"),
                     }
                 }
+                None => error!("expected START END, type help for the commands"),
+            },
+        }
+    }
+}
+
+/// Splits a line typed at the --interactive prompt into its START and END
+/// articles. Words are separated by spaces unless double-quoted.
+fn split_repl_line(line: &str) -> Option<(Vec<String>, Vec<String>)> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            ch if ch.is_whitespace() && !quoted => words.extend(word.take()),
+            ch => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+
+    let [start, end] = <[String; 2]>::try_from(words).ok()?;
+    let (start, end) = (parse_titles(&start), parse_titles(&end));
+    (!start.is_empty() && !end.is_empty()).then_some((start, end))
+}
+
+/// Searches from `start` to `end` at the --interactive prompt, printing the
+/// paths and errors like a search from the command line
+async fn repl_search(
+    c: &Cli,
+    shared: &BfsSearcher,
+    forbidden: &HashSet<String>,
+    start: Vec<String>,
+    end: Vec<String>,
+) {
+    let builder =
+        searcher_builder(c, start.clone(), end.clone(), forbidden.clone()).share_requests(shared);
+    let mut searcher = match with_printers(c, builder).build() {
+        Ok(searcher) => searcher,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        }
+    };
+
+    if !c.skip_precheck && !preflight(&searcher, &start, &end, &c.via).await && !c.force {
+        return;
+    }
+
+    print_start(c, &start);
+    let before = searcher.traffic();
+    let res = searcher.search().await;
+    let paths = res.as_deref().unwrap_or_default();
+
+    // Nothing is sent with --dump
+    if !paths.is_empty() && !c.quiet && c.dump.is_none() {
+        let traffic = searcher.traffic();
+        eprintln!(
+            "{} HTTP requests, {} downloaded",
+            traffic.requests - before.requests,
+            format_bytes(traffic.bytes - before.bytes)
+        );
+    }
+
+    if c.histogram {
+        print_histogram(searcher.frontier_sizes());
+    }
+
+    if c.depth_histogram {
+        print_length_distribution(paths, c.format);
+    }
+
+    if let Err(err) = &res {
+        error!("{}", err);
+    }
+    match searcher.limit_reached() {
+        Some(Limit::Articles) => warn!("Search limit reached"),
+        Some(Limit::Time) if paths.is_empty() => warn!("No path found within time limit"),
+        _ => {}
+    }
+}
+
+/// What `run_server` shares between the searches it runs
+struct Server {
+    c: Cli,
+    forbidden: HashSet<String>,
+    /// Searches share its requests, and so its rate limit and memory cache
+    shared: Mutex<BfsSearcher>,
+}
+
+/// Body of a POST /search
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchRequest {
+    start: String,
+    end: String,
+    max_depth: Option<u32>,
+}
+
+/// Answers searches over HTTP until Ctrl-C is pressed, see SERVE_HELP
+async fn run_server(
+    c: Cli,
+    forbidden: HashSet<String>,
+    host: &str,
+    port: u16,
+) -> Result<(), String> {
+    let shared = searcher_builder(
+        &c,
+        Vec::<String>::new(),
+        Vec::<String>::new(),
+        HashSet::new(),
+    )
+    .memory_cache(true)
+    .build()
+    .map_err(|err| err.to_string())?;
+    let quiet = c.quiet;
+    let server = Arc::new(Server {
+        c,
+        forbidden,
+        shared: Mutex::new(shared),
+    });
+
+    let app = axum::Router::new()
+        .route("/search", routing::post(serve_search))
+        .route("/health", routing::get(serve_health))
+        .route("/cache/stats", routing::get(serve_cache_stats))
+        .with_state(server);
+
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .map_err(|err| format!("{}:{}: {}", host, port, err))?;
+    if !quiet {
+        eprintln!("Listening on http://{}", listener.local_addr().unwrap());
+    }
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// JSON `{"error": message}` answered with `status`
+fn error_response(status: StatusCode, message: impl fmt::Display) -> Response {
+    let body = serde_json::json!({ "error": message.to_string() });
+    (status, axum::Json(body)).into_response()
+}
+
+async fn serve_search(
+    State(server): State<Arc<Server>>,
+    req: Result<axum::Json<SearchRequest>, JsonRejection>,
+) -> Response {
+    let req = match req {
+        Ok(axum::Json(req)) => req,
+        Err(rejection) => return error_response(rejection.status(), rejection.body_text()),
+    };
+    let c = &server.c;
+    let start = parse_titles(&req.start);
+    let end = parse_titles(&req.end);
+    if start.is_empty() || end.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "start and end can't be empty");
+    }
+    let max_depth = req.max_depth.unwrap_or(c.max_depth).min(c.max_depth);
+
+    let builder = {
+        let shared = server.shared.lock().unwrap();
+        searcher_builder(c, start.clone(), end.clone(), server.forbidden.clone())
+            .share_requests(&shared)
+    };
+    let mut searcher = match builder.max_depth(max_depth).progress(false).build() {
+        Ok(searcher) => searcher,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+    };
+
+    // Searching for an article that doesn't exist would go through every
+    // depth
+    match searcher.missing(&end).await {
+        Ok(missing) if !missing.is_empty() => {
+            let err = SearchError::ArticleNotFound(missing[0].clone());
+            return error_response(StatusCode::NOT_FOUND, err);
+        }
+        Ok(_) => {}
+        Err(err) => warn!("{}", err),
+    }
+
+    debug!(
+        "Searching {} -> {}",
+        start
+            .iter()
+            .map(|a| normalize_display(a))
+            .collect::<Vec<_>>()
+            .join(", "),
+        end.iter()
+            .map(|a| normalize_display(a))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let paths = match searcher.search().await {
+        Ok(paths) => paths,
+        Err(err) => {
+            let status = match err {
+                SearchError::DepthExceeded | SearchError::ArticleNotFound(_) => {
+                    StatusCode::NOT_FOUND
+                }
+                SearchError::RateLimitExceeded => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::BAD_GATEWAY,
+            };
+            return error_response(status, err);
+        }
+    };
+
+    let Some(path) = paths.iter().min_by_key(|path| path.articles.len()) else {
+        return error_response(StatusCode::NOT_FOUND, "Search limit reached");
+    };
+    let output = PathOutput {
+        path: &path.articles,
+        length: path.articles.len(),
+        elapsed_ms: path.elapsed.as_millis(),
+        stats: TrafficOutput {
+            requests: path.traffic.requests,
+            bytes: path.traffic.bytes,
+        },
+        frontier_sizes: &path.frontier_sizes,
+        weight: path.weight,
+    };
+    axum::Json(output).into_response()
+}
+
+async fn serve_health() -> Response {
+    axum::Json(serde_json::json!({ "status": "ok" })).into_response()
+}
+
+async fn serve_cache_stats(State(server): State<Arc<Server>>) -> Response {
+    let shared = server.shared.lock().unwrap();
+    let stats = shared.cache_stats().unwrap_or_default();
+    let traffic = shared.traffic();
+    axum::Json(serde_json::json!({
+        "articles": stats.articles,
+        "hits": stats.hits,
+        "requests": traffic.requests,
+        "bytes": traffic.bytes,
+    }))
+    .into_response()
+}
+
+/// Prints the path saved to `file`, and with `verify` checks its hops in the
+/// edition it was found in, returning whether they all still hold. With
+/// --verify-path the path is also searched again where they don't.
+async fn run_load_path(mut c: Cli, file: &Path, verify: bool, forbidden: &HashSet<String>) -> bool {
+    let saved = match PathFile::load(file) {
+        Ok(saved) => saved,
+        Err(err) => {
+            error!("{}: {}", file.display(), err);
+            return false;
+        }
+    };
+    c.lang = saved.lang;
+    let wiki = wiki(&c);
+
+    if !c.quiet {
+        let saved_at =
+            jiff::Timestamp::from_second(saved.saved as i64).unwrap_or(jiff::Timestamp::UNIX_EPOCH);
+        eprintln!("Saved {} from {}", saved_at, wiki.host());
+    }
+    print_path(&saved.path, c.format, &wiki);
+    if !verify {
+        return true;
+    }
+
+    let searcher = match searcher_builder(&c, Vec::new(), Vec::new(), HashSet::new())
+        .progress(false)
+        .build()
+    {
+        Ok(searcher) => searcher,
+        Err(err) => {
+            error!("{}", err);
+            return false;
+        }
+    };
+
+    if c.verify_path {
+        let checked = verified_path(&c, &searcher, &saved.path, forbidden).await;
+        if checked.articles == saved.path.articles {
+            return true;
+        }
+        eprintln!("Path now:");
+        print_path(&checked, c.format, &wiki);
+        return false;
+    }
+
+    match searcher.verify_path(&saved.path.articles).await {
+        Ok(None) => {
+            if !c.quiet {
+                eprintln!("Every article still links to the next");
+            }
+            true
+        }
+        Ok(Some(hop)) => {
+            warn!(
+                "'{}' doesn't link to '{}' anymore",
+                normalize_display(&saved.path.articles[hop]),
+                normalize_display(&saved.path.articles[hop + 1])
+            );
+            false
+        }
+        Err(err) => {
+            error!("Couldn't verify the path: {}", err);
+            false
+        }
+    }
+}
+
+/// Checks each hop of `path` for --verify-path, replacing those that don't
+/// hold by a search between their articles. Hops that can't be checked or
+/// replaced are kept, with a warning.
+async fn verified_path(
+    c: &Cli,
+    searcher: &BfsSearcher,
+    path: &wiki_path::Path,
+    forbidden: &HashSet<String>,
+) -> wiki_path::Path {
+    let mut path = path.clone();
+    let mut replaced = HashSet::new();
+
+    loop {
+        let hop = match searcher.verify_path(&path.articles).await {
+            Ok(Some(hop)) => hop,
+            Ok(None) => return path,
+            Err(err) => {
+                warn!("Couldn't verify the path: {}", err);
+                return path;
+            }
+        };
+        let (from, to) = (path.articles[hop].clone(), path.articles[hop + 1].clone());
+        warn!(
+            "'{}' doesn't link to '{}'",
+            normalize_display(&from),
+            normalize_display(&to)
+        );
+
+        // A search finding the same hop again would never end
+        if !replaced.insert((from.clone(), to.clone())) {
+            return path;
+        }
+
+        let segment = searcher_builder(c, vec![from.clone()], vec![to.clone()], forbidden.clone())
+            .share_requests(searcher)
+            .via(Vec::<String>::new())
+            .graph(false)
+            .progress(false)
+            .build();
+        let segment = match segment {
+            Ok(mut segment) => segment
+                .search()
+                .await
+                .ok()
+                .and_then(|paths| paths.into_iter().next()),
+            Err(_) => None,
+        };
+        let Some(segment) = segment else {
+            warn!(
+                "No other way from '{}' to '{}' found",
+                normalize_display(&from),
+                normalize_display(&to)
+            );
+            return path;
+        };
+
+        path.articles.splice(hop..=hop + 1, segment.articles);
+        // Weighed by the links replaced
+        path.weight = None;
+    }
+}
+
+/// Prints a bar for the size of each depth of a search to stderr, scaled
+/// to the width of the terminal
+fn print_histogram(sizes: &[usize]) {
+    let Some(&max) = sizes.iter().max() else {
+        return;
+    };
+    let columns = terminal::size().map_or(80, |(columns, _)| columns as usize);
+    let label_width = (sizes.len() - 1).to_string().len();
+    // "depth N: [" and " SIZE]" around the bar
+    let width = columns
+        .saturating_sub(label_width + 10 + max.to_string().len() + 2)
+        .max(1);
+
+    for (depth, &size) in sizes.iter().enumerate() {
+        let bar = "█".repeat(size * width / max.max(1));
+        let gap = if bar.is_empty() { "" } else { " " };
+        eprintln!("depth {depth:>label_width$}: [{bar}{gap}{size}]");
+    }
+}
+
+/// Prints how many of `paths` have each length for --depth-histogram, on
+/// stdout as JSON with --format json and ndjson
+fn print_length_distribution(paths: &[wiki_path::Path], format: Format) {
+    let mut counts = BTreeMap::new();
+    for path in paths {
+        *counts.entry(path.articles.len()).or_insert(0) += 1;
+    }
+
+    match format {
+        Format::Json | Format::Ndjson => {
+            outln!(
+                "{}",
+                serde_json::json!({ "path_length_distribution": counts })
+            )
+        }
+        // The graph is alone on stdout
+        Format::Text | Format::Markdown | Format::Cytoscape => {
+            let counts: Vec<_> = counts
+                .iter()
+                .map(|(length, &count)| {
+                    let plural = if count == 1 { "" } else { "s" };
+                    format!("Length {}: {} path{}", length, count, plural)
+                })
+                .collect();
+            eprintln!("{}", counts.join(", "));
+        }
+    }
+}
+
+/// Prints a table of how each pair of a batch went to stderr
+fn print_batch_summary(results: &[PairResult]) {
+    let width = results
+        .iter()
+        .map(|result| result.pair.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("pair".len());
+
+    eprintln!();
+    eprintln!("{:<width$} | length | {:>12} | found", "pair", "time");
+    for result in results {
+        let length = match result.length {
+            Some(length) => length.to_string(),
+            None => "-".to_string(),
+        };
+        let elapsed = jiff::SignedDuration::from_millis(result.elapsed.as_millis() as i64);
+        eprintln!(
+            "{:<width$} | {:>6} | {:>12} | {}",
+            result.pair,
+            length,
+            format!("{:#}", elapsed),
+            if result.length.is_some() { "yes" } else { "no" }
+        );
+    }
+}
+
+/// How a search of --compare went
+struct Comparison {
+    algorithm: &'static str,
+    /// Length of the shortest path found
+    length: Option<usize>,
+    expanded: usize,
+    requests: usize,
+    elapsed: Duration,
+    /// Most memory the articles of the search took at once
+    memory: usize,
+}
+
+/// Runs the searches of --compare one after the other and prints a table
+/// of how they went. Returns false if the articles are missing or the
+/// searches found paths of different lengths.
+async fn run_compare(
+    c: &Cli,
+    start: &[String],
+    end: &[String],
+    forbidden: &HashSet<String>,
+) -> bool {
+    let algorithms = [
+        ("bfs", Algorithm::Bfs, false),
+        ("iddfs", Algorithm::Iddfs, false),
+        ("bidirectional", Algorithm::Bfs, true),
+    ];
+    let mut results = Vec::new();
+
+    for (i, (name, algorithm, bidirectional)) in algorithms.into_iter().enumerate() {
+        let expanded = Arc::new(AtomicUsize::new(0));
+        let counter = expanded.clone();
+        let builder = searcher_builder(c, start.to_vec(), end.to_vec(), forbidden.clone())
+            .algorithm(algorithm)
+            .bidirectional(bidirectional)
+            .on_expand(move |_, _| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        let mut searcher = match builder.build() {
+            Ok(searcher) => searcher,
+            Err(err) => {
+                error!("{}", err);
+                return false;
+            }
+        };
+
+        if i == 0 && !c.skip_precheck && !preflight(&searcher, start, end, &[]).await && !c.force {
+            return false;
+        }
+
+        debug!("Searching with {}", name);
+        let started = Instant::now();
+        let res = searcher.search().await;
+        let elapsed = started.elapsed();
+
+        if let Err(err) = &res {
+            error!("{}: {}", name, err);
+        }
+        results.push(Comparison {
+            algorithm: name,
+            length: res
+                .ok()
+                .and_then(|paths| paths.iter().map(|path| path.articles.len()).min()),
+            expanded: expanded.load(Ordering::Relaxed),
+            requests: searcher.traffic().requests,
+            elapsed,
+            memory: searcher.peak_memory(),
+        });
+    }
+
+    print_comparison(&results);
+
+    let lengths: HashSet<_> = results.iter().filter_map(|result| result.length).collect();
+    if lengths.len() > 1 {
+        warn!("The algorithms found paths of different lengths");
+        return false;
+    }
+    true
+}
+
+fn print_comparison(results: &[Comparison]) {
+    let width = results
+        .iter()
+        .map(|result| result.algorithm.len())
+        .max()
+        .unwrap_or(0)
+        .max("algorithm".len());
+
+    outln!(
+        "{:<width$} | length | articles | requests | {:>12} | {:>10}",
+        "algorithm",
+        "time",
+        "memory"
+    );
+    for result in results {
+        let length = match result.length {
+            Some(length) => length.to_string(),
+            None => "-".to_string(),
+        };
+        let elapsed = jiff::SignedDuration::from_millis(result.elapsed.as_millis() as i64);
+        outln!(
+            "{:<width$} | {:>6} | {:>8} | {:>8} | {:>12} | {:>10}",
+            result.algorithm,
+            length,
+            result.expanded,
+            result.requests,
+            format!("{:#}", elapsed),
+            format_bytes(result.memory as u64)
+        );
+    }
+}
+
+/// A searcher from `start` to `end` set up with the options of `c` that are
+/// the same for every search of a run
+fn searcher_builder(
+    c: &Cli,
+    start: Vec<String>,
+    end: Vec<String>,
+    forbidden: HashSet<String>,
+) -> BfsSearcherBuilder {
+    let mut builder = BfsSearcher::builder(start, end)
+        .max_depth(c.max_depth)
+        .all(c.all)
+        .algorithm(c.algorithm)
+        .bidirectional(c.bidirectional)
+        .weighted(c.weighted)
+        .weight_fns(c.weight_fn.clone())
+        .suboptimal_ok(c.suboptimal_ok)
+        .transposition_table(c.transposition_table)
+        .prevalidate(c.prevalidate)
+        .via(c.via.clone())
+        .forbidden(forbidden)
+        .skip_patterns(c.skip_pattern.clone())
+        .allow_patterns(c.allow_pattern.clone())
+        .no_disambig(c.no_disambig)
+        .include_redirects(c.include_redirects)
+        .require_categories(c.require_category.clone())
+        .category_depth(c.category_depth)
+        .prioritize_infobox(c.prioritize_infobox)
+        .prioritize_see_also(c.prioritize_see_also)
+        .intro_only(c.intro_only)
+        .mobile_api(c.mobile_api)
+        .interwiki(c.interwiki.clone())
+        .skip_sections(if c.all_sections {
+            Vec::new()
+        } else {
+            c.skip_sections.clone()
+        })
+        .lang(c.lang.clone())
+        .api(c.api)
+        .workers(if c.concurrent { c.workers } else { 1 })
+        .max_retries(c.max_retries)
+        .max_retry_wait(Duration::from_secs_f64(c.max_retry_wait))
+        .user_agent(c.user_agent.clone())
+        .timeout((c.timeout > 0.0).then(|| Duration::from_secs_f64(c.timeout)))
+        .graph(c.export_graph.is_some() || c.format == Format::Cytoscape)
+        // Debug logs would be interleaved with the bar
+        .progress(!c.no_progress && !c.verbose && !c.quiet)
+        .stats(c.stats)
+        .tui(c.tui);
+    if let Some(max) = c.max_articles {
+        builder = builder.max_articles(max);
+    }
+    if let Some(mb) = c.max_memory {
+        builder = builder.max_memory(mb * 1024 * 1024);
+    }
+    if let Some(limit) = c.level_limit {
+        builder = builder.level_limit(limit);
+    }
+    if let Some(hops) = c.min_length {
+        builder = builder.min_length(hops);
+    }
+    if let Some(url) = &c.base_url {
+        builder = builder.base_url(url.clone());
+    }
+    if let Some(prefix) = &c.wiki_prefix {
+        builder = builder.wiki_prefix(prefix.clone());
+    }
+    // Unknown namespaces were refused by main
+    if !c.namespace.is_empty() {
+        builder = builder.namespaces(c.namespace.iter().filter_map(|ns| parse_namespace(ns)));
+    }
+    if let Some(max) = c.max_fan_out {
+        builder = builder.max_fan_out(max);
+    }
+    if let Some(seed) = c.seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(bytes) = c.min_article_size {
+        builder = builder.min_article_size(bytes);
+    }
+    if let Some(threads) = c.parallel_parse {
+        builder = builder.parallel_parse(threads);
+    }
+    if let Some(weight) = c.popularity_weight {
+        builder = builder.popularity_weight(weight);
+    }
+    if let Some(secs) = c.time_limit {
+        builder = builder.time_limit(Duration::from_secs_f64(secs.max(0.0)));
+    }
+    if let Some(proxy) = &c.proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    if let Some(dir) = &c.cache_dir {
+        builder = builder.cache(dir, Duration::from_secs_f64(c.cache_ttl.max(0.0) * 3600.0));
+    }
+    if let Some(file) = &c.db {
+        builder = builder.db(file);
+    }
+    if let Some(index) = dump_index(c) {
+        builder = builder.dump(index);
+    }
+    builder
+}
+
+/// Where the index of --dump is kept
+fn dump_index(c: &Cli) -> Option<PathBuf> {
+    let dump = c.dump.as_ref()?;
+    Some(c.dump_index.clone().unwrap_or_else(|| {
+        let mut index = dump.clone().into_os_string();
+        index.push(".index.sqlite");
+        index.into()
+    }))
+}
+
+/// Indexes the --dump file into `index`, unless it was indexed there since
+/// it last changed
+fn prepare_dump(c: &Cli, dump: &Path, index: &Path) -> Result<(), String> {
+    let modified = |file: &Path| fs::metadata(file).and_then(|meta| meta.modified());
+    let dump_modified = modified(dump).map_err(|err| format!("{}: {}", dump.display(), err))?;
+    if modified(index).is_ok_and(|indexed| indexed >= dump_modified) {
+        return Ok(());
+    }
+
+    if !c.quiet {
+        eprintln!(
+            "Indexing {} into {}, later runs will reuse it",
+            dump.display(),
+            index.display()
+        );
+    }
+    let progress = !c.no_progress && !c.verbose && !c.quiet;
+    let articles =
+        index_dump(dump, index, progress).map_err(|err| format!("{}: {}", dump.display(), err))?;
+    if !c.quiet {
+        eprintln!("Indexed {} articles", articles);
+    }
+    Ok(())
+}
+
+/// Sends logs to stderr, debug ones only with --verbose and only errors
+/// with --quiet
+fn init_logging(c: &Cli) {
+    let level = if c.quiet {
+        LevelFilter::ERROR
+    } else if c.verbose {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::WARN
+    };
+
+    // Dependencies only get to warn, their debug logs are about HTTP
+    let filter = Targets::new()
+        .with_default(level.min(LevelFilter::WARN))
+        .with_target("wiki_path", level);
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(io::stderr)
+                .with_ansi(io::stderr().is_terminal())
+                .with_target(false)
+                .without_time(),
+        )
+        .with(filter)
+        .init();
+}
+
+/// Checks that the articles to search from, to and through exist, printing
+/// suggestions for those that don't. Returns false if any is missing.
+async fn preflight(
+    searcher: &BfsSearcher,
+    start: &[String],
+    end: &[String],
+    via: &[String],
+) -> bool {
+    let mut titles: Vec<_> = start.iter().chain(end).chain(via).cloned().collect();
+    titles.sort();
+    titles.dedup();
+
+    let missing = match searcher.missing(&titles).await {
+        Ok(missing) => missing,
+        Err(err) => {
+            // Nothing to go on, the search will tell
+            warn!("{}", err);
+            return true;
+        }
+    };
+
+    let place = match searcher.dump() {
+        Some(_) => "in the dump".to_string(),
+        None => format!("on {}", searcher.wiki().host()),
+    };
+    for title in &missing {
+        let shown = normalize_display(title);
+        match searcher.suggestions(title).await {
+            Ok(suggestions) if !suggestions.is_empty() => {
+                let mut quoted: Vec<_> = suggestions
+                    .iter()
+                    .map(|s| format!("'{}'", normalize_display(s)))
+                    .collect();
+                let last = quoted.pop().unwrap();
+                let alternatives = if quoted.is_empty() {
+                    last
+                } else {
+                    format!("{} or {}", quoted.join(", "), last)
+                };
+                error!(
+                    "article '{}' not found {}. Did you mean {}?",
+                    shown, place, alternatives
+                );
+            }
+            Ok(_) => error!("article '{}' not found {}", shown, place),
+            Err(err) => {
+                error!("article '{}' not found {}", shown, place);
+                warn!("{}", err);
+            }
+        }
+    }
+
+    missing.is_empty()
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = Cli::command().get_matches();
+    let mut c = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    if let Some(Command::Completions { shell }) = c.command {
+        clap_complete::generate(shell, &mut Cli::command(), "wiki-path", &mut io::stdout());
+        return;
+    }
+
+    // The search runs as without the subcommand, the path being saved after
+    let mut save_path = None;
+    if let Some(Command::SavePath { output, start, end }) = &mut c.command {
+        if c.batch.is_some() || c.interactive || c.compare {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "save-path can't be used with --batch, --interactive or --compare",
+                )
+                .exit();
+        }
+        c.start = mem::take(start);
+        c.end = mem::take(end);
+        save_path = Some(mem::take(output));
+    }
+
+    // Logging waits for the config, which may set --verbose or --quiet
+    let res = Config::load(c.config.as_deref()).map(|config| config.apply(&mut c, &matches));
+    c.quiet |= c.count_only;
+    init_logging(&c);
+    if let Err(err) = res {
+        error!("{}", err);
+        process::exit(1);
+    }
+
+    if c.print_config {
+        match toml::to_string(&Config::from_cli(&c)) {
+            Ok(config) => print!("{}", config),
+            Err(err) => {
+                error!("{}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(file) = &c.output_file {
+        let opened = File::options()
+            .create(true)
+            .write(true)
+            .append(c.append)
+            .truncate(!c.append)
+            .open(file);
+        match opened {
+            Ok(opened) => {
+                let _ = OUTPUT_FILE.set(Mutex::new(LineWriter::new(opened)));
+            }
+            Err(err) => {
+                error!("{}: {}", file.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Only draw over an interactive terminal
+    c.tui &= io::stdout().is_terminal();
+
+    if c.tui && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--tui can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.db.is_some() && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--db can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.bidirectional && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--bidirectional can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.popularity_weight.is_some() && c.algorithm != Algorithm::Astar {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--popularity-weight can only be used with --algorithm astar",
+            )
+            .exit();
+    }
+
+    if c.format == Format::Cytoscape && (c.batch.is_some() || c.interactive || c.count_only) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--format cytoscape can't be used with --batch, --interactive or --count-only",
+            )
+            .exit();
+    }
+
+    if c.min_article_size.is_some() && c.algorithm == Algorithm::Astar {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--min-article-size can't be used with --algorithm astar, which uses the API",
+            )
+            .exit();
+    }
+
+    if c.prevalidate && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--prevalidate can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.max_memory.is_some() && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--max-memory can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.level_limit.is_some() && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--level-limit can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.min_length.is_some() && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--min-length can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if c.transposition_table && c.algorithm != Algorithm::Iddfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--transposition-table can only be used with --algorithm iddfs",
+            )
+            .exit();
+    }
+
+    if c.mobile_api && c.algorithm == Algorithm::Astar {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--mobile-api can't be used with --algorithm astar, which uses the API",
+            )
+            .exit();
+    }
+
+    if !c.interwiki.is_empty() && c.algorithm == Algorithm::Astar {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--interwiki can't be used with --algorithm astar, which uses the API",
+            )
+            .exit();
+    }
+
+    if c.dump.is_some() && c.algorithm == Algorithm::Astar {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--dump can't be used with --algorithm astar, which needs categories",
+            )
+            .exit();
+    }
+
+    if c.weighted && c.algorithm != Algorithm::Bfs {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--weighted can only be used with --algorithm bfs",
+            )
+            .exit();
+    }
+
+    if !c.weight_fn.is_empty() && c.algorithm != Algorithm::Dijkstra {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--weight-fn can only be used with --algorithm dijkstra",
+            )
+            .exit();
+    }
+
+    if let Some(ns) = c.namespace.iter().find(|ns| parse_namespace(ns).is_none()) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::InvalidValue,
+                format!(
+                    "--namespace {} is neither the canonical name nor the ID of a namespace",
+                    ns
+                ),
+            )
+            .exit();
+    }
+
+    if let Some(weight_fn) = c
+        .weight_fn
+        .iter()
+        .find(|weight_fn| matches!(weight_fn, WeightFn::Position | WeightFn::SectionDepth))
+        .filter(|_| c.api || c.dump.is_some())
+    {
+        let name = weight_fn.to_possible_value().unwrap();
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!(
+                    "--weight-fn {} needs where the links are in the HTML, which --api and --dump don't give",
+                    name.get_name()
+                ),
+            )
+            .exit();
+    }
+
+    // With --random-start the first article given is an end article
+    if c.random_start {
+        let given = mem::take(&mut c.start);
+        if c.random_end && !given.is_empty() {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "no articles can be given with both --random-start and --random-end",
+                )
+                .exit();
+        }
+        if !given.is_empty() {
+            c.end.splice(0..0, given.split(',').map(str::to_string));
+        }
+    } else if c.random_end && !c.end.is_empty() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "only START can be given with --random-end",
+            )
+            .exit();
+    }
+
+    // Titles may be copied straight from a URL or typed as displayed
+    let mut start: Vec<_> = c
+        .start
+        .split(',')
+        .filter(|start| !start.is_empty())
+        .map(|title| normalize_title(&decode_title(title)))
+        .collect();
+    c.end = c
+        .end
+        .iter()
+        .map(|end| normalize_title(&decode_title(end)))
+        .collect();
+    c.via = c
+        .via
+        .iter()
+        .map(|via| normalize_title(&decode_title(via)))
+        .collect();
+
+    if c.list_langs {
+        for code in LANGUAGES.keys() {
+            println!("{}", code);
+        }
+        return;
+    }
+
+    // A resumed search keeps its original parameters
+    let resume = match c.resume.as_deref().map(BfsState::load) {
+        Some(Ok(state)) => {
+            start = state.start.clone();
+            c.end = state.end.clone();
+            c.max_depth = state.max_depth;
+            Some(state)
+        }
+        Some(Err(err)) => {
+            error!("{}: {}", c.resume.as_ref().unwrap().display(), err);
+            process::exit(1);
+        }
+        None => None,
+    };
+
+    let mut forbidden: HashSet<_> = c
+        .forbidden
+        .iter()
+        .map(|a| normalize_title(&decode_title(a)))
+        .collect();
+    if let Some(file) = &c.forbidden_file {
+        match fs::read_to_string(file) {
+            Ok(list) => forbidden.extend(
+                list.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|title| normalize_title(&decode_title(title))),
+            ),
+            Err(err) => {
+                error!("{}: {}", file.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let (Some(dump), Some(index)) = (&c.dump, dump_index(&c)) {
+        if let Err(err) = task::block_in_place(|| prepare_dump(&c, dump, &index)) {
+            error!("{}", err);
+            process::exit(1);
+        }
+    }
+
+    let wiki = wiki(&c);
+    let format = c.format;
+
+    if let Some(Command::LoadPath { input, no_verify }) = &c.command {
+        let (input, verify) = (input.clone(), !no_verify);
+        if !run_load_path(c, &input, verify, &forbidden).await {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(Command::Serve { host, port }) = c.command.take() {
+        if let Err(err) = run_server(c, forbidden, &host, port).await {
+            error!("{}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(file) = &c.batch {
+        let pairs = match read_batch(file) {
+            Ok(pairs) => pairs,
+            Err(err) => {
+                error!("{}", err);
+                process::exit(1);
+            }
+        };
+
+        if !run_batch(&c, pairs, &forbidden).await {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if c.interactive {
+        if let Err(err) = run_repl(&c, &forbidden).await {
+            error!("{}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut picker = None;
+    if c.random_start || c.random_end {
+        let searcher = match searcher_builder(&c, Vec::new(), Vec::new(), HashSet::new()).build() {
+            Ok(searcher) => searcher,
+            Err(err) => {
+                error!("{}", err);
+                process::exit(1);
+            }
+        };
+
+        if c.random_start {
+            start = vec![pick_random(&c, &searcher, "start").await];
+        }
+        if c.random_end {
+            c.end = vec![pick_random(&c, &searcher, "end").await];
+        }
+        picker = Some(searcher);
+    }
+
+    if c.compare {
+        if !run_compare(&c, &start, &c.end, &forbidden).await {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut builder = searcher_builder(&c, start.clone(), c.end.clone(), forbidden.clone());
+    // The random articles are counted in the traffic of the search
+    if let Some(picker) = &picker {
+        builder = builder.share_requests(picker);
+    }
+    if let Some(file) = &c.checkpoint {
+        builder = builder.checkpoint(file);
+    }
+    if let Some(state) = resume {
+        builder = builder.resume(state);
+    }
+    // With the TUI, paths are shown once it's closed
+    if !c.tui {
+        builder = with_printers(&c, builder);
+    }
+
+    let mut searcher = match builder.build() {
+        Ok(searcher) => searcher,
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    if !c.skip_precheck && !preflight(&searcher, &start, &c.end, &c.via).await && !c.force {
+        process::exit(1);
+    }
+
+    print_start(&c, &start);
+    let res = searcher.search().await;
+    let mut paths = res.as_deref().unwrap_or_default().to_vec();
+
+    if c.verify_path {
+        for path in &mut paths {
+            *path = verified_path(&c, &searcher, path, &forbidden).await;
+            if c.count_only {
+                print_length(path, format);
+            } else if shows(&c, Event::Found) {
+                print_path(path, format, &wiki);
             }
         }
     }
+
+    if let (Some(file), Some(path)) = (&save_path, paths.first()) {
+        if let Err(err) = PathFile::new(path.clone(), &c.lang).save(file) {
+            error!("{}: {}", file.display(), err);
+            process::exit(1);
+        }
+    }
+
+    if c.tui && shows(&c, Event::Found) {
+        for path in &paths {
+            print_path(path, format, &wiki);
+        }
+        if let Some(path) = searcher.stopped_at() {
+            eprintln!("Stopped, last article expanded:");
+            print_path(path, format, &wiki);
+        }
+    }
+
+    if c.histogram {
+        print_histogram(searcher.frontier_sizes());
+    }
+
+    if c.depth_histogram {
+        print_length_distribution(&paths, format);
+    }
+
+    if let (Format::Cytoscape, Some(graph)) = (format, searcher.graph()) {
+        if let Err(err) = graph.write_cytoscape(BufWriter::new(Output)) {
+            error!("{}", err);
+        }
+    }
+
+    if let (Some(file), Some(graph)) = (&c.export_graph, searcher.graph()) {
+        let res = File::create(file).and_then(|f| graph.write_dot(BufWriter::new(f)));
+        if let Err(err) = res {
+            error!("{}: {}", file.display(), err);
+        }
+    }
+
+    // Nothing is sent with --dump
+    if !paths.is_empty() && !c.quiet && c.dump.is_none() {
+        let traffic = searcher.traffic();
+        eprintln!(
+            "{} HTTP requests, {} downloaded",
+            traffic.requests,
+            format_bytes(traffic.bytes)
+        );
+    }
+
+    if let Err(err) = &res {
+        error!("{}", err);
+        process::exit(exit_code(err));
+    }
+
+    if c.count_only {
+        if let Some(length) = paths.iter().map(|path| path.articles.len()).min() {
+            process::exit(length.min(125) as i32);
+        }
+    }
+
+    match searcher.limit_reached() {
+        Some(Limit::Articles) => {
+            warn!("Search limit reached");
+            process::exit(2);
+        }
+        Some(Limit::Time) => {
+            if paths.is_empty() {
+                warn!("No path found within time limit");
+            }
+            process::exit(3);
+        }
+        Some(Limit::Stopped) | None => {}
+    }
+}
+
+/// Fetches a random article for `role` (start or end) and prints it, on
+/// stderr with the JSON formats and --count-only so that stdout stays
+/// parseable
+async fn pick_random(c: &Cli, searcher: &BfsSearcher, role: &str) -> String {
+    match searcher.random_article().await {
+        Ok(title) => {
+            let line = format!("Random {}: {}", role, normalize_display(&title));
+            match c.format {
+                Format::Text | Format::Markdown if !c.count_only => outln!("{}", line),
+                _ => eprintln!("{}", line),
+            }
+            title
+        }
+        Err(err) => {
+            error!("Couldn't pick a random {} article: {}", role, err);
+            process::exit(exit_code(&err));
+        }
+    }
+}
+
+/// Exit code for a search that failed with `err`, see EXIT_CODES_HELP
+fn exit_code(err: &SearchError) -> i32 {
+    match err {
+        SearchError::RateLimitExceeded => 4,
+        SearchError::Interrupted => 130,
+        _ => 1,
+    }
 }
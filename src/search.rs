@@ -0,0 +1,2411 @@
+//! The search algorithms
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    iter, mem,
+    ops::Range,
+    path::{Path as FilePath, PathBuf},
+    time::{Duration, Instant},
+};
+
+use futures_util::{stream, Stream, StreamExt};
+use indicatif::ProgressBar;
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn, Level};
+
+use crate::{
+    db::SearchDb,
+    fetch::{FetchError, Fetcher, Links, TitleStatus, TITLES_PER_QUERY},
+    graph::Graph,
+    spill::Spill,
+    stats::Stats,
+    tui,
+    wiki::{category_name, normalize_display},
+    Article, Limit, OnEvent, OnExpand, OnPath, Options, Path, SearchError, SearchEvent,
+};
+
+/// Where pressing s in the TUI saves the search without a checkpoint file
+const DEFAULT_TUI_CHECKPOINT: &str = "wiki-path.checkpoint";
+
+/// Cost of following a link in A* priorities, which are integers so the
+/// heuristic can be a fraction of a link
+const LINK_COST: u64 = 1000;
+
+/// Articles that took longest to fetch logged at the end of a search
+const SLOWEST_FETCHES: usize = 5;
+
+/// Bytes into an article that add 1 to the weight of a link with
+/// `WeightFn::Position`
+const POSITION_WEIGHT_BYTES: f64 = 1024.0;
+
+/// Units of weight in a whole one, as `Algorithm::Dijkstra` adds weights
+/// up in integers
+const WEIGHT_UNITS: f64 = 1_000_000.0;
+
+/// How many links the A* heuristic can count for with `suboptimal_ok`
+const SUBOPTIMAL_WEIGHT: u64 = 3;
+
+/// How a search explores the links
+#[derive(clap::ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// Breadth-first search
+    Bfs,
+    /// Depth-first search up to the maximum depth: keeps only the current
+    /// path in memory and can find a path sooner, but not the shortest one
+    Dfs,
+    /// Iterative deepening depth-first search: keeps only the current path in
+    /// memory, but fetches articles again for every depth
+    Iddfs,
+    /// A* search, preferring articles that share categories with END (uses
+    /// the API)
+    Astar,
+    /// Dijkstra's algorithm, finding the path whose links weigh the least
+    /// by --weight-fn
+    Dijkstra,
+}
+
+/// What following a link costs in `Algorithm::Dijkstra`. The weights of
+/// several are multiplied.
+#[derive(clap::ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeightFn {
+    /// Every link weighs 1, finding the shortest path like bfs
+    Uniform,
+    /// Links weigh 1 more for each KiB into their article's HTML (not with
+    /// --api or --dump)
+    Position,
+    /// Links weigh 1 divided by how many links their article has
+    InverseDegree,
+    /// Links weigh 1 more for each character their title would need
+    /// changed to become END's
+    AlphabeticDistance,
+    /// Links weigh 1 in the lead of their article, 2 in a section, 3 in a
+    /// subsection and so on (not with --api or --dump)
+    SectionDepth,
+}
+
+impl WeightFn {
+    /// Weight of a link to `link`, `offset` bytes into an article with
+    /// `degree` links and in a section `section_depth` deep, on the way to
+    /// `end`
+    fn weight(
+        self,
+        link: &str,
+        offset: usize,
+        section_depth: u8,
+        degree: usize,
+        end: &[String],
+    ) -> f64 {
+        match self {
+            WeightFn::Uniform => 1.0,
+            WeightFn::Position => 1.0 + offset as f64 / POSITION_WEIGHT_BYTES,
+            WeightFn::InverseDegree => 1.0 / degree.max(1) as f64,
+            WeightFn::AlphabeticDistance => {
+                let distance = end.iter().map(|end| edit_distance(link, end)).min();
+                1.0 + distance.unwrap_or(0) as f64
+            }
+            WeightFn::SectionDepth => 1.0 + f64::from(section_depth),
+        }
+    }
+}
+
+/// Characters to insert, remove or replace to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Estimated distance from an article to the closest target, in
+/// `LINK_COST`s. With a `weight` of 1 it's never more than a link, so it
+/// never overestimates.
+fn astar_heuristic(categories: &HashSet<String>, targets: &[HashSet<String>], weight: u64) -> u64 {
+    targets
+        .iter()
+        .map(|target| {
+            let shared = categories.intersection(target).count() as u64;
+            weight * (LINK_COST - LINK_COST * shared / (target.len() as u64 + 1))
+        })
+        .min()
+        .unwrap_or(weight * LINK_COST)
+}
+
+/// Divides an A* priority so that articles with more `views` come first
+fn popularity_factor(views: u64, weight: f64) -> f64 {
+    1.0 / (1.0 + weight * (1.0 + views as f64).log10())
+}
+
+/// All the paths from `start` to the article at `idx`, through
+/// `other_parents` as well as `article_parent`
+fn reconstruct_paths<'a>(
+    articles: &'a [String],
+    article_parent: &HashMap<usize, usize>,
+    other_parents: &HashMap<usize, Vec<usize>>,
+    idx: usize,
+) -> Vec<Vec<&'a String>> {
+    if idx == 0 {
+        return vec![Vec::new()];
+    }
+
+    let parents = other_parents.get(&idx).into_iter().flatten();
+    let mut paths = Vec::new();
+
+    for &parent in iter::once(&article_parent[&idx]).chain(parents) {
+        for mut path in reconstruct_paths(articles, article_parent, other_parents, parent) {
+            path.push(&articles[idx]);
+            paths.push(path);
+        }
+    }
+
+    paths
+}
+
+/// Article the one at `idx` was reached from, in the layout of `BfsState`,
+/// or `None` for a start article
+fn parent_of<'a>(
+    articles: &'a [String],
+    article_parent: &HashMap<usize, usize>,
+    idx: usize,
+) -> Option<&'a str> {
+    match article_parent.get(&idx) {
+        Some(&parent) if parent != 0 => Some(&articles[parent]),
+        _ => None,
+    }
+}
+
+/// Title of the article the one at `idx` of `state` was first reached
+/// from, reading it back from `spill` if it was moved there
+fn parent_title(
+    state: &BfsState,
+    spill: Option<&mut Spill>,
+    idx: usize,
+) -> io::Result<Option<String>> {
+    match spill {
+        Some(spill) => match spill.parent(state, idx)? {
+            0 => Ok(None),
+            parent => spill.title(state, parent).map(Some),
+        },
+        None => Ok(parent_of(&state.articles, &state.article_parent, idx).map(str::to_string)),
+    }
+}
+
+/// Index of the article of `state` found under `title`, looking it up in
+/// `spill` if it was moved there
+fn visited_idx(
+    state: &BfsState,
+    spill: Option<&mut Spill>,
+    title: &str,
+) -> io::Result<Option<usize>> {
+    match spill {
+        Some(spill) => spill.visited(state, title),
+        None => Ok(state.visited.get(title).copied()),
+    }
+}
+
+/// Paths from the start to the article at `idx` of `state`, the one through
+/// the article it was first reached from and, if `all`, those through
+/// `other_parents`. Articles moved to `spill` are read back from it.
+fn state_paths(
+    state: &BfsState,
+    spill: Option<&mut Spill>,
+    idx: usize,
+    all: bool,
+) -> io::Result<Vec<Vec<String>>> {
+    let paths = match spill {
+        Some(spill) if all => return spill.paths(state, idx),
+        Some(spill) => return Ok(vec![spill.path(state, idx)?]),
+        None if all => reconstruct_paths(
+            &state.articles,
+            &state.article_parent,
+            &state.other_parents,
+            idx,
+        ),
+        None => vec![reconstruct_path(
+            &state.articles,
+            &state.article_parent,
+            idx,
+        )],
+    };
+    Ok(paths
+        .into_iter()
+        .map(|path| path.into_iter().cloned().collect())
+        .collect())
+}
+
+/// Bytes taken by `titles`, with the strings holding them
+fn titles_bytes<'a>(titles: impl IntoIterator<Item = &'a String>) -> usize {
+    titles
+        .into_iter()
+        .map(|title| mem::size_of::<String>() + title.capacity())
+        .sum()
+}
+
+/// Looks up the thumbnail of `article` for the TUI, which goes without it
+/// if that fails
+async fn send_thumbnail(fetcher: Fetcher, article: String, thumbnails: tui::Thumbnails) {
+    match fetcher.thumbnail(article.clone(), thumbnails.images).await {
+        Ok(Some(thumbnail)) => thumbnails.send(thumbnail),
+        Ok(None) => {}
+        Err(err) => debug!("Couldn't fetch the thumbnail of {}: {}", article, err),
+    }
+}
+
+/// Articles from `start` to the article at `idx`
+fn reconstruct_path<'a>(
+    articles: &'a [String],
+    article_parent: &HashMap<usize, usize>,
+    mut idx: usize,
+) -> Vec<&'a String> {
+    let mut path = Vec::new();
+
+    while idx != 0 {
+        path.push(&articles[idx]);
+        idx = article_parent[&idx];
+    }
+
+    path.reverse();
+    path
+}
+
+/// Articles reached from one end of a bidirectional search. Each article
+/// points to the one it was reached from.
+struct SearchTree {
+    articles: Vec<String>,
+    parent: Vec<usize>,
+    index: HashMap<String, usize>,
+    level_start: usize,
+}
+
+impl SearchTree {
+    /// Bytes the articles of the tree take in memory, roughly
+    fn heap_bytes(&self) -> usize {
+        titles_bytes(&self.articles)
+            + titles_bytes(self.index.keys())
+            + (self.parent.len() + self.index.len()) * mem::size_of::<usize>()
+    }
+
+    fn new(roots: &[String]) -> Self {
+        let mut tree = Self {
+            articles: Vec::new(),
+            parent: Vec::new(),
+            index: HashMap::new(),
+            level_start: 0,
+        };
+
+        for root in roots {
+            if !tree.index.contains_key(root) {
+                tree.index.insert(root.clone(), tree.articles.len());
+                tree.parent.push(tree.articles.len());
+                tree.articles.push(root.clone());
+            }
+        }
+
+        tree
+    }
+
+    fn level_len(&self) -> usize {
+        self.articles.len() - self.level_start
+    }
+
+    /// Articles from the root to `idx`. Roots are their own parent.
+    fn chain(&self, mut idx: usize) -> Vec<&String> {
+        let mut chain = vec![&self.articles[idx]];
+        while self.parent[idx] != idx {
+            idx = self.parent[idx];
+            chain.push(&self.articles[idx]);
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// Articles a search never enters, by title or by a pattern their titles
+/// match, or because they match none of the allowed patterns
+#[derive(Clone, Default)]
+pub(crate) struct Forbidden {
+    titles: HashSet<String>,
+    patterns: Option<RegexSet>,
+    allowed: Option<RegexSet>,
+    /// Articles the allowed patterns don't apply to
+    exempt: HashSet<String>,
+}
+
+impl Forbidden {
+    pub(crate) fn new(
+        titles: HashSet<String>,
+        patterns: Option<RegexSet>,
+        allowed: Option<RegexSet>,
+    ) -> Self {
+        Self {
+            titles,
+            patterns,
+            allowed,
+            exempt: HashSet::new(),
+        }
+    }
+
+    /// Lets `titles` be entered whatever the allowed patterns, unless
+    /// they're forbidden otherwise
+    pub(crate) fn exempt<'a>(mut self, titles: impl IntoIterator<Item = &'a String>) -> Self {
+        self.exempt.extend(titles.into_iter().cloned());
+        self
+    }
+
+    pub(crate) fn contains(&self, title: &str) -> bool {
+        self.titles.contains(title)
+            || self
+                .patterns
+                .as_ref()
+                .is_some_and(|patterns| patterns.is_match(title))
+            || !self.allows(title)
+    }
+
+    /// Whether `title` passes the allowed patterns, if there are any
+    pub(crate) fn allows(&self, title: &str) -> bool {
+        match &self.allowed {
+            Some(allowed) => allowed.is_match(title) || self.exempt.contains(title),
+            None => true,
+        }
+    }
+
+    pub(crate) fn has_allowed(&self) -> bool {
+        self.allowed.is_some()
+    }
+
+    pub(crate) fn extend(&mut self, titles: impl IntoIterator<Item = String>) {
+        self.titles.extend(titles);
+    }
+}
+
+/// A search from `start` to `end` and what is collected along the way
+pub(crate) struct Search<'a> {
+    pub(crate) c: &'a Options,
+    pub(crate) start: Vec<String>,
+    pub(crate) end: Vec<String>,
+    /// Articles to pass through on the way to `end`
+    pub(crate) via: Vec<String>,
+    pub(crate) fetcher: Fetcher,
+    pub(crate) start_time: Instant,
+    pub(crate) graph: Option<Graph>,
+    pub(crate) progress: ProgressBar,
+    /// Articles that are never entered
+    pub(crate) forbidden: Forbidden,
+    /// Categories of `require_categories` and their subcategories, empty
+    /// if articles can be in any
+    pub(crate) categories: HashSet<String>,
+    /// Whether each article looked up is in one of `categories`
+    pub(crate) in_categories: HashMap<String, bool>,
+    /// Articles first found in a See also section, with
+    /// `prioritize_see_also`
+    pub(crate) see_also: HashSet<String>,
+    /// How long the HTML of each article expanded took to fetch, kept for
+    /// debug logs
+    pub(crate) fetch_times: Vec<(String, Duration)>,
+    /// What was learned about each article expanded, in order, stored
+    /// with --db
+    pub(crate) articles: Vec<Article>,
+    pub(crate) db: Option<SearchDb>,
+    pub(crate) stats: Option<Stats>,
+    pub(crate) tui: Option<tui::Tui>,
+    /// Path to the last article expanded, if the search was stopped from
+    /// the TUI
+    pub(crate) aborted: Option<Path>,
+    /// Limit the search was stopped by
+    pub(crate) limit: Option<Limit>,
+    /// Error the search was stopped by
+    pub(crate) error: Option<SearchError>,
+    /// Articles in each depth of a breadth-first search so far
+    pub(crate) frontier_sizes: Vec<usize>,
+    /// Text of the first link found to each article, kept for debug logs
+    pub(crate) anchors: HashMap<String, String>,
+    /// Articles put on the stack by depth-first searches so far
+    pub(crate) pushed: usize,
+    /// Whether this is a segment of a search through waypoints, whose
+    /// paths are only collected
+    pub(crate) segment: bool,
+    pub(crate) found: Vec<Path>,
+    /// Most bytes the articles kept by the search took at once, as far as
+    /// `heap_bytes` can tell
+    pub(crate) peak_memory: usize,
+    pub(crate) on_path: Option<&'a mut OnPath>,
+    pub(crate) on_expand: Option<&'a mut OnExpand>,
+    pub(crate) on_event: Option<&'a mut OnEvent>,
+}
+
+impl Search<'_> {
+    fn report_path(&mut self, path: &[&String]) {
+        self.report_weighed_path(path, None);
+    }
+
+    fn report_weighed_path(&mut self, path: &[&String], weight: Option<f64>) {
+        let path = Path {
+            articles: path.iter().map(|a| a.to_string()).collect(),
+            elapsed: self.start_time.elapsed(),
+            traffic: self.fetcher.traffic(),
+            frontier_sizes: self.frontier_sizes.clone(),
+            weight,
+        };
+
+        if !self.segment {
+            self.show_path(&path);
+        }
+        self.found.push(path);
+    }
+
+    fn show_path(&mut self, path: &Path) {
+        if let Some(on_path) = &mut self.on_path {
+            self.progress.suspend(|| on_path(path));
+        }
+        self.event(SearchEvent::PathFound(path));
+
+        if let Some(graph) = &mut self.graph {
+            graph.add_path(&path.articles);
+        }
+    }
+
+    /// Logs that the links of `article`, `depth` links away from the start
+    /// and first reached from `parent`, are being fetched, or have been as
+    /// `links`
+    fn expanding(
+        &mut self,
+        article: &str,
+        depth: u32,
+        parent: Option<&str>,
+        links: Option<&Links>,
+    ) {
+        // Where the link to the article was, if they're told apart
+        let source = if !self.c.prioritize_see_also || depth == 0 {
+            ""
+        } else if self.see_also.contains(article) {
+            " source=see-also"
+        } else {
+            " source=body"
+        };
+        let description = match links.and_then(|links| links.description.as_deref()) {
+            Some(description) => format!(": \"{}\"", description),
+            None => String::new(),
+        };
+        let fetch = match links.and_then(|links| links.fetch_time) {
+            Some(time) if tracing::enabled!(Level::DEBUG) => {
+                self.fetch_times.push((article.to_string(), time));
+                format!(" fetch={}ms", time.as_millis())
+            }
+            _ => String::new(),
+        };
+        match self.anchors.get(article) {
+            Some(anchor) => debug!(
+                "[{} → {}]{} depth={}{}{}",
+                anchor,
+                normalize_display(article),
+                description,
+                depth,
+                fetch,
+                source
+            ),
+            None => debug!(
+                "{}{} depth={}{}{}",
+                normalize_display(article),
+                description,
+                depth,
+                fetch,
+                source
+            ),
+        }
+
+        if let Some(links) = links {
+            self.articles.push(Article::new(
+                links.canonical.as_deref().unwrap_or(article),
+                links,
+            ));
+        }
+
+        if let Some(on_expand) = &mut self.on_expand {
+            self.progress.suspend(|| on_expand(article, depth));
+        }
+        self.event(SearchEvent::Expanded {
+            article,
+            depth,
+            parent,
+            fetch_time: links.and_then(|links| links.fetch_time),
+        });
+    }
+
+    /// Reports `event` to `on_event`, which can stop the search
+    fn event(&mut self, event: SearchEvent<'_>) {
+        if let Some(on_event) = &mut self.on_event {
+            if self.progress.suspend(|| on_event(event)).is_break() {
+                self.limit = Some(Limit::Stopped);
+            }
+        }
+    }
+
+    /// Remembers the text of the links to articles not seen yet, to log it
+    /// when they're expanded
+    fn note_anchors(&mut self, links: &Links) {
+        if !tracing::enabled!(Level::DEBUG) {
+            return;
+        }
+        for (title, anchor) in links.titles.iter().zip(&links.anchors) {
+            if !anchor.is_empty() && !self.anchors.contains_key(title) {
+                self.anchors.insert(title.clone(), anchor.clone());
+            }
+        }
+    }
+
+    /// Logs the median time the article HTML took to fetch, and the articles
+    /// that took longest
+    pub(crate) fn log_fetch_times(&mut self) {
+        if self.fetch_times.is_empty() {
+            return;
+        }
+        self.fetch_times.sort_by_key(|(_, time)| Reverse(*time));
+
+        let median = self.fetch_times[self.fetch_times.len() / 2].1;
+        debug!(
+            "Median fetch time: {}ms over {} articles",
+            median.as_millis(),
+            self.fetch_times.len()
+        );
+        let slowest: Vec<_> = self
+            .fetch_times
+            .iter()
+            .take(SLOWEST_FETCHES)
+            .map(|(article, time)| {
+                format!("{} ({}ms)", normalize_display(article), time.as_millis())
+            })
+            .collect();
+        debug!("Slowest fetches: {}", slowest.join(", "));
+    }
+
+    /// Whether `article` has more links than `max_fan_out` allows, in which
+    /// case they aren't followed. The start articles were chosen, so they
+    /// always are.
+    fn over_fan_out(&self, article: &str, links: usize) -> bool {
+        if self.c.max_fan_out.is_none_or(|max| links <= max)
+            || self.start.iter().any(|start| start == article)
+        {
+            return false;
+        }
+        debug!(
+            "Skipping {}, it has {} links",
+            normalize_display(article),
+            links
+        );
+        true
+    }
+
+    /// Whether `article` is a stub, its HTML of `size` bytes being smaller
+    /// than `min_article_size`, in which case its links aren't followed. The
+    /// start articles always are, and the end ones aren't left out: a
+    /// depth-first search only finds it's reached one through a redirect
+    /// once expanded.
+    fn is_stub(&self, article: &str, size: Option<usize>) -> bool {
+        let Some((min, size)) = self.c.min_article_size.zip(size) else {
+            return false;
+        };
+        if size >= min
+            || self.start.iter().any(|start| start == article)
+            || self.end.iter().any(|end| end == article)
+        {
+            return false;
+        }
+        debug!(
+            "Skipping {}, a stub of {} bytes",
+            normalize_display(article),
+            size
+        );
+        true
+    }
+
+    /// Those of `titles` that aren't in any of the categories articles must
+    /// be in, and can't be entered. The start and end articles always can.
+    /// Articles are looked up once, those not known yet all at once.
+    async fn outside_categories(
+        &mut self,
+        titles: &[String],
+    ) -> Result<HashSet<String>, FetchError> {
+        if self.categories.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let unknown: Vec<_> = titles
+            .iter()
+            .filter(|title| {
+                !self.in_categories.contains_key(*title)
+                    && !self.start.contains(title)
+                    && !self.end.contains(title)
+            })
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if !unknown.is_empty() {
+            let mut categories = self.fetcher.categories_of(&unknown).await?;
+            for title in unknown {
+                // Those that don't exist have no categories
+                let inside = categories
+                    .remove(&title)
+                    .is_some_and(|of| !of.is_disjoint(&self.categories));
+                self.in_categories.insert(title, inside);
+            }
+        }
+
+        let outside: HashSet<_> = titles
+            .iter()
+            .filter(|title| self.in_categories.get(*title) == Some(&false))
+            .cloned()
+            .collect();
+        if !outside.is_empty() {
+            debug!("Skipping {} links outside the categories", outside.len());
+        }
+        Ok(outside)
+    }
+
+    /// Whether `queued` articles are as many as `max_articles` allows, in
+    /// which case the search stops
+    fn over_limit(&mut self, queued: usize) -> bool {
+        if self.c.max_articles.is_some_and(|max| queued >= max) {
+            self.limit = Some(Limit::Articles);
+            return true;
+        }
+        false
+    }
+
+    /// Raises `peak_memory` to `bytes` if they're more
+    fn note_memory(&mut self, bytes: usize) {
+        self.peak_memory = self.peak_memory.max(bytes);
+    }
+
+    /// Whether a path of `hops` links is shorter than `min_length`
+    fn too_short(&self, hops: usize) -> bool {
+        self.c.min_length.is_some_and(|min| hops < min)
+    }
+
+    /// Whether the search has been going for longer than `time_limit`, in
+    /// which case it stops, or `on_event` stopped it
+    fn timed_out(&mut self) -> bool {
+        if self.limit == Some(Limit::Stopped) {
+            return true;
+        }
+        if self
+            .c
+            .time_limit
+            .is_some_and(|limit| self.start_time.elapsed() >= limit)
+        {
+            self.limit = Some(Limit::Time);
+            return true;
+        }
+        false
+    }
+
+    /// Whether the search was stopped before it was over
+    fn stopped(&self) -> bool {
+        self.aborted.is_some() || self.limit.is_some() || self.error.is_some()
+    }
+
+    /// Reports that an article couldn't be fetched, so that it's skipped.
+    /// Returns true if Wikipedia is refusing requests instead: then the
+    /// search must stop.
+    fn fetch_failed(&mut self, err: FetchError) -> bool {
+        if err.is_rate_limit() {
+            self.error = Some(err.into());
+            return true;
+        }
+        self.report_error(err);
+        false
+    }
+
+    /// Unwraps the result of moving articles to disk or reading them back,
+    /// stopping the search if it failed
+    fn spilled<T>(&mut self, res: io::Result<T>) -> Option<T> {
+        match res {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.error = Some(SearchError::Spill(err));
+                None
+            }
+        }
+    }
+
+    fn report_error(&mut self, err: impl Display) {
+        let err = err.to_string();
+        match &self.tui {
+            Some(tui) => tui.update(tui::Update::Error(err.clone())),
+            None => self.progress.suspend(|| warn!("{}", err)),
+        }
+        self.event(SearchEvent::Error(&err));
+    }
+
+    pub(crate) async fn run(&mut self, resume: Option<BfsState>) {
+        match self.c.algorithm {
+            Algorithm::Bfs if self.c.bidirectional => self.bidirectional().await,
+            Algorithm::Bfs if self.c.weighted => self.weighted().await,
+            Algorithm::Bfs => self.bfs(resume).await,
+            Algorithm::Dfs => self.depth_first().await,
+            Algorithm::Iddfs => self.iddfs().await,
+            Algorithm::Astar => self.astar().await,
+            Algorithm::Dijkstra => self.dijkstra().await,
+        }
+    }
+
+    /// Searches from `start` to the first waypoint, from there to the next
+    /// one and so on until `end`, then reports the joined path. Articles of
+    /// earlier segments are not entered again. Nothing is found if a
+    /// segment has no path.
+    pub(crate) async fn via(&mut self) {
+        let mut stops: Vec<_> = self.via.iter().map(|via| vec![via.clone()]).collect();
+        stops.push(self.end.clone());
+
+        // The first segment can begin at any start article
+        let mut path: Vec<String> = Vec::new();
+
+        self.segment = true;
+
+        for to in stops {
+            if let Some((from, before)) = path.split_last() {
+                self.forbidden.extend(before.iter().cloned());
+                self.start = vec![from.clone()];
+            }
+            self.end = to.clone();
+            self.found.clear();
+
+            self.run(None).await;
+
+            if self.stopped() {
+                return;
+            }
+
+            let Some(segment) = self.found.first() else {
+                self.report_error(format_args!(
+                    "No path found from {} to {}",
+                    self.start.join(", "),
+                    to.join(", ")
+                ));
+                return;
+            };
+
+            let skip = if path.is_empty() { 0 } else { 1 };
+            path.extend(segment.articles[skip..].iter().cloned());
+        }
+
+        self.segment = false;
+        self.found.clear();
+
+        let path: Vec<_> = path.iter().collect();
+        self.report_path(&path);
+    }
+
+    /// Expands the forward tree from `start` by following links and the
+    /// reverse tree from `end` by following backlinks, one depth at a time on
+    /// whichever side has the smaller frontier. A path is found when the
+    /// trees meet.
+    async fn bidirectional(&mut self) {
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+
+        if let Some(start) = self.start.iter().find(|start| self.end.contains(start)) {
+            let start = start.clone();
+            self.report_path(&[&start]);
+            return;
+        }
+
+        let mut forward = SearchTree::new(&self.start);
+        let mut reverse = SearchTree::new(&self.end);
+        self.bidirectional_levels(&mut forward, &mut reverse).await;
+        self.note_memory(forward.heap_bytes() + reverse.heap_bytes());
+    }
+
+    /// Expands `forward` and `reverse` until they meet, for `bidirectional`
+    async fn bidirectional_levels(&mut self, forward: &mut SearchTree, reverse: &mut SearchTree) {
+        let c = self.c;
+        let fetcher = self.fetcher.clone();
+
+        let mut forward_depth = 0;
+        let mut reverse_depth = 0;
+
+        // The trees can meet at several articles of the same path
+        let mut found = HashSet::new();
+
+        while forward_depth + reverse_depth <= c.max_depth {
+            let is_forward = forward.level_len() <= reverse.level_len();
+            let (tree, other, depth) = if is_forward {
+                (&mut *forward, &*reverse, &mut forward_depth)
+            } else {
+                (&mut *reverse, &*forward, &mut reverse_depth)
+            };
+
+            let level_start = tree.level_start;
+            let level_end = tree.articles.len();
+            tree.level_start = level_end;
+
+            if level_start == level_end {
+                return;
+            }
+            if is_forward {
+                self.frontier_sizes.push(level_end - level_start);
+            }
+
+            self.progress.set_prefix(if is_forward {
+                format!("depth {}", depth)
+            } else {
+                format!("depth {} (backlinks)", depth)
+            });
+            self.progress.set_length((level_end - level_start) as u64);
+            self.progress.set_position(0);
+
+            let level = tree.articles[level_start..level_end].to_vec();
+            let mut neighbours = stream::iter(level)
+                .map(|article| {
+                    if is_forward {
+                        tokio::spawn(fetcher.clone().links(article))
+                    } else {
+                        tokio::spawn(fetcher.clone().backlinks(article))
+                    }
+                })
+                .buffered(c.workers);
+
+            let mut curr_idx = level_start;
+            while let Some(res) = neighbours.next().await {
+                if self.timed_out() {
+                    return;
+                }
+
+                let idx = curr_idx;
+                curr_idx += 1;
+
+                self.progress.inc(1);
+                self.progress.set_message(format!(
+                    "{} visited",
+                    tree.articles.len() + other.articles.len()
+                ));
+
+                let res = res.expect("fetch task panicked");
+                if is_forward {
+                    let parent = tree.parent[idx];
+                    let parent = (parent != idx).then(|| tree.articles[parent].as_str());
+                    self.expanding(&tree.articles[idx], *depth, parent, res.as_ref().ok());
+                } else {
+                    debug!(
+                        "{} depth={} (backlinks)",
+                        normalize_display(&tree.articles[idx]),
+                        depth
+                    );
+                }
+
+                let links = match res {
+                    Ok(links) => links,
+                    Err(err) => {
+                        if self.fetch_failed(err) {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+
+                self.note_anchors(&links);
+
+                // Articles where the trees meet
+                let mut meetings = Vec::new();
+
+                // Redirected: continue under the canonical title, unless it
+                // was already reached
+                if let Some(canonical) = links.canonical {
+                    if tree.index.contains_key(&canonical) || self.forbidden.contains(&canonical) {
+                        continue;
+                    }
+
+                    if other.index.contains_key(&canonical) {
+                        meetings.push(idx);
+                    }
+
+                    tree.index.insert(canonical.clone(), idx);
+                    tree.articles[idx] = canonical;
+                }
+
+                if c.no_disambig && links.disambiguation {
+                    debug!(
+                        "Skipping disambiguation page {}",
+                        normalize_display(&tree.articles[idx])
+                    );
+                    continue;
+                }
+
+                // Backlinks are how many link to the article, not from it
+                if is_forward && self.over_fan_out(&tree.articles[idx], links.titles.len()) {
+                    continue;
+                }
+                if self.is_stub(&tree.articles[idx], links.size) {
+                    continue;
+                }
+
+                let outside = match self.outside_categories(&links.titles).await {
+                    Ok(outside) => outside,
+                    Err(err) => {
+                        if self.fetch_failed(err) {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+
+                for link in links.titles {
+                    if let Some(graph) = &mut self.graph {
+                        if is_forward {
+                            graph.add_edge(&tree.articles[idx], &link);
+                        } else {
+                            graph.add_edge(&link, &tree.articles[idx]);
+                        }
+                    }
+
+                    if tree.index.contains_key(&link)
+                        || self.forbidden.contains(&link)
+                        || outside.contains(&link)
+                    {
+                        continue;
+                    }
+
+                    if self.over_limit(tree.articles.len() + other.articles.len()) {
+                        return;
+                    }
+
+                    if other.index.contains_key(&link) {
+                        meetings.push(tree.articles.len());
+                    }
+
+                    if is_forward {
+                        self.event(SearchEvent::Discovered {
+                            article: &link,
+                            depth: *depth + 1,
+                        });
+                    }
+
+                    tree.articles.push(link.clone());
+                    tree.parent.push(idx);
+                    tree.index.insert(link, tree.articles.len() - 1);
+                }
+
+                for meeting in meetings {
+                    let other_idx = other.index[&tree.articles[meeting]];
+
+                    let (fwd_tree, fwd_idx, rev_tree, rev_idx) = if is_forward {
+                        (&*tree, meeting, other, other_idx)
+                    } else {
+                        (other, other_idx, &*tree, meeting)
+                    };
+
+                    let mut path = fwd_tree.chain(fwd_idx);
+                    let mut tail = rev_tree.chain(rev_idx);
+                    tail.reverse();
+                    path.extend(tail.into_iter().skip(1));
+
+                    // Both halves can pass through the same article
+                    if path.iter().collect::<HashSet<_>>().len() != path.len() {
+                        continue;
+                    }
+
+                    if !found.insert(path.iter().map(|a| a.to_string()).collect::<Vec<_>>()) {
+                        continue;
+                    }
+
+                    self.report_path(&path);
+
+                    if !c.all {
+                        return;
+                    }
+                }
+            }
+
+            *depth += 1;
+        }
+    }
+
+    /// Searches breadth-first from `start`, following links until `end` is
+    /// found. The search continues from `state` when resuming.
+    async fn bfs(&mut self, state: Option<BfsState>) {
+        let c = self.c;
+
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+
+        let mut state = state.unwrap_or_else(|| BfsState::new(&self.start, &self.end, c.max_depth));
+
+        // Saving the state needs every article in memory
+        let mut spill = None;
+        if let Some(max) = c.max_memory {
+            if c.checkpoint.is_none() && c.db.is_none() && !c.tui && !c.prioritize_see_also {
+                let Some(new) = self.spilled(Spill::new(max)) else {
+                    return;
+                };
+                spill = Some(new);
+            }
+        }
+
+        let found = self.bfs_levels(&mut state, &mut spill).await;
+        self.note_memory(state.heap_bytes());
+        if let Some(spill) = &spill {
+            self.note_memory(spill.peak_bytes());
+        }
+
+        if let Some(stats) = &mut self.stats {
+            stats.finish_level(state.articles.len() - 1, &self.progress);
+            stats.print_totals(&self.progress);
+        }
+
+        // Stopped midway, the state saved at the start of the depth is kept
+        if self.stopped() {
+            return;
+        }
+
+        if let Some(db) = &mut self.db {
+            // Went through every depth: a deeper search can pick up from here
+            if !found {
+                state.depth = c.max_depth + 1;
+            }
+            if let Err(err) = db.save(&state, found, &self.articles) {
+                self.report_error(err);
+            }
+        }
+    }
+
+    /// The articles of `level` in order, each with whether it exists. With
+    /// `prevalidate` they're looked up `TITLES_PER_QUERY` at a time, the
+    /// next batch while the articles of the last are expanded; otherwise,
+    /// or if a lookup fails, they're taken to exist.
+    fn validated(&self, level: Vec<String>) -> impl Stream<Item = (String, bool)> + 'static {
+        let fetcher = self.fetcher.clone();
+        let prevalidate = self.c.prevalidate;
+        let batches: Vec<_> = level.chunks(TITLES_PER_QUERY).map(<[_]>::to_vec).collect();
+
+        stream::iter(batches)
+            .map(move |batch| {
+                let fetcher = fetcher.clone();
+                async move {
+                    let mut statuses = HashMap::new();
+                    if prevalidate {
+                        match fetcher.validate_titles(&batch).await {
+                            Ok(looked_up) => statuses = looked_up,
+                            Err(err) => debug!("Couldn't look the articles up: {}", err),
+                        }
+                    }
+                    stream::iter(batch.into_iter().map(move |article| {
+                        let exists = statuses.get(&article) != Some(&TitleStatus::Missing);
+                        (article, exists)
+                    }))
+                }
+            })
+            .buffered(2)
+            .flatten()
+    }
+
+    /// Indices of the articles of `level` expanded with `level_limit`, the
+    /// first ones or, with `seed`, a random sample of them, in the order
+    /// they were found. `None` when the whole level is expanded.
+    fn level_sample(&self, level: Range<usize>, depth: u32) -> Option<Vec<usize>> {
+        let limit = self.c.level_limit?;
+        if level.len() <= limit {
+            return None;
+        }
+
+        let mut sample: Vec<_> = level.collect();
+        if let Some(seed) = self.c.seed {
+            fastrand::Rng::with_seed(seed ^ u64::from(depth)).shuffle(&mut sample);
+            sample.truncate(limit);
+            sample.sort_unstable();
+        } else {
+            sample.truncate(limit);
+        }
+        Some(sample)
+    }
+
+    /// Returns whether the search stopped at a path
+    async fn bfs_levels(&mut self, state: &mut BfsState, spill: &mut Option<Spill>) -> bool {
+        let c = self.c;
+        let fetcher = self.fetcher.clone();
+
+        let first_depth = state.depth;
+
+        // Checkpoints from older versions and the database don't keep them
+        let started = first_depth as usize + usize::from(state.level_left > 0);
+        if state.frontier_sizes.len() != started {
+            state.frontier_sizes = state.level_sizes(started);
+        }
+
+        for depth in first_depth..(c.max_depth + 1) {
+            state.depth = depth;
+
+            if depth != first_depth {
+                if let Some(file) = &c.checkpoint {
+                    if let Err(err) = state.save(file) {
+                        self.report_error(format_args!("{}: {}", file.display(), err));
+                    }
+                }
+                if let Some(db) = &mut self.db {
+                    if let Err(err) = db.save(state, false, &self.articles) {
+                        self.report_error(err);
+                    }
+                }
+            }
+
+            let level_len = if depth == first_depth && state.level_left > 0 {
+                mem::take(&mut state.level_left)
+            } else {
+                mem::take(&mut state.next_level_len)
+            };
+
+            if state.frontier_sizes.len() <= depth as usize {
+                state.frontier_sizes.push(level_len);
+            }
+            self.frontier_sizes = state.frontier_sizes.clone();
+
+            let first_idx = state.curr_idx + 1;
+            let end_idx = state.curr_idx + level_len;
+            let sample = self.level_sample(first_idx..end_idx + 1, depth);
+            if let Some(sample) = &sample {
+                self.progress.suspend(|| {
+                    warn!(
+                        "Expanding {} of the {} articles at depth {}, dropping {}",
+                        sample.len(),
+                        level_len,
+                        depth,
+                        level_len - sample.len()
+                    )
+                });
+            }
+
+            self.progress.set_prefix(format!("depth {}", depth));
+            self.progress
+                .set_length(sample.as_ref().map_or(level_len, Vec::len) as u64);
+            self.progress.set_position(0);
+
+            if let Some(stats) = &mut self.stats {
+                stats.start_level(depth, level_len, &self.progress);
+            }
+            if let Some(tui) = &self.tui {
+                tui.update(tui::Update::Level {
+                    depth,
+                    frontier: level_len,
+                });
+            }
+
+            // Targets reached during this depth, with --all
+            let mut found = Vec::new();
+            // Links followed from this depth, and those the allowed
+            // patterns let through
+            let (mut linked, mut allowed) = (0, 0);
+
+            // Responses are consumed in frontier order, so parents are
+            // assigned the same way regardless of which request finishes
+            // first
+            let level = match spill {
+                Some(spill) => {
+                    let res = spill.titles(state, state.curr_idx + 1..end_idx + 1);
+                    let Some(level) = self.spilled(res) else {
+                        return false;
+                    };
+                    level
+                }
+                None => state.articles[first_idx..=end_idx].to_vec(),
+            };
+            let level = match &sample {
+                Some(sample) => {
+                    let mut level = level;
+                    sample
+                        .iter()
+                        .map(|&idx| mem::take(&mut level[idx - first_idx]))
+                        .collect()
+                }
+                None => level,
+            };
+            let mut sample = sample.map(Vec::into_iter);
+            let thumbnails = self.tui.as_ref().map(tui::Tui::thumbnails);
+            let mut neighbours = self
+                .validated(level)
+                .map(|(article, exists)| {
+                    let fetcher = fetcher.clone();
+                    if let Some(thumbnails) = thumbnails.clone().filter(|_| exists) {
+                        tokio::spawn(send_thumbnail(fetcher.clone(), article.clone(), thumbnails));
+                    }
+                    tokio::spawn(async move {
+                        if exists {
+                            Some(fetcher.links(article).await)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .buffered(c.workers);
+
+            'level: while let Some(res) = neighbours.next().await {
+                if self.timed_out() {
+                    break;
+                }
+
+                if let Some(tui) = &self.tui {
+                    if tui.aborted() {
+                        let path = reconstruct_path(
+                            &state.articles,
+                            &state.article_parent,
+                            state.curr_idx,
+                        );
+                        let path = Path {
+                            articles: path.into_iter().cloned().collect(),
+                            elapsed: self.start_time.elapsed(),
+                            traffic: self.fetcher.traffic(),
+                            frontier_sizes: self.frontier_sizes.clone(),
+                            weight: None,
+                        };
+                        if let Some(graph) = &mut self.graph {
+                            graph.add_path(&path.articles);
+                        }
+                        self.aborted = Some(path);
+                        return false;
+                    }
+
+                    if tui.take_save() {
+                        let file = c
+                            .checkpoint
+                            .clone()
+                            .unwrap_or_else(|| PathBuf::from(DEFAULT_TUI_CHECKPOINT));
+
+                        state.level_left = end_idx - state.curr_idx;
+                        let res = state.save(&file);
+                        state.level_left = 0;
+
+                        match res {
+                            Ok(()) => tui.update(tui::Update::Message(format!(
+                                "Saved checkpoint to {}",
+                                file.display()
+                            ))),
+                            Err(err) => {
+                                self.report_error(format_args!("{}: {}", file.display(), err))
+                            }
+                        }
+                    }
+                }
+
+                state.curr_idx = match &mut sample {
+                    Some(sample) => sample.next().expect("more articles than sampled"),
+                    None => state.curr_idx + 1,
+                };
+                let curr_idx = state.curr_idx;
+
+                // Moved to disk before the article is read back
+                if let Some(spill) = spill {
+                    let res = spill
+                        .check(state)
+                        .and_then(|()| spill.restore(state, curr_idx));
+                    if self.spilled(res).is_none() {
+                        return false;
+                    }
+                }
+
+                self.progress.inc(1);
+                self.progress
+                    .set_message(format!("{} visited", state.articles.len() - 1));
+
+                let Some(res) = res.expect("fetch task panicked") else {
+                    debug!(
+                        "Skipping {}, which doesn't exist",
+                        normalize_display(&state.articles[curr_idx])
+                    );
+                    continue;
+                };
+                let Some(parent) = self.spilled(parent_title(state, spill.as_mut(), curr_idx))
+                else {
+                    return false;
+                };
+                self.expanding(
+                    &state.articles[curr_idx],
+                    depth,
+                    parent.as_deref(),
+                    res.as_ref().ok(),
+                );
+
+                let links = match res {
+                    Ok(links) => {
+                        if let Some(stats) = &mut self.stats {
+                            stats.expanded();
+                        }
+                        links
+                    }
+                    Err(err) => {
+                        if let Some(stats) = &mut self.stats {
+                            stats.error();
+                        }
+                        if self.fetch_failed(err) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+
+                self.note_anchors(&links);
+
+                // Redirected: continue under the canonical title, unless it
+                // was already reached
+                if let Some(canonical) = links.canonical {
+                    let visited = visited_idx(state, spill.as_mut(), &canonical);
+                    let Some(visited) = self.spilled(visited) else {
+                        return false;
+                    };
+                    if visited.is_some() || self.forbidden.contains(&canonical) {
+                        continue;
+                    }
+                    if self.end.contains(&canonical) && self.too_short(depth as usize) {
+                        continue;
+                    }
+
+                    state.visited.insert(canonical.clone(), curr_idx);
+                    let title = mem::replace(&mut state.articles[curr_idx], canonical);
+                    // Found under the title it was reached by too, which
+                    // may have been moved to disk
+                    state.visited.insert(title, curr_idx);
+
+                    if self.end.contains(&state.articles[curr_idx]) {
+                        if c.all {
+                            found.push(curr_idx);
+                        } else {
+                            let res = state_paths(state, spill.as_mut(), curr_idx, false);
+                            if let Some(paths) = self.spilled(res) {
+                                self.report_path(&paths[0].iter().collect::<Vec<_>>());
+                            }
+                            return true;
+                        }
+                    }
+                }
+
+                if let Some(tui) = &self.tui {
+                    tui.update(tui::Update::Expanded {
+                        article: state.articles[curr_idx].clone(),
+                        parent,
+                        discovered: state.articles.len() - 1,
+                    });
+                }
+
+                if c.no_disambig && links.disambiguation {
+                    debug!(
+                        "Skipping disambiguation page {}",
+                        normalize_display(&state.articles[curr_idx])
+                    );
+                    continue;
+                }
+
+                if self.over_fan_out(&state.articles[curr_idx], links.titles.len()) {
+                    continue;
+                }
+                if self.is_stub(&state.articles[curr_idx], links.size) {
+                    continue;
+                }
+
+                let outside = match self.outside_categories(&links.titles).await {
+                    Ok(outside) => outside,
+                    Err(err) => {
+                        if self.fetch_failed(err) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+
+                for new_article in links.titles {
+                    if let Some(graph) = &mut self.graph {
+                        graph.add_edge(&state.articles[curr_idx], &new_article);
+                    }
+
+                    linked += 1;
+                    if self.forbidden.allows(&new_article) {
+                        allowed += 1;
+                    }
+                    if self.forbidden.contains(&new_article) || outside.contains(&new_article) {
+                        continue;
+                    }
+
+                    let visited = visited_idx(state, spill.as_mut(), &new_article);
+                    let Some(visited) = self.spilled(visited) else {
+                        return false;
+                    };
+                    if let Some(idx) = visited {
+                        // Also reached from this article on a path just as short
+                        if c.all && idx > end_idx {
+                            let parent = match spill {
+                                Some(spill) => spill.parent(state, idx),
+                                None => Ok(state.article_parent[&idx]),
+                            };
+                            let Some(parent) = self.spilled(parent) else {
+                                return false;
+                            };
+                            if parent != curr_idx {
+                                let parents = state.other_parents.entry(idx).or_default();
+                                if !parents.contains(&curr_idx) {
+                                    parents.push(curr_idx);
+                                }
+                            }
+                        }
+                        continue;
+                    }
+
+                    if self.over_limit(state.articles.len() - 1) {
+                        break 'level;
+                    }
+
+                    let is_target = self.end.contains(&new_article);
+                    // Left unvisited, to be reached again further on
+                    if is_target && self.too_short(depth as usize + 1) {
+                        continue;
+                    }
+
+                    if c.prioritize_see_also && links.see_also.contains(&new_article) {
+                        self.see_also.insert(new_article.clone());
+                    }
+
+                    self.event(SearchEvent::Discovered {
+                        article: &new_article,
+                        depth: depth + 1,
+                    });
+
+                    state.articles.push(new_article.clone());
+                    let new_idx = state.articles.len() - 1;
+                    state.visited.insert(new_article, new_idx);
+                    state.article_parent.insert(new_idx, curr_idx);
+
+                    state.next_level_len += 1;
+
+                    if is_target {
+                        if c.all {
+                            found.push(new_idx);
+                        } else {
+                            let res = state_paths(state, spill.as_mut(), new_idx, false);
+                            if let Some(paths) = self.spilled(res) {
+                                self.report_path(&paths[0].iter().collect::<Vec<_>>());
+                            }
+                            return true;
+                        }
+                    }
+                }
+            }
+
+            // Those left out of the sample are skipped along with those
+            // expanded
+            if sample.is_some_and(|mut sample| sample.next().is_none()) {
+                state.curr_idx = end_idx;
+            }
+
+            // Every parent of the targets is known once the whole depth has
+            // been expanded. Targets reached through a redirect are a link
+            // closer than those linked to.
+            if !found.is_empty() {
+                if found.iter().any(|&idx| idx <= end_idx) {
+                    found.retain(|&idx| idx <= end_idx);
+                }
+
+                for idx in found {
+                    let res = state_paths(state, spill.as_mut(), idx, true);
+                    let Some(paths) = self.spilled(res) else {
+                        return false;
+                    };
+                    for path in paths {
+                        self.report_path(&path.iter().collect::<Vec<_>>());
+                    }
+                }
+                return true;
+            }
+
+            if self.limit.is_some() {
+                return false;
+            }
+
+            if self.forbidden.has_allowed() && linked > 0 && allowed == 0 {
+                self.progress.suspend(|| {
+                    warn!(
+                        "None of the {} links at depth {} match the allowed patterns",
+                        linked, depth
+                    )
+                });
+            }
+
+            self.event(SearchEvent::LevelCompleted {
+                depth,
+                articles: state.articles.len() - 1,
+            });
+            // Stopped by `on_event`
+            if self.limit.is_some() {
+                return false;
+            }
+
+            if c.prioritize_see_also {
+                state.put_first(end_idx + 1, |article| self.see_also.contains(article));
+            }
+
+            if let Some(stats) = &mut self.stats {
+                stats.finish_level(state.articles.len() - 1, &self.progress);
+            }
+        }
+
+        false
+    }
+
+    /// Follows the links of each article before those of its siblings, down
+    /// to the maximum depth. Articles are only kept out of the current path,
+    /// so other branches can go through them.
+    async fn depth_first(&mut self) {
+        let c = self.c;
+
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+
+        self.progress.set_prefix("DFS");
+        self.progress.set_length(1);
+        self.progress.set_position(0);
+
+        self.dfs(c.max_depth + 1, &mut HashSet::new(), None).await;
+    }
+
+    /// Runs depth-first searches limited to one link, then two and so on, so
+    /// the first path found is a shortest one
+    async fn iddfs(&mut self) {
+        let c = self.c;
+
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+
+        // Targets already reported with --all
+        let mut reported = HashSet::new();
+        // Shallowest depth each article was expanded at in this iteration
+        let mut table = c.transposition_table.then(HashMap::new);
+
+        for limit in 1..(c.max_depth + 2) {
+            self.progress.set_prefix(format!("depth limit {}", limit));
+            self.progress.set_length(1);
+            self.progress.set_position(0);
+
+            // Articles get more links to go below them with each limit
+            if let Some(table) = &mut table {
+                table.clear();
+            }
+            if self.dfs(limit, &mut reported, table.as_mut()).await && !c.all || self.stopped() {
+                return;
+            }
+        }
+    }
+
+    /// Depth-first search that expands articles up to `limit - 1` links
+    /// away from `start`. Returns whether a new path was found. Articles
+    /// in `table` are only expanded again closer to `start` than they were,
+    /// since the links below them were gone through already.
+    async fn dfs(
+        &mut self,
+        limit: u32,
+        reported: &mut HashSet<String>,
+        mut table: Option<&mut HashMap<String, usize>>,
+    ) -> bool {
+        struct Frame {
+            article: String,
+            links: std::vec::IntoIter<String>,
+        }
+
+        /// Whether `article` is in `table` at `depth` or closer to the start
+        fn expanded_above(
+            table: Option<&HashMap<String, usize>>,
+            article: &str,
+            depth: usize,
+        ) -> bool {
+            table
+                .and_then(|table| table.get(article))
+                .is_some_and(|&expanded| expanded <= depth)
+        }
+
+        let c = self.c;
+        let limit = limit as usize;
+
+        let mut found = false;
+        let mut pruned = 0;
+
+        for start in self.start.clone() {
+            let Some((start, links)) = self.expand(start, None, 0).await else {
+                if self.stopped() {
+                    return found;
+                }
+                continue;
+            };
+            if let Some(table) = table.as_deref_mut() {
+                table.insert(start.clone(), 0);
+            }
+            let mut on_path = HashSet::from([start.clone()]);
+            let mut stack = vec![Frame {
+                article: start,
+                links: links.into_iter(),
+            }];
+
+            while let Some(frame) = stack.last_mut() {
+                let Some(link) = frame.links.next() else {
+                    on_path.remove(&frame.article);
+                    stack.pop();
+                    self.progress.inc(1);
+                    continue;
+                };
+
+                if let Some(graph) = &mut self.graph {
+                    graph.add_edge(&frame.article, &link);
+                }
+
+                if on_path.contains(&link) || self.forbidden.contains(&link) {
+                    continue;
+                }
+
+                if self.end.contains(&link) {
+                    if reported.insert(link.clone()) {
+                        let mut path: Vec<_> = stack.iter().map(|frame| &frame.article).collect();
+                        path.push(&link);
+                        self.report_path(&path);
+
+                        found = true;
+                        if !c.all {
+                            return true;
+                        }
+                    }
+                    continue;
+                }
+
+                if stack.len() >= limit {
+                    continue;
+                }
+
+                if expanded_above(table.as_deref(), &link, stack.len()) {
+                    pruned += 1;
+                    continue;
+                }
+
+                if self.timed_out() {
+                    return found;
+                }
+
+                let parent = stack.last().map(|frame| frame.article.as_str());
+                let Some((article, links)) = self.expand(link, parent, stack.len()).await else {
+                    if self.stopped() {
+                        return found;
+                    }
+                    continue;
+                };
+
+                if on_path.contains(&article) || self.forbidden.contains(&article) {
+                    continue;
+                }
+
+                // Redirected to a target
+                if self.end.contains(&article) {
+                    if reported.insert(article.clone()) {
+                        let mut path: Vec<_> = stack.iter().map(|frame| &frame.article).collect();
+                        path.push(&article);
+                        self.report_path(&path);
+
+                        found = true;
+                        if !c.all {
+                            return true;
+                        }
+                    }
+                    continue;
+                }
+
+                // Reached through a redirect
+                if expanded_above(table.as_deref(), &article, stack.len()) {
+                    pruned += 1;
+                    continue;
+                }
+                if let Some(table) = table.as_deref_mut() {
+                    table.insert(article.clone(), stack.len());
+                }
+
+                if self.over_limit(self.pushed) {
+                    return found;
+                }
+                self.pushed += 1;
+
+                self.progress.inc_length(1);
+                on_path.insert(article.clone());
+                stack.push(Frame {
+                    article,
+                    links: links.into_iter(),
+                });
+
+                let stack_bytes: usize = stack
+                    .iter()
+                    .map(|frame| {
+                        titles_bytes(iter::once(&frame.article))
+                            + titles_bytes(frame.links.as_slice())
+                    })
+                    .sum();
+                let table_bytes = table.as_deref().map_or(0, |table| {
+                    titles_bytes(table.keys()) + table.len() * mem::size_of::<usize>()
+                });
+                self.note_memory(stack_bytes + titles_bytes(&on_path) + table_bytes);
+            }
+        }
+
+        if table.is_some() {
+            debug!(
+                "Depth limit {}: skipped {} articles already expanded",
+                limit, pruned
+            );
+        }
+        found
+    }
+
+    /// Fetches the links of `article`, returning its canonical title along
+    /// with them. Returns `None` if it can't be fetched or is skipped.
+    async fn expand(
+        &mut self,
+        article: String,
+        parent: Option<&str>,
+        depth: usize,
+    ) -> Option<(String, Vec<String>)> {
+        let c = self.c;
+
+        let res = self.fetcher.clone().links(article.clone()).await;
+        self.expanding(&article, depth as u32, parent, res.as_ref().ok());
+
+        let links = match res {
+            Ok(links) => links,
+            Err(err) => {
+                self.fetch_failed(err);
+                return None;
+            }
+        };
+
+        self.note_anchors(&links);
+        let article = links.canonical.unwrap_or(article);
+
+        if c.no_disambig && links.disambiguation {
+            debug!(
+                "Skipping disambiguation page {}",
+                normalize_display(&article)
+            );
+            return None;
+        }
+
+        if self.over_fan_out(&article, links.titles.len()) || self.is_stub(&article, links.size) {
+            return None;
+        }
+
+        let mut titles = links.titles;
+        match self.outside_categories(&titles).await {
+            Ok(outside) => titles.retain(|title| !outside.contains(title)),
+            Err(err) => {
+                self.fetch_failed(err);
+                return None;
+            }
+        }
+
+        self.progress.set_message(normalize_display(&article));
+
+        Some((article, titles))
+    }
+
+    /// Expands articles in order of path length plus the estimate of
+    /// `astar_heuristic`
+    async fn astar(&mut self) {
+        let c = self.c;
+        let fetcher = self.fetcher.clone();
+
+        let mut targets = Vec::new();
+        for end in self.end.clone() {
+            match fetcher.clone().categories(end).await {
+                Ok(categories) => targets.push(categories),
+                Err(err) => {
+                    if self.fetch_failed(err) {
+                        return;
+                    }
+                }
+            }
+        }
+        let weight = if c.suboptimal_ok {
+            SUBOPTIMAL_WEIGHT
+        } else {
+            1
+        };
+
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+        self.progress.set_prefix("A*");
+
+        // Same layout as BfsState, index 0 is the roots' parent
+        let mut articles = vec![String::new()];
+        let mut article_parent = HashMap::new();
+        let mut depth = vec![0];
+        let mut index = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        for start in &self.start {
+            if index.contains_key(start) {
+                continue;
+            }
+            articles.push(start.clone());
+            depth.push(0);
+            index.insert(start.clone(), articles.len() - 1);
+            article_parent.insert(articles.len() - 1, 0);
+            queue.push(Reverse((0, 0, articles.len() - 1)));
+        }
+
+        let mut expanded = HashSet::new();
+        let mut reported = HashSet::new();
+        // Page views of the links seen, with `popularity_weight`
+        let mut views = HashMap::new();
+
+        while let Some(Reverse((_, g, idx))) = queue.pop() {
+            // Outdated by a shorter path or already expanded
+            if g > depth[idx] || g > c.max_depth || !expanded.insert(idx) {
+                continue;
+            }
+
+            if self.timed_out() {
+                return;
+            }
+
+            let article = articles[idx].clone();
+            // Linked articles come from the API, which isn't timed
+            self.expanding(
+                &article,
+                g,
+                parent_of(&articles, &article_parent, idx),
+                None,
+            );
+
+            self.progress.set_length(articles.len() as u64 - 1);
+            self.progress.set_position(expanded.len() as u64);
+            self.progress.set_message(normalize_display(&article));
+
+            let linked = match fetcher.clone().linked(article.clone()).await {
+                Ok(linked) => linked,
+                Err(err) => {
+                    if self.fetch_failed(err) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            if self.over_fan_out(&article, linked.len()) {
+                continue;
+            }
+
+            if c.popularity_weight.is_some() {
+                let unknown: Vec<_> = linked
+                    .iter()
+                    .map(|link| link.title.clone())
+                    .filter(|title| {
+                        !views.contains_key(title)
+                            && !self.forbidden.contains(title)
+                            && !self.end.contains(title)
+                    })
+                    .collect();
+                let fetched: Vec<_> = stream::iter(unknown)
+                    .map(|title| {
+                        let fetcher = fetcher.clone();
+                        async move { (title.clone(), fetcher.pageviews(title).await) }
+                    })
+                    .buffer_unordered(c.workers)
+                    .collect()
+                    .await;
+
+                for (title, res) in fetched {
+                    match res {
+                        Ok(count) => {
+                            views.insert(title, count);
+                        }
+                        Err(err) => {
+                            if self.fetch_failed(err) {
+                                return;
+                            }
+                            // Counted as obscure rather than fetched again
+                            views.insert(title, 0);
+                        }
+                    }
+                }
+            }
+
+            for link in linked {
+                if let Some(graph) = &mut self.graph {
+                    graph.add_edge(&article, &link.title);
+                }
+
+                if self.forbidden.contains(&link.title) {
+                    continue;
+                }
+
+                if self.end.contains(&link.title) {
+                    if reported.insert(link.title.clone()) {
+                        let mut path = reconstruct_path(&articles, &article_parent, idx);
+                        path.push(&link.title);
+                        self.report_path(&path);
+
+                        if !c.all {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+
+                if c.no_disambig && link.disambiguation {
+                    debug!(
+                        "Skipping disambiguation page {}",
+                        normalize_display(&link.title)
+                    );
+                    continue;
+                }
+
+                // The categories came with the link
+                if !self.categories.is_empty()
+                    && !link
+                        .categories
+                        .iter()
+                        .any(|category| self.categories.contains(&category_name(category)))
+                {
+                    continue;
+                }
+
+                let child = match index.get(&link.title) {
+                    Some(&child) if depth[child] <= g + 1 => continue,
+                    Some(&child) => child,
+                    None => {
+                        if self.over_limit(articles.len() - 1) {
+                            return;
+                        }
+                        self.event(SearchEvent::Discovered {
+                            article: &link.title,
+                            depth: g + 1,
+                        });
+                        articles.push(link.title.clone());
+                        depth.push(g + 1);
+                        index.insert(link.title.clone(), articles.len() - 1);
+                        articles.len() - 1
+                    }
+                };
+                depth[child] = g + 1;
+                article_parent.insert(child, idx);
+
+                let h = astar_heuristic(&link.categories, &targets, weight);
+                let mut priority = u64::from(g + 1) * LINK_COST + h;
+                if let Some(popularity) = c.popularity_weight {
+                    let factor = popularity_factor(views[&link.title], popularity);
+                    priority = (priority as f64 * factor).round() as u64;
+                }
+                queue.push(Reverse((priority, g + 1, child)));
+            }
+        }
+    }
+
+    /// Dijkstra's algorithm on the number of links in a path, then on the
+    /// sum of the byte offsets of those links in their articles. Of the
+    /// shortest paths, the one whose links come earliest is found.
+    async fn weighted(&mut self) {
+        self.cheapest_first(
+            "weighted",
+            |links, offsets| (links, offsets),
+            |_, offset, _, _, _| offset as u64,
+            |_| None,
+        )
+        .await;
+    }
+
+    /// Dijkstra's algorithm on the sum of the weights `weight_fns` give the
+    /// links of a path, so the one found weighs the least rather than being
+    /// the shortest
+    async fn dijkstra(&mut self) {
+        let c = self.c;
+        let weight_fns = if c.weight_fns.is_empty() {
+            &[WeightFn::Uniform][..]
+        } else {
+            &c.weight_fns
+        };
+
+        self.cheapest_first(
+            "dijkstra",
+            |links, weight| (weight, links),
+            |link, offset, section_depth, degree, end| {
+                let weight: f64 = weight_fns
+                    .iter()
+                    .map(|weight_fn| weight_fn.weight(link, offset, section_depth, degree, end))
+                    .product();
+                (weight * WEIGHT_UNITS).round() as u64
+            },
+            |weight| Some(weight as f64 / WEIGHT_UNITS),
+        )
+        .await;
+    }
+
+    /// Dijkstra's algorithm, expanding articles in the order of the `key`
+    /// of the best path to them, from the number of its links and the sum
+    /// of what `link_weight` gives those, taking the link, its offset, the
+    /// depth of its section, the links of its article and the targets.
+    /// Paths are reported with what `path_weight` makes of that sum.
+    async fn cheapest_first<K: Copy + Ord>(
+        &mut self,
+        prefix: &'static str,
+        key: impl Fn(u32, u64) -> K,
+        link_weight: impl Fn(&str, usize, u8, usize, &[String]) -> u64,
+        path_weight: impl Fn(u64) -> Option<f64>,
+    ) {
+        let c = self.c;
+        let fetcher = self.fetcher.clone();
+
+        if let Some(graph) = &mut self.graph {
+            for start in &self.start {
+                graph.add_start(start);
+            }
+        }
+        self.progress.set_prefix(prefix);
+
+        // Same layout as BfsState, index 0 is the roots' parent
+        let mut articles = vec![String::new()];
+        let mut article_parent = HashMap::new();
+        // Key of the best path to each article
+        let mut cost = vec![key(0, 0)];
+        let mut index = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        for start in &self.start {
+            if index.contains_key(start) {
+                continue;
+            }
+            articles.push(start.clone());
+            cost.push(key(0, 0));
+            index.insert(start.clone(), articles.len() - 1);
+            article_parent.insert(articles.len() - 1, 0);
+            queue.push(Reverse((key(0, 0), 0, 0, articles.len() - 1)));
+        }
+
+        let mut expanded = HashSet::new();
+        let mut reported = HashSet::new();
+
+        while let Some(Reverse((best, g, weight, idx))) = queue.pop() {
+            // Outdated by a better path or already expanded
+            if best > cost[idx] || !expanded.insert(idx) {
+                continue;
+            }
+
+            if self.timed_out() {
+                return;
+            }
+
+            // Only the best path to a target is known once it's taken out of
+            // the queue
+            if self.end.contains(&articles[idx]) {
+                if reported.insert(articles[idx].clone()) {
+                    let path = reconstruct_path(&articles, &article_parent, idx);
+                    self.report_weighed_path(&path, path_weight(weight));
+
+                    if !c.all {
+                        return;
+                    }
+                }
+                continue;
+            }
+            if g > c.max_depth {
+                continue;
+            }
+
+            let article = articles[idx].clone();
+            self.progress.set_length(articles.len() as u64 - 1);
+            self.progress.set_position(expanded.len() as u64);
+            self.progress.set_message(normalize_display(&article));
+
+            let res = fetcher.clone().links(article.clone()).await;
+            self.expanding(
+                &article,
+                g,
+                parent_of(&articles, &article_parent, idx),
+                res.as_ref().ok(),
+            );
+
+            let links = match res {
+                Ok(links) => links,
+                Err(err) => {
+                    if self.fetch_failed(err) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            self.note_anchors(&links);
+
+            // Redirected: continue under the canonical title, unless it
+            // was already reached
+            if let Some(canonical) = links.canonical {
+                if index.contains_key(&canonical) || self.forbidden.contains(&canonical) {
+                    continue;
+                }
+
+                index.insert(canonical.clone(), idx);
+                articles[idx] = canonical;
+
+                if self.end.contains(&articles[idx]) {
+                    if reported.insert(articles[idx].clone()) {
+                        let path = reconstruct_path(&articles, &article_parent, idx);
+                        self.report_weighed_path(&path, path_weight(weight));
+
+                        if !c.all {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if c.no_disambig && links.disambiguation {
+                debug!(
+                    "Skipping disambiguation page {}",
+                    normalize_display(&articles[idx])
+                );
+                continue;
+            }
+
+            if self.over_fan_out(&articles[idx], links.titles.len()) {
+                continue;
+            }
+            if self.is_stub(&articles[idx], links.size) {
+                continue;
+            }
+
+            let outside = match self.outside_categories(&links.titles).await {
+                Ok(outside) => outside,
+                Err(err) => {
+                    if self.fetch_failed(err) {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            let degree = links.titles.len();
+            // Links from the API or a dump have no offsets nor sections
+            let offsets = links.offsets.into_iter().chain(iter::repeat(0));
+            let section_depths = links.section_depths.into_iter().chain(iter::repeat(0));
+            for ((link, offset), section_depth) in
+                links.titles.into_iter().zip(offsets).zip(section_depths)
+            {
+                if let Some(graph) = &mut self.graph {
+                    graph.add_edge(&articles[idx], &link);
+                }
+
+                if self.forbidden.contains(&link) || outside.contains(&link) {
+                    continue;
+                }
+
+                let next_weight =
+                    weight + link_weight(&link, offset, section_depth, degree, &self.end);
+                let next = key(g + 1, next_weight);
+                let child = match index.get(&link) {
+                    Some(&child) if cost[child] <= next => continue,
+                    Some(&child) => child,
+                    None => {
+                        if self.over_limit(articles.len() - 1) {
+                            return;
+                        }
+                        self.event(SearchEvent::Discovered {
+                            article: &link,
+                            depth: g + 1,
+                        });
+                        articles.push(link.clone());
+                        cost.push(next);
+                        index.insert(link, articles.len() - 1);
+                        articles.len() - 1
+                    }
+                };
+                cost[child] = next;
+                article_parent.insert(child, idx);
+
+                queue.push(Reverse((next, g + 1, next_weight, child)));
+            }
+        }
+    }
+}
+
+/// Everything needed to resume a breadth-first search at the start of a
+/// depth
+#[derive(Serialize, Deserialize)]
+pub struct BfsState {
+    pub start: Vec<String>,
+    pub end: Vec<String>,
+    pub max_depth: u32,
+    pub(crate) depth: u32,
+    pub(crate) articles: Vec<String>,
+    pub(crate) article_parent: HashMap<usize, usize>,
+    pub(crate) visited: HashMap<String, usize>,
+    /// Parents of articles on other paths as short as the one through
+    /// `article_parent`, only kept with `all`
+    #[serde(default)]
+    pub(crate) other_parents: HashMap<usize, Vec<usize>>,
+    pub(crate) curr_idx: usize,
+    pub(crate) next_level_len: usize,
+    /// Articles of `depth` left to expand, when saved in the middle of it
+    #[serde(default)]
+    pub(crate) level_left: usize,
+    /// Articles in each depth started so far
+    #[serde(default)]
+    pub(crate) frontier_sizes: Vec<usize>,
+}
+
+impl BfsState {
+    pub(crate) fn new(start: &[String], end: &[String], max_depth: u32) -> Self {
+        let mut state = Self {
+            start: start.to_vec(),
+            end: end.to_vec(),
+            max_depth,
+            depth: 0,
+            articles: vec![String::new()],
+            article_parent: HashMap::new(),
+            visited: HashMap::new(),
+            other_parents: HashMap::new(),
+            curr_idx: 0,
+            next_level_len: 0,
+            level_left: 0,
+            frontier_sizes: Vec::new(),
+        };
+
+        // Every start is at depth 0, with the sentinel as its parent
+        for article in start {
+            if state.visited.contains_key(article) {
+                continue;
+            }
+            state.articles.push(article.clone());
+            let idx = state.articles.len() - 1;
+            state.visited.insert(article.clone(), idx);
+            state.article_parent.insert(idx, 0);
+            state.next_level_len += 1;
+        }
+
+        state
+    }
+
+    /// Bytes the articles and their parents take in memory, roughly
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let other_parents: usize = self.other_parents.values().map(Vec::len).sum();
+        titles_bytes(&self.articles)
+            + titles_bytes(self.visited.keys())
+            + (self.visited.len() + 2 * self.article_parent.len() + other_parents)
+                * mem::size_of::<usize>()
+    }
+
+    /// Moves the articles from `level_start` on for which `first` is true
+    /// before the others, keeping the order of both
+    pub(crate) fn put_first(&mut self, level_start: usize, first: impl Fn(&str) -> bool) {
+        let (mut order, rest): (Vec<usize>, Vec<usize>) =
+            (level_start..self.articles.len()).partition(|&idx| first(&self.articles[idx]));
+        if order.is_empty() || rest.is_empty() {
+            return;
+        }
+        order.extend(rest);
+
+        let moved: HashMap<usize, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(i, &idx)| (idx, level_start + i))
+            .collect();
+        let articles: Vec<_> = order
+            .iter()
+            .map(|&idx| mem::take(&mut self.articles[idx]))
+            .collect();
+        self.articles.truncate(level_start);
+        self.articles.extend(articles);
+
+        for idx in level_start..self.articles.len() {
+            self.visited.insert(self.articles[idx].clone(), idx);
+        }
+        // Their parents are all before `level_start`
+        let new_idx = |idx: usize| moved.get(&idx).copied().unwrap_or(idx);
+        self.article_parent = mem::take(&mut self.article_parent)
+            .into_iter()
+            .map(|(child, parent)| (new_idx(child), parent))
+            .collect();
+        self.other_parents = mem::take(&mut self.other_parents)
+            .into_iter()
+            .map(|(child, parents)| (new_idx(child), parents))
+            .collect();
+    }
+
+    /// Counts the articles in each of the first `depths` depths, from how
+    /// far each is from the start articles
+    fn level_sizes(&self, depths: usize) -> Vec<usize> {
+        let mut sizes = vec![0; depths];
+        let mut depth = vec![0; self.articles.len()];
+
+        // Parents come before their children
+        for idx in 1..self.articles.len() {
+            let Some(&parent) = self.article_parent.get(&idx) else {
+                continue;
+            };
+            depth[idx] = if parent == 0 { 0 } else { depth[parent] + 1 };
+            if let Some(size) = sizes.get_mut(depth[idx]) {
+                *size += 1;
+            }
+        }
+        sizes
+    }
+
+    /// Paths the search took from the start to `article`, all as short,
+    /// the first through the article it was first reached from. Empty if
+    /// it wasn't reached.
+    pub fn paths_to(&self, article: &str) -> Vec<Vec<String>> {
+        let Some(&idx) = self.visited.get(article) else {
+            return Vec::new();
+        };
+        reconstruct_paths(
+            &self.articles,
+            &self.article_parent,
+            &self.other_parents,
+            idx,
+        )
+        .into_iter()
+        .map(|path| path.into_iter().cloned().collect())
+        .collect()
+    }
+
+    pub fn load(file: &FilePath) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(file)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes the state to a temporary file first, so an interrupted write
+    /// never leaves a corrupt checkpoint behind
+    pub(crate) fn save(&self, file: &FilePath) -> io::Result<()> {
+        let mut tmp = file.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&tmp, file)
+    }
+}
@@ -0,0 +1,379 @@
+//! The articles of a breadth-first search kept on disk with --max-memory
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    env,
+    fs::{self, File},
+    hash::BuildHasher,
+    io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    mem,
+    num::NonZeroUsize,
+    ops::Range,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use lru::LruCache;
+use tracing::debug;
+
+use crate::search::BfsState;
+
+/// Articles read back from disk kept in memory, the parents near the start
+/// being read again for every path
+const CACHE_ENTRIES: NonZeroUsize = match NonZeroUsize::new(4096) {
+    Some(entries) => entries,
+    None => unreachable!(),
+};
+
+/// Bytes an article takes in `BfsState` on top of its title: its slot in
+/// `articles` and its entry in `article_parent`
+const ENTRY_BYTES: usize = mem::size_of::<String>() + 2 * mem::size_of::<usize>() + 8;
+
+/// Bytes its entry in `visited` takes on top of the second copy of its title
+const VISITED_BYTES: usize = mem::size_of::<String>() + mem::size_of::<usize>() + 1;
+
+/// Bytes an article on disk takes in `hashes`
+const HASH_BYTES: usize = mem::size_of::<u64>() + mem::size_of::<usize>() + 1;
+
+/// Spill files made by this process, numbered so that searches running at
+/// the same time don't share one
+static SPILLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Moves the articles of a `BfsState` and their parents to an append-only
+/// file once they take more than `max_bytes` of memory, each as a line of
+/// its index, parent index and title separated by tabs, which titles can't
+/// have. An article renamed after that gets another line, the last one
+/// counting. Articles on disk keep their slot in `articles`, empty, and
+/// lose their entry in `article_parent`. Their entry in `visited` is
+/// replaced by the hash of their title, the title being read back to
+/// tell them apart, unless they were found under another title before a
+/// redirect. The file is removed when the spill is dropped.
+pub(crate) struct Spill {
+    path: PathBuf,
+    file: File,
+    /// Where the last line of each article on disk starts, by index
+    offsets: Vec<u64>,
+    /// Articles before this index are on disk
+    spilled: usize,
+    /// Articles on disk read back into the state by `restore`, with the
+    /// title they had there
+    restored: Vec<(usize, String)>,
+    cache: LruCache<usize, (String, usize)>,
+    hasher: RandomState,
+    /// Articles on disk by the hash of their title
+    hashes: HashMap<u64, usize>,
+    /// Articles on disk whose title has the hash of another in `hashes`
+    collided: HashMap<u64, Vec<usize>>,
+    max_bytes: usize,
+    /// Bytes taken by the articles in memory, counted up to `counted`
+    bytes: usize,
+    counted: usize,
+    /// Most bytes the articles in memory and `hashes` took at once
+    peak: usize,
+}
+
+impl Spill {
+    pub(crate) fn new(max_bytes: usize) -> io::Result<Self> {
+        let path = env::temp_dir().join(format!(
+            "wiki-path-{}-{}.spill",
+            process::id(),
+            SPILLS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(Self {
+            path,
+            file,
+            // The sentinel at 0 is never moved
+            offsets: vec![0],
+            spilled: 1,
+            restored: Vec::new(),
+            cache: LruCache::new(CACHE_ENTRIES),
+            hasher: RandomState::new(),
+            hashes: HashMap::new(),
+            collided: HashMap::new(),
+            max_bytes,
+            bytes: 0,
+            counted: 1,
+            peak: 0,
+        })
+    }
+
+    /// Most bytes the articles kept in memory took at once, with the hashes
+    /// of the titles of those on disk
+    pub(crate) fn peak_bytes(&self) -> usize {
+        self.peak.max(self.bytes + self.hashes_bytes())
+    }
+
+    fn hashes_bytes(&self) -> usize {
+        let collided: usize = self.collided.values().map(Vec::len).sum();
+        (self.hashes.len() + collided) * HASH_BYTES
+    }
+
+    fn on_disk(&self, state: &BfsState, idx: usize) -> bool {
+        idx != 0 && idx < self.spilled && state.articles[idx].is_empty()
+    }
+
+    /// Title and parent index of the article at `idx`, which is on disk
+    fn read(&mut self, idx: usize) -> io::Result<(String, usize)> {
+        if let Some(entry) = self.cache.get(&idx) {
+            return Ok(entry.clone());
+        }
+
+        let mut reader = BufReader::new(&self.file);
+        reader.seek(SeekFrom::Start(self.offsets[idx]))?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let entry = parse_line(&line)
+            .filter(|(read, _, _)| *read == idx)
+            .map(|(_, title, parent)| (title, parent))
+            .ok_or_else(corrupt)?;
+
+        self.cache.put(idx, entry.clone());
+        Ok(entry)
+    }
+
+    /// Title of the article at `idx`, from memory or disk
+    pub(crate) fn title(&mut self, state: &BfsState, idx: usize) -> io::Result<String> {
+        if self.on_disk(state, idx) {
+            return Ok(self.read(idx)?.0);
+        }
+        Ok(state.articles[idx].clone())
+    }
+
+    /// Index of the article found under `title`, from `visited` or by the
+    /// hash of the title if it's on disk. `None` if it wasn't found.
+    pub(crate) fn visited(&mut self, state: &BfsState, title: &str) -> io::Result<Option<usize>> {
+        if let Some(&idx) = state.visited.get(title) {
+            return Ok(Some(idx));
+        }
+
+        let hash = self.hasher.hash_one(title);
+        let same_hash: Vec<usize> = self
+            .hashes
+            .get(&hash)
+            .into_iter()
+            .chain(self.collided.get(&hash).into_iter().flatten())
+            .copied()
+            .collect();
+        for idx in same_hash {
+            if self.title(state, idx)? == title {
+                return Ok(Some(idx));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Index of the article the one at `idx` was first reached from, `0`
+    /// for the start articles
+    pub(crate) fn parent(&mut self, state: &BfsState, idx: usize) -> io::Result<usize> {
+        match state.article_parent.get(&idx) {
+            Some(&parent) => Ok(parent),
+            None => Ok(self.read(idx)?.1),
+        }
+    }
+
+    /// Titles of the articles in `range`, reading those on disk in the
+    /// order they were written
+    pub(crate) fn titles(
+        &mut self,
+        state: &BfsState,
+        range: Range<usize>,
+    ) -> io::Result<Vec<String>> {
+        let mut titles = Vec::with_capacity(range.len());
+        let on_disk = range.start..range.end.min(self.spilled);
+
+        if !on_disk.is_empty() {
+            let mut pos = self.offsets[on_disk.start];
+            // Sharing the position with `read`, and seeking again after it
+            let mut reader = BufReader::new(self.file.try_clone()?);
+            reader.seek(SeekFrom::Start(pos))?;
+            let mut line = String::new();
+
+            for idx in on_disk {
+                let (title, renamed) = loop {
+                    line.clear();
+                    let start = pos;
+                    pos += reader.read_line(&mut line)? as u64;
+                    match parse_line(&line) {
+                        Some((read, title, _)) if read == idx => {
+                            break (title, self.offsets[idx] != start)
+                        }
+                        // The line of an article renamed since
+                        Some(_) => {}
+                        None => return Err(corrupt()),
+                    }
+                };
+
+                let title = if !self.on_disk(state, idx) {
+                    state.articles[idx].clone()
+                } else if renamed {
+                    let title = self.read(idx)?.0;
+                    reader.seek(SeekFrom::Start(pos))?;
+                    title
+                } else {
+                    title
+                };
+                titles.push(title);
+            }
+        }
+
+        let in_memory = range.start.max(self.spilled)..range.end;
+        titles.extend(state.articles[in_memory].iter().cloned());
+        Ok(titles)
+    }
+
+    /// Reads the article at `idx` back into the state if it's on disk, to
+    /// be expanded. It goes back with the next `check` to move articles.
+    pub(crate) fn restore(&mut self, state: &mut BfsState, idx: usize) -> io::Result<()> {
+        if !self.on_disk(state, idx) {
+            return Ok(());
+        }
+        let (title, parent) = self.read(idx)?;
+        self.bytes += title.len() + ENTRY_BYTES;
+        state.articles[idx] = title.clone();
+        state.article_parent.insert(idx, parent);
+        self.restored.push((idx, title));
+        Ok(())
+    }
+
+    /// Moves the articles in memory to disk if they take more than
+    /// `max_bytes`
+    pub(crate) fn check(&mut self, state: &mut BfsState) -> io::Result<()> {
+        // Once in `articles` and once in `visited`
+        for title in &state.articles[self.counted..] {
+            self.bytes += 2 * title.len() + ENTRY_BYTES + VISITED_BYTES;
+        }
+        self.counted = state.articles.len();
+
+        if self.bytes > self.max_bytes {
+            self.peak = self.peak_bytes();
+            self.spill(state)?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self, state: &mut BfsState) -> io::Result<()> {
+        let mut pos = self.file.seek(SeekFrom::End(0))?;
+        let mut writer = BufWriter::new(&self.file);
+        let mut line = |idx: usize, title: &str, offsets: &mut Vec<u64>| -> io::Result<()> {
+            let parent = state.article_parent[&idx];
+            let line = format!("{}\t{}\t{}\n", idx, parent, title);
+            writer.write_all(line.as_bytes())?;
+            match offsets.get_mut(idx) {
+                Some(offset) => *offset = pos,
+                None => offsets.push(pos),
+            }
+            pos += line.len() as u64;
+            Ok(())
+        };
+
+        let restored = mem::take(&mut self.restored);
+        for (idx, title) in &restored {
+            if state.articles[*idx] != *title {
+                line(*idx, &state.articles[*idx], &mut self.offsets)?;
+                self.cache.pop(idx);
+            }
+        }
+        for idx in self.spilled..state.articles.len() {
+            line(idx, &state.articles[idx], &mut self.offsets)?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        let (hasher, hashes, collided) = (&self.hasher, &mut self.hashes, &mut self.collided);
+        state.visited.retain(|title, &mut idx| {
+            if state.articles[idx] != *title {
+                return true;
+            }
+            let hash = hasher.hash_one(title);
+            match hashes.get(&hash) {
+                None => {
+                    hashes.insert(hash, idx);
+                }
+                Some(&same) if same != idx => collided.entry(hash).or_default().push(idx),
+                Some(_) => {}
+            }
+            false
+        });
+        state.visited.shrink_to_fit();
+
+        // Only emptied once all of them are on disk
+        let moved = state.articles.len() - self.spilled;
+        for idx in restored
+            .iter()
+            .map(|(idx, _)| *idx)
+            .chain(self.spilled..state.articles.len())
+        {
+            state.articles[idx] = String::new();
+            state.article_parent.remove(&idx);
+        }
+        state.article_parent.shrink_to_fit();
+        self.spilled = state.articles.len();
+        self.bytes = 0;
+
+        debug!(
+            "Moved {} articles to {}, {} in all",
+            moved,
+            self.path.display(),
+            self.spilled - 1
+        );
+        Ok(())
+    }
+
+    /// Articles from the start to the one at `idx`
+    pub(crate) fn path(&mut self, state: &BfsState, mut idx: usize) -> io::Result<Vec<String>> {
+        let mut path = Vec::new();
+        while idx != 0 {
+            path.push(self.title(state, idx)?);
+            idx = self.parent(state, idx)?;
+        }
+        path.reverse();
+        Ok(path)
+    }
+
+    /// Paths from the start to the article at `idx`, through the parent it
+    /// was first reached from and then `other_parents`, like
+    /// `reconstruct_paths`
+    pub(crate) fn paths(&mut self, state: &BfsState, idx: usize) -> io::Result<Vec<Vec<String>>> {
+        let parent = self.parent(state, idx)?;
+        if parent == 0 {
+            return Ok(vec![vec![self.title(state, idx)?]]);
+        }
+
+        let title = self.title(state, idx)?;
+        let others = state.other_parents.get(&idx).into_iter().flatten();
+        let mut paths = Vec::new();
+        for &parent in [parent].iter().chain(others) {
+            for mut path in self.paths(state, parent)? {
+                path.push(title.clone());
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupt spill file")
+}
+
+/// The index, title and parent index of a line of the spill file
+fn parse_line(line: &str) -> Option<(usize, String, usize)> {
+    let mut fields = line.trim_end_matches('\n').splitn(3, '\t');
+    let idx = fields.next()?.parse().ok()?;
+    let parent = fields.next()?.parse().ok()?;
+    let title = fields.next()?.to_string();
+    Some((idx, title, parent))
+}
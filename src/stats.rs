@@ -0,0 +1,112 @@
+//! Per-depth statistics of a breadth-first search, printed with --stats
+
+use std::time::{Duration, Instant};
+
+use indicatif::ProgressBar;
+
+/// Counters of the depth being searched, for --stats
+struct LevelStats {
+    depth: u32,
+    articles: usize,
+    expanded: usize,
+    errors: usize,
+    start: Instant,
+}
+
+/// Statistics printed to stderr with --stats, one row per depth and totals
+/// at the end
+#[derive(Default)]
+pub(crate) struct Stats {
+    level: Option<LevelStats>,
+    levels: usize,
+    expanded: usize,
+    errors: usize,
+    articles: usize,
+    elapsed: Duration,
+}
+
+impl Stats {
+    pub(crate) fn start_level(&mut self, depth: u32, articles: usize, progress: &ProgressBar) {
+        if self.levels == 0 {
+            progress.suspend(|| {
+                eprintln!(
+                    "depth | articles_in_level | articles_expanded | http_errors | cumulative_articles | level_elapsed"
+                )
+            });
+        }
+
+        self.level = Some(LevelStats {
+            depth,
+            articles,
+            expanded: 0,
+            errors: 0,
+            start: Instant::now(),
+        });
+    }
+
+    pub(crate) fn expanded(&mut self) {
+        if let Some(level) = &mut self.level {
+            level.expanded += 1;
+        }
+    }
+
+    pub(crate) fn error(&mut self) {
+        if let Some(level) = &mut self.level {
+            level.errors += 1;
+        }
+    }
+
+    /// Prints the row of the current depth, `cumulative` being the number
+    /// of articles found so far
+    pub(crate) fn finish_level(&mut self, cumulative: usize, progress: &ProgressBar) {
+        let Some(level) = self.level.take() else {
+            return;
+        };
+        let elapsed = level.start.elapsed();
+
+        self.levels += 1;
+        self.expanded += level.expanded;
+        self.errors += level.errors;
+        self.articles = cumulative;
+        self.elapsed += elapsed;
+
+        progress.suspend(|| {
+            eprintln!(
+                "{:>5} | {:>17} | {:>17} | {:>11} | {:>19} | {:#}",
+                level.depth,
+                level.articles,
+                level.expanded,
+                level.errors,
+                cumulative,
+                rounded_duration(elapsed)
+            )
+        });
+    }
+
+    pub(crate) fn print_totals(&self, progress: &ProgressBar) {
+        let levels = self.levels.max(1) as f64;
+        let per_level = self.elapsed.div_f64(levels);
+
+        progress.suspend(|| {
+            eprintln!(
+                "Total: {} depths, {} articles expanded, {} HTTP errors, {} articles discovered, took {:#}",
+                self.levels,
+                self.expanded,
+                self.errors,
+                self.articles,
+                rounded_duration(self.elapsed)
+            );
+            eprintln!(
+                "Average per depth: {:.1} articles expanded, {:.1} HTTP errors, took {:#}",
+                self.expanded as f64 / levels,
+                self.errors as f64 / levels,
+                rounded_duration(per_level)
+            );
+        });
+    }
+}
+
+/// `duration` to the millisecond, for display
+fn rounded_duration(duration: Duration) -> jiff::SignedDuration {
+    jiff::SignedDuration::from_millis(duration.as_millis() as i64)
+}
@@ -0,0 +1,477 @@
+//! Full-terminal view of a breadth-first search, shown with --tui
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ratatui::{
+    crossterm::{
+        cursor::MoveTo,
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        queue,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::{fetch::Thumbnail, wiki::normalize_display};
+
+/// How often the screen is redrawn
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// Rows of the article panel taken by the thumbnail, below the title
+const THUMBNAIL_ROWS: u16 = 5;
+
+/// Progress reported by the search
+pub enum Update {
+    /// A new depth started with `frontier` articles to expand
+    Level {
+        depth: u32,
+        frontier: usize,
+    },
+    /// The links of `article` were fetched; `discovered` articles are
+    /// known so far
+    Expanded {
+        article: String,
+        parent: Option<String>,
+        discovered: usize,
+    },
+    /// The lead image of an article was looked up
+    Thumbnail(Thumbnail),
+    Error(String),
+    Message(String),
+}
+
+/// Whether the terminal can draw images, with the inline images of iTerm2
+/// (which WezTerm has too)
+fn inline_images() -> bool {
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    program == "iTerm.app"
+        || program == "WezTerm"
+        || env::var("LC_TERMINAL").is_ok_and(|terminal| terminal == "iTerm2")
+}
+
+/// Handle to the thread drawing the TUI. Keys pressed there are read back
+/// by the search with `aborted` and `take_save`.
+pub struct Tui {
+    updates: mpsc::Sender<Update>,
+    abort: Arc<AtomicBool>,
+    save: Arc<AtomicBool>,
+    thread: thread::JoinHandle<io::Result<()>>,
+    images: bool,
+}
+
+/// Handle given to the tasks fetching the thumbnails of the articles, which
+/// run alongside those fetching their links
+#[derive(Clone)]
+pub struct Thumbnails {
+    updates: mpsc::Sender<Update>,
+    /// Whether to download the images, or only show their URL
+    pub images: bool,
+}
+
+impl Thumbnails {
+    pub fn send(&self, thumbnail: Thumbnail) {
+        let _ = self.updates.send(Update::Thumbnail(thumbnail));
+    }
+}
+
+impl Tui {
+    /// Takes over the terminal until `stop` is called or `q` is pressed
+    pub fn start() -> Self {
+        let (updates, rx) = mpsc::channel();
+        let abort = Arc::new(AtomicBool::new(false));
+        let save = Arc::new(AtomicBool::new(false));
+        let images = inline_images();
+
+        let thread = thread::spawn({
+            let abort = abort.clone();
+            let save = save.clone();
+            move || {
+                let mut terminal = ratatui::try_init()?;
+                let res = App::new(rx, images).run(&mut terminal, &abort, &save);
+                ratatui::restore();
+                res
+            }
+        });
+
+        Self {
+            updates,
+            abort,
+            save,
+            thread,
+            images,
+        }
+    }
+
+    pub fn thumbnails(&self) -> Thumbnails {
+        Thumbnails {
+            updates: self.updates.clone(),
+            images: self.images,
+        }
+    }
+
+    pub fn update(&self, update: Update) {
+        // The TUI is already gone if the user quit
+        let _ = self.updates.send(update);
+    }
+
+    /// Whether the user asked to stop the search
+    pub fn aborted(&self) -> bool {
+        self.abort.load(Ordering::Relaxed)
+    }
+
+    /// Whether the user asked to save a checkpoint since the last call
+    pub fn take_save(&self) -> bool {
+        self.save.swap(false, Ordering::Relaxed)
+    }
+
+    /// Gives the terminal back
+    pub fn stop(self) -> io::Result<()> {
+        drop(self.updates);
+        self.thread.join().expect("TUI thread panicked")
+    }
+}
+
+struct App {
+    updates: mpsc::Receiver<Update>,
+    start_time: Instant,
+    depth: u32,
+    frontier: usize,
+    discovered: usize,
+    errors: usize,
+    message: Option<String>,
+    /// Expanded articles, in order
+    articles: Vec<String>,
+    /// Index in `articles` of the parent of each article
+    parents: Vec<Option<usize>>,
+    index: HashMap<String, usize>,
+    list: ListState,
+    /// Whether the list scrolls to new articles
+    follow: bool,
+    thumbnails: HashMap<String, Thumbnail>,
+    images: bool,
+    /// Where the thumbnail goes in the article panel, once drawn
+    thumbnail_area: Option<Rect>,
+    /// Article whose thumbnail is on screen
+    drawn: Option<String>,
+}
+
+impl App {
+    fn new(updates: mpsc::Receiver<Update>, images: bool) -> Self {
+        Self {
+            updates,
+            start_time: Instant::now(),
+            depth: 0,
+            frontier: 0,
+            discovered: 0,
+            errors: 0,
+            message: None,
+            articles: Vec::new(),
+            parents: Vec::new(),
+            index: HashMap::new(),
+            list: ListState::default(),
+            follow: true,
+            thumbnails: HashMap::new(),
+            images,
+            thumbnail_area: None,
+            drawn: None,
+        }
+    }
+
+    fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+        abort: &AtomicBool,
+        save: &AtomicBool,
+    ) -> io::Result<()> {
+        loop {
+            loop {
+                match self.updates.try_recv() {
+                    Ok(update) => self.apply(update),
+                    Err(TryRecvError::Empty) => break,
+                    // The search is over
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+            if self.images {
+                self.draw_thumbnail(terminal.backend_mut())?;
+            }
+
+            if !event::poll(REFRESH)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    abort.store(true, Ordering::Relaxed);
+                    return Ok(());
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    abort.store(true, Ordering::Relaxed);
+                    return Ok(());
+                }
+                KeyCode::Char('s') => {
+                    save.store(true, Ordering::Relaxed);
+                    self.message = Some("Saving checkpoint...".to_string());
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.follow = false;
+                    self.list.select_previous();
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.follow = false;
+                    self.list.select_next();
+                }
+                KeyCode::End | KeyCode::Char('G') => self.follow = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Level { depth, frontier } => {
+                self.depth = depth;
+                self.frontier = frontier;
+            }
+            Update::Expanded {
+                article,
+                parent,
+                discovered,
+            } => {
+                let parent = parent.and_then(|parent| self.index.get(&parent).copied());
+                self.index.insert(article.clone(), self.articles.len());
+                self.articles.push(article);
+                self.parents.push(parent);
+                self.discovered = discovered;
+            }
+            Update::Thumbnail(thumbnail) => {
+                self.thumbnails.insert(thumbnail.article.clone(), thumbnail);
+            }
+            Update::Error(err) => {
+                self.errors += 1;
+                self.message = Some(err);
+            }
+            Update::Message(message) => self.message = Some(message),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [left, center, right] = Layout::horizontal([
+            Constraint::Percentage(30),
+            Constraint::Percentage(45),
+            Constraint::Percentage(25),
+        ])
+        .areas(frame.area());
+
+        if self.follow && !self.articles.is_empty() {
+            self.list.select(Some(self.articles.len() - 1));
+        }
+        let list = List::new(
+            self.articles
+                .iter()
+                .map(|article| normalize_display(article)),
+        )
+        .block(Block::bordered().title(" Expanded "))
+        .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let tree =
+            Paragraph::new(self.tree_lines(center)).block(Block::bordered().title(" Search tree "));
+        frame.render_widget(tree, center);
+
+        let [article, right] =
+            Layout::vertical([Constraint::Length(THUMBNAIL_ROWS + 3), Constraint::Min(0)])
+                .areas(right);
+        self.draw_article(frame, article);
+
+        let elapsed = self.start_time.elapsed();
+        let requests = self.articles.len() + self.errors;
+        let rate = requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+        let mut stats = vec![
+            Line::from(format!("Depth: {}", self.depth)),
+            Line::from(format!("Frontier: {}", self.frontier)),
+            Line::from(format!("Expanded: {}", self.articles.len())),
+            Line::from(format!("Discovered: {}", self.discovered)),
+            Line::from(format!("HTTP errors: {}", self.errors)),
+            Line::from(format!("Elapsed: {}s", elapsed.as_secs())),
+            Line::from(format!("Rate: {:.1} req/s", rate)),
+            Line::from(""),
+        ];
+        if let Some(message) = &self.message {
+            stats.push(Line::from(message.as_str()));
+            stats.push(Line::from(""));
+        }
+        stats.push(Line::from("q: stop  s: save").dim());
+        stats.push(Line::from("up/down: scroll  end: follow").dim());
+
+        let stats = Paragraph::new(stats)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::bordered().title(" Stats "));
+        frame.render_widget(stats, right);
+    }
+
+    /// Article selected in the list
+    fn selected(&self) -> Option<&String> {
+        self.articles.get(self.list.selected()?)
+    }
+
+    /// The selected article and its thumbnail, left for `draw_thumbnail`
+    /// to draw if the terminal can, or its URL otherwise
+    fn draw_article(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Article ");
+        let inner = block.inner(area);
+        let selected = self.selected();
+
+        let mut lines = vec![Line::from(
+            selected
+                .map(|article| normalize_display(article))
+                .unwrap_or_default(),
+        )];
+        let thumbnail = selected.and_then(|article| self.thumbnails.get(article));
+        if !self.images || thumbnail.is_none() {
+            lines.push(match thumbnail {
+                Some(thumbnail) => Line::from(thumbnail.url.as_str()).dim(),
+                None => Line::from("No image").dim(),
+            });
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(block);
+        frame.render_widget(paragraph, area);
+
+        let thumbnail_area = Rect {
+            y: inner.y + 1,
+            height: inner.height.saturating_sub(1),
+            ..inner
+        };
+        // Resized: the widgets were drawn over it
+        if self.thumbnail_area != Some(thumbnail_area) {
+            self.drawn = None;
+        }
+        self.thumbnail_area = Some(thumbnail_area);
+    }
+
+    /// Draws the thumbnail of the selected article as an inline image, after
+    /// the widgets so that they don't clear it, when another article is
+    /// selected. The cells under it are left blank in the widgets.
+    fn draw_thumbnail(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let Some(area) = self.thumbnail_area else {
+            return Ok(());
+        };
+        let selected = self.selected().cloned();
+        if self.drawn == selected {
+            return Ok(());
+        }
+
+        // The image of the article selected before
+        if self.drawn.is_some() {
+            let blank = " ".repeat(area.width as usize);
+            for y in area.y..area.y + area.height {
+                queue!(out, MoveTo(area.x, y))?;
+                write!(out, "{}", blank)?;
+            }
+        }
+
+        let image = selected
+            .as_ref()
+            .and_then(|article| self.thumbnails.get(article))
+            .and_then(|thumbnail| thumbnail.image.as_ref());
+        if let Some(image) = image {
+            queue!(out, MoveTo(area.x, area.y))?;
+            write!(
+                out,
+                "\x1b]1337;File=inline=1;size={};height={};preserveAspectRatio=1:{}\x07",
+                image.len(),
+                area.height,
+                BASE64.encode(image)
+            )?;
+            self.drawn = selected;
+        } else {
+            // Drawn once it's fetched
+            self.drawn = None;
+        }
+        out.flush()
+    }
+
+    /// The most recently expanded articles along with their ancestors, as
+    /// many as fit in `area`
+    fn tree_lines(&self, area: Rect) -> Vec<Line<'_>> {
+        let height = area.height.saturating_sub(2) as usize;
+
+        let mut shown = BTreeSet::new();
+        for idx in (0..self.articles.len()).rev() {
+            if shown.len() >= height {
+                break;
+            }
+            let mut ancestor = Some(idx);
+            while let Some(i) = ancestor {
+                if !shown.insert(i) {
+                    break;
+                }
+                ancestor = self.parents[i];
+            }
+        }
+
+        let mut children: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+        for &idx in &shown {
+            children.entry(self.parents[idx]).or_default().push(idx);
+        }
+
+        let mut lines = Vec::new();
+        // Depth-first, with the prefix drawn before each child
+        let mut stack: Vec<(usize, String, String)> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .rev()
+            .map(|&root| (root, String::new(), String::new()))
+            .collect();
+
+        while let Some((idx, branch, indent)) = stack.pop() {
+            lines.push(Line::from(format!(
+                "{}{}",
+                branch,
+                normalize_display(&self.articles[idx])
+            )));
+
+            let Some(kids) = children.get(&Some(idx)) else {
+                continue;
+            };
+            for (i, &child) in kids.iter().enumerate().rev() {
+                let last = i == kids.len() - 1;
+                let branch = format!("{}{}", indent, if last { "└─ " } else { "├─ " });
+                let indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+                stack.push((child, branch, indent));
+            }
+        }
+
+        // Keep the newest articles in view
+        let skip = lines.len().saturating_sub(height);
+        lines.split_off(skip)
+    }
+}
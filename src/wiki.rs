@@ -0,0 +1,816 @@
+//! Wikipedia editions and article titles
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    io::{self, Read},
+    iter,
+    sync::{LazyLock, OnceLock},
+};
+
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        states::RawKind, BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer,
+        TokenizerOpts,
+    },
+};
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use scraper as sc;
+use serde::Deserialize;
+
+/// An edition as `languages.toml` lists it
+#[derive(Debug, Deserialize)]
+pub struct Language {
+    /// Title of the main page, whose links are never followed
+    pub main_page: String,
+    /// Localized names of the namespaces by ID, on top of the canonical
+    /// ones. `None` if they're looked up instead.
+    pub namespaces: Option<HashMap<String, i32>>,
+}
+
+/// The supported Wikipedia editions by language code, from the
+/// `languages.toml` bundled at build time
+pub static LANGUAGES: LazyLock<BTreeMap<String, Language>> = LazyLock::new(|| {
+    toml::from_str(include_str!("languages.toml")).expect("invalid languages.toml")
+});
+
+/// The namespaces of MediaWiki by their canonical names, which every
+/// edition accepts along with its own, and their English aliases. Articles
+/// are in the main namespace, 0, which has no name.
+const NAMESPACES: &[(&str, i32)] = &[
+    ("Media", -2),
+    ("Special", -1),
+    ("Talk", 1),
+    ("User", 2),
+    ("User_talk", 3),
+    ("Project", 4),
+    ("Wikipedia", 4),
+    ("WP", 4),
+    ("Project_talk", 5),
+    ("Wikipedia_talk", 5),
+    ("WT", 5),
+    ("File", 6),
+    ("Image", 6),
+    ("File_talk", 7),
+    ("Image_talk", 7),
+    ("MediaWiki", 8),
+    ("MediaWiki_talk", 9),
+    ("Template", 10),
+    ("Template_talk", 11),
+    ("Help", 12),
+    ("Help_talk", 13),
+    ("Category", 14),
+    ("Category_talk", 15),
+    ("Portal", 100),
+    ("Portal_talk", 101),
+    ("Draft", 118),
+    ("Draft_talk", 119),
+    ("TimedText", 710),
+    ("TimedText_talk", 711),
+    ("Module", 828),
+    ("Module_talk", 829),
+];
+
+/// The namespace of the articles
+pub const MAIN_NAMESPACE: i32 = 0;
+
+/// Characters escaped in the article titles of URLs
+const TITLE_ESCAPE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'_')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/')
+    .remove(b':');
+
+/// Characters escaped in the article titles of REST API paths, where a
+/// slash would start another path segment
+const REST_TITLE_ESCAPE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'_')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b':');
+
+/// Path of the article pages before their title on Wikipedia
+const DEFAULT_ARTICLE_PATH: &str = "/wiki/";
+
+/// Path of the REST API serving the mobile HTML of an article, followed by
+/// its title
+pub(crate) const MOBILE_HTML_PATH: &str = "/api/rest_v1/page/mobile-html/";
+
+/// Parses one of the selectors below, which are fixed and known to be valid
+fn selector(css: &str) -> sc::Selector {
+    sc::Selector::parse(css).expect("invalid CSS selector")
+}
+
+/// Links along with the headings of the sections they're in
+static LINK_OR_HEADING_SELECTOR: LazyLock<sc::Selector> =
+    LazyLock::new(|| selector("a[href], h2, h3, h4, h5, h6"));
+/// Title of a heading in the older layout, next to its [edit] link
+static HEADLINE_SELECTOR: LazyLock<sc::Selector> = LazyLock::new(|| selector(".mw-headline"));
+pub(crate) static DISAMBIG_SELECTOR: LazyLock<sc::Selector> = LazyLock::new(|| {
+    selector(
+        r#"#disambigbox, #disambig, .dmbox-disambig, a[href="/wiki/Category:All_disambiguation_pages"], meta[property="mw:PageProp/disambiguation"]"#,
+    )
+});
+/// Wrapper of the article text, without the page around it
+static CONTENT_SELECTOR: LazyLock<sc::Selector> = LazyLock::new(|| selector(".mw-parser-output"));
+static CANONICAL_SELECTOR: LazyLock<sc::Selector> =
+    LazyLock::new(|| selector(r#"link[rel="canonical"]"#));
+/// Link of the message a soft redirect shows in place of its target, for
+/// JavaScript to follow
+static SOFT_REDIRECT_SELECTOR: LazyLock<sc::Selector> =
+    LazyLock::new(|| selector(".redirectMsg a[href]"));
+static LINK_SELECTOR: LazyLock<sc::Selector> = LazyLock::new(|| selector("a[href]"));
+static SHORT_DESCRIPTION_SELECTOR: LazyLock<sc::Selector> =
+    LazyLock::new(|| selector(".shortdescription"));
+/// Box of the categories shown at the bottom of an article, without the
+/// hidden ones
+static CATEGORY_LINKS_SELECTOR: LazyLock<sc::Selector> =
+    LazyLock::new(|| selector("#mw-normal-catlinks"));
+static LIST_LINK_SELECTOR: LazyLock<sc::Selector> = LazyLock::new(|| selector("li a[href]"));
+
+/// Depth of the sections under an h6, the deepest heading
+pub(crate) const MAX_SECTION_DEPTH: u8 = 5;
+
+/// Characters short descriptions are cut to
+const SHORT_DESCRIPTION_CHARS: usize = 60;
+
+/// Bytes read at a time by `extract_links_streaming`
+const STREAMING_CHUNK_BYTES: usize = 16 * 1024;
+
+/// A Wikipedia edition
+pub struct Wiki {
+    lang: String,
+    host: String,
+    base_url: String,
+    /// Path of the article pages, before their title
+    article_path: String,
+    article_url: String,
+    mobile_url: String,
+    pub(crate) api_url: String,
+    language: Option<&'static Language>,
+    /// Namespaces of the titles links are followed to
+    follow: Vec<i32>,
+    /// Names of the namespaces of the edition, in lowercase, once looked up
+    namespaces: OnceLock<HashMap<String, i32>>,
+}
+
+impl Wiki {
+    pub fn new(lang: &str) -> Self {
+        Self::with_base_url(lang, &format!("https://{}.wikipedia.org", lang))
+    }
+
+    /// The wiki at `base_url`, like `http://localhost:8080`, serving
+    /// articles and the API under the same paths as Wikipedia
+    pub fn with_base_url(lang: &str, base_url: &str) -> Self {
+        let base = base_url.trim_end_matches('/');
+        let host = base.split_once("://").map_or(base, |(_, host)| host);
+        let host = host.split_once('/').map_or(host, |(host, _)| host);
+
+        Self {
+            lang: lang.to_string(),
+            host: host.to_string(),
+            base_url: base.to_string(),
+            article_path: DEFAULT_ARTICLE_PATH.to_string(),
+            article_url: format!("{}{}", base, DEFAULT_ARTICLE_PATH),
+            mobile_url: format!("{}{}", base, MOBILE_HTML_PATH),
+            api_url: format!("{}/w/api.php", base),
+            language: LANGUAGES.get(lang),
+            follow: vec![MAIN_NAMESPACE],
+            namespaces: OnceLock::new(),
+        }
+    }
+
+    /// Follows links to the titles of the `namespaces`, by ID, instead of
+    /// only to the articles of the main namespace
+    pub fn with_namespaces(mut self, namespaces: &[i32]) -> Self {
+        self.follow = namespaces.to_vec();
+        self.follow.sort_unstable();
+        self.follow.dedup();
+        self
+    }
+
+    /// Serves the article pages under `prefix`, like `/w/` or
+    /// `/index.php/`, instead of `/wiki/`
+    pub fn with_article_path(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        self.article_path = if prefix.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{}/", prefix)
+        };
+        self.article_url = format!("{}{}", self.base_url, self.article_path);
+        self
+    }
+
+    /// Path of the article pages before their title, `/wiki/` on Wikipedia
+    pub(crate) fn article_path(&self) -> &str {
+        &self.article_path
+    }
+
+    /// Language code of the edition
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    /// Domain name of the edition, like en.wikipedia.org
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// URL of the page of `article`, on its own edition if `in_edition`
+    /// titled it
+    pub fn url(&self, article: &str) -> String {
+        match split_edition(article) {
+            Some((lang, title)) if lang != self.lang => format!(
+                "https://{}.wikipedia.org{}{}",
+                lang,
+                DEFAULT_ARTICLE_PATH,
+                encode_title(title)
+            ),
+            _ => format!("{}{}", self.article_url, encode_title(article)),
+        }
+    }
+
+    /// URL of the mobile HTML of `article`, from the REST API
+    pub(crate) fn mobile_url(&self, article: &str) -> String {
+        format!(
+            "{}{}",
+            self.mobile_url,
+            percent_encoding::utf8_percent_encode(article, REST_TITLE_ESCAPE)
+        )
+    }
+
+    pub(crate) fn is_main_page(&self, article: &str) -> bool {
+        self.language
+            .is_some_and(|language| language.main_page == article)
+    }
+
+    /// IDs of the namespaces links are followed to
+    pub(crate) fn followed_namespaces(&self) -> &[i32] {
+        &self.follow
+    }
+
+    /// The followed namespaces as the API takes them, like `0|14`
+    pub(crate) fn namespace_param(&self) -> String {
+        let ids: Vec<_> = self.follow.iter().map(i32::to_string).collect();
+        ids.join("|")
+    }
+
+    /// Whether the names of the edition's namespaces are known, looked up
+    /// or listed in `languages.toml`
+    pub(crate) fn knows_namespaces(&self) -> bool {
+        self.listed_namespaces().is_some() || self.namespaces.get().is_some()
+    }
+
+    fn listed_namespaces(&self) -> Option<&'static HashMap<String, i32>> {
+        self.language?.namespaces.as_ref()
+    }
+
+    /// Sets the names of the edition's namespaces, as its API lists them
+    pub(crate) fn set_namespaces(&self, names: impl IntoIterator<Item = (String, i32)>) {
+        let names = names
+            .into_iter()
+            .map(|(name, id)| (namespace_key(&name), id))
+            .collect();
+        let _ = self.namespaces.set(names);
+    }
+
+    /// Namespace of `title`, from the name before its first colon. Titles
+    /// like `Doctor_Who:_The_Movie`, whose prefix names no namespace, are
+    /// articles. Unknown prefixes can't be told apart from the localized
+    /// names of an edition that weren't looked up, so those are `None`.
+    pub(crate) fn namespace(&self, title: &str) -> Option<i32> {
+        let Some((prefix, _)) = title.split_once(':') else {
+            return Some(MAIN_NAMESPACE);
+        };
+        let key = namespace_key(prefix);
+        let known = self.namespaces.get().and_then(|names| names.get(&key));
+        let listed = self.listed_namespaces().and_then(|names| {
+            names
+                .iter()
+                .find(|(name, _)| namespace_key(name) == key)
+                .map(|(_, id)| id)
+        });
+        let canonical = NAMESPACES
+            .iter()
+            .find(|(name, _)| name.to_lowercase() == key)
+            .map(|(_, id)| id);
+        match known.or(listed).or(canonical) {
+            Some(&id) => Some(id),
+            None if self.knows_namespaces() => Some(MAIN_NAMESPACE),
+            None => None,
+        }
+    }
+
+    /// Whether links to `title` are followed, its namespace being one of
+    /// `with_namespaces`
+    pub(crate) fn follows(&self, title: &str) -> bool {
+        self.namespace(title)
+            .is_some_and(|namespace| self.follow.contains(&namespace))
+    }
+}
+
+/// Parses a namespace from its ID, like `14`, or its canonical name, like
+/// `Category` or `Category:`. The main namespace is `0`, `Main` or
+/// `Article`.
+pub fn parse_namespace(namespace: &str) -> Option<i32> {
+    let namespace = namespace.trim().trim_end_matches(':');
+    if let Ok(id) = namespace.parse() {
+        return Some(id);
+    }
+    let key = namespace_key(namespace);
+    if key == "main" || key == "article" {
+        return Some(MAIN_NAMESPACE);
+    }
+    NAMESPACES
+        .iter()
+        .find(|(name, _)| name.to_lowercase() == key)
+        .map(|(_, id)| *id)
+}
+
+/// Namespace names match whatever their case and with spaces or
+/// underscores
+fn namespace_key(name: &str) -> String {
+    name.trim().replace(' ', "_").to_lowercase()
+}
+
+pub(crate) fn encode_title(title: &str) -> String {
+    percent_encoding::utf8_percent_encode(title, TITLE_ESCAPE).to_string()
+}
+
+/// Title of `title` in the edition of `lang` other than the one searched,
+/// as searches tell those articles apart: `[de]Philosophie`. Brackets can't
+/// be in titles, so these don't clash with any.
+pub(crate) fn in_edition(lang: &str, title: &str) -> String {
+    format!("[{}]{}", lang, title)
+}
+
+/// The language code and title of an article `in_edition` wrote
+pub(crate) fn split_edition(title: &str) -> Option<(&str, &str)> {
+    title.strip_prefix('[')?.split_once(']')
+}
+
+/// Writes a title the way Wikipedia does in URLs, so that differently typed
+/// titles of the same article compare equal: underscores instead of spaces
+/// and an uppercase first letter
+pub fn normalize_title(title: &str) -> String {
+    let title = title.trim().replace(' ', "_");
+    let mut chars = title.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => title,
+    }
+}
+
+/// Writes a title the way Wikipedia displays it: spaces instead of
+/// underscores and an uppercase first letter. Only for showing titles, which
+/// are stored and compared as `normalize_title` writes them.
+pub fn normalize_display(title: &str) -> String {
+    normalize_title(title).replace('_', " ")
+}
+
+/// Name of the category `title`, without the namespace each edition writes
+/// differently: `Category:Ancient philosophy` is `Ancient_philosophy`
+pub(crate) fn category_name(title: &str) -> String {
+    let name = title.split_once(':').map_or(title, |(_, name)| name);
+    normalize_title(name)
+}
+
+pub fn decode_title(title: &str) -> String {
+    percent_encoding::percent_decode_str(title)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Extracts the title from the canonical URL of an article's HTML
+pub(crate) fn canonical_title(document: &sc::Html, wiki: &Wiki) -> Option<String> {
+    let href = document
+        .select(&CANONICAL_SELECTOR)
+        .next()?
+        .value()
+        .attr("href")?;
+    let (_, title) = href.split_once(wiki.article_path())?;
+
+    Some(normalize_title(&decode_title(title)))
+}
+
+/// The article an article's HTML is a soft redirect to, if it is one
+pub(crate) fn soft_redirect_target(document: &sc::Html, wiki: &Wiki) -> Option<String> {
+    let href = document
+        .select(&SOFT_REDIRECT_SELECTOR)
+        .next()?
+        .value()
+        .attr("href")?;
+    let title = href.strip_prefix(wiki.article_path())?;
+    let title = title.split(['#', '?']).next().unwrap_or(title);
+
+    // Soft redirects to other namespaces lead out of the articles
+    Some(normalize_title(&decode_title(title)))
+        .filter(|title| !title.is_empty() && wiki.follows(title))
+}
+
+/// Links from an article's HTML to the articles the Wikipedia `editions`
+/// follow, like the language links of its sidebar, as the code of the
+/// edition, the title and the text of each
+pub(crate) fn interwiki_links(
+    document: &sc::Html,
+    editions: &[&Wiki],
+) -> Vec<(String, String, String)> {
+    let mut links = Vec::new();
+    for element in document.select(&LINK_SELECTOR) {
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let href = href.strip_prefix("https:").unwrap_or(href);
+        for edition in editions {
+            let Some(title) = href
+                .strip_prefix("//")
+                .and_then(|href| href.strip_prefix(edition.lang()))
+                .and_then(|href| href.strip_prefix(".wikipedia.org/wiki/"))
+            else {
+                continue;
+            };
+            let title = title.split(['#', '?']).next().unwrap_or(title);
+            let title = normalize_title(&decode_title(title));
+            if !title.is_empty() && edition.follows(&title) {
+                let anchor = element.text().collect::<String>().trim().to_string();
+                links.push((edition.lang().to_string(), title, anchor));
+            }
+        }
+    }
+    links
+}
+
+/// The short description at the top of an article's HTML, like
+/// "German-born theoretical physicist", cut to `SHORT_DESCRIPTION_CHARS`
+pub(crate) fn short_description(document: &sc::Html) -> Option<String> {
+    let text: String = document
+        .select(&SHORT_DESCRIPTION_SELECTOR)
+        .next()?
+        .text()
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    match text.char_indices().nth(SHORT_DESCRIPTION_CHARS) {
+        Some((end, _)) => Some(format!("{}…", text[..end].trim_end())),
+        None => Some(text.to_string()),
+    }
+}
+
+/// Categories listed at the bottom of an article's HTML, by the names
+/// `category_name` gives them, or `None` if the page shows none, as the
+/// mobile HTML doesn't
+pub(crate) fn document_categories(document: &sc::Html, wiki: &Wiki) -> Option<Vec<String>> {
+    let categories = document.select(&CATEGORY_LINKS_SELECTOR).next()?;
+    Some(
+        categories
+            .select(&LIST_LINK_SELECTOR)
+            .filter_map(|element| element.value().attr("href"))
+            .filter_map(|href| href.strip_prefix(wiki.article_path()))
+            .map(|title| category_name(&decode_title(title)))
+            .collect(),
+    )
+}
+
+/// Title of the section of editorial recommendations, whose links
+/// `document_links` tells apart
+const SEE_ALSO: &str = "See also";
+
+/// Extracts the names of the articles linked from the HTML of an English
+/// Wikipedia article, as a search follows them with the default settings.
+/// Invalid UTF-8 is replaced rather than rejected.
+pub fn extract_links(html: &[u8]) -> Vec<String> {
+    let html = String::from_utf8_lossy(html);
+    let document = sc::Html::parse_document(&html);
+    document_links(&document, &html, &Wiki::new("en"), &[], false, false)
+        .into_iter()
+        .map(|link| link.title)
+        .collect()
+}
+
+/// Like `extract_links`, but reads the HTML from `reader` a chunk at a
+/// time and goes through its tags as they come, without building a
+/// document, so only the links found are kept in memory
+pub fn extract_links_streaming(mut reader: impl Read) -> io::Result<Vec<String>> {
+    let tokenizer = Tokenizer::new(
+        LinkSink {
+            wiki: Wiki::new("en"),
+            links: RefCell::new(Vec::new()),
+        },
+        TokenizerOpts::default(),
+    );
+    let input = BufferQueue::default();
+    let mut buf = vec![0; STREAMING_CHUNK_BYTES];
+    // Bytes of a character cut by the end of the last chunk
+    let mut partial = 0;
+
+    loop {
+        let read = match reader.read(&mut buf[partial..]) {
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let end = partial + read;
+        let mut rest = &buf[..end];
+
+        // Invalid UTF-8 is replaced as `extract_links` replaces it
+        let mut text = String::new();
+        while !rest.is_empty() {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).unwrap());
+                    match err.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        // Completed by the next chunk, unless there's none
+                        None if read > 0 => break,
+                        None => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &[];
+                        }
+                    }
+                }
+            }
+        }
+        partial = rest.len();
+        buf.copy_within(end - partial..end, 0);
+
+        if !text.is_empty() {
+            input.push_back(StrTendril::from(text));
+            let _ = tokenizer.feed(&input);
+        }
+        if read == 0 {
+            break;
+        }
+    }
+    tokenizer.end();
+
+    Ok(tokenizer.sink.links.into_inner())
+}
+
+/// Collects the articles linked from the tags the tokenizer goes through
+struct LinkSink {
+    wiki: Wiki,
+    links: RefCell<Vec<String>>,
+}
+
+impl TokenSink for LinkSink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line: u64) -> TokenSinkResult<()> {
+        let Token::TagToken(tag) = token else {
+            return TokenSinkResult::Continue;
+        };
+        if tag.kind != TagKind::StartTag {
+            return TokenSinkResult::Continue;
+        }
+
+        // What the tree builder would tell the tokenizer, so that markup
+        // in scripts and styles isn't taken for tags
+        match &*tag.name {
+            "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                return TokenSinkResult::RawData(RawKind::Rawtext)
+            }
+            "title" | "textarea" => return TokenSinkResult::RawData(RawKind::Rcdata),
+            "plaintext" => return TokenSinkResult::Plaintext,
+            "a" => {}
+            _ => return TokenSinkResult::Continue,
+        }
+
+        let href = tag.attrs.iter().find(|attr| &*attr.name.local == "href");
+        if let Some(name) = href
+            .and_then(|href| article_href(&href.value, &self.wiki))
+            .and_then(|path| linked_article(path, &self.wiki))
+        {
+            self.links.borrow_mut().push(name);
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// A link to an article, as `document_links` finds it
+pub(crate) struct DocumentLink {
+    pub(crate) title: String,
+    /// Byte offset of the link in the HTML
+    pub(crate) offset: usize,
+    /// Text of the link
+    pub(crate) anchor: String,
+    /// Whether the link is in the See also section
+    pub(crate) see_also: bool,
+    /// Depth of the section the link is in, see `section_depth`
+    pub(crate) section_depth: u8,
+}
+
+/// Extracts the links to articles in an article's HTML. Links in the
+/// sections titled one of `skip_sections` and in their subsections are
+/// left out. With `infobox_first`, the links of the infobox come before
+/// the others and are given offset 0. With `intro_only`, only the links
+/// before the first section heading are.
+pub(crate) fn document_links(
+    document: &sc::Html,
+    html: &str,
+    wiki: &Wiki,
+    skip_sections: &[String],
+    infobox_first: bool,
+    intro_only: bool,
+) -> Vec<DocumentLink> {
+    let mut links = Vec::new();
+    let mut infobox_links = Vec::new();
+    // Elements come in document order, so each href is looked for after the
+    // previous one
+    let mut offset = 0;
+    let mut searched = 0;
+    // Level of the heading of the skipped section the links are in
+    let mut skipping: Option<u8> = None;
+    // Level of the heading of the See also section, if the links are in it
+    let mut see_also: Option<u8> = None;
+    // The table of contents has a heading before the article text
+    let has_content = document.select(&CONTENT_SELECTOR).next().is_some();
+
+    for element in document.select(&LINK_OR_HEADING_SELECTOR) {
+        if let Some(level) = heading_level(element.value().name()) {
+            if intro_only && level == 2 && (!has_content || in_content(element)) {
+                break;
+            }
+            // A heading of the same level or above ends the section
+            if skipping.is_some_and(|skipped| level <= skipped) {
+                skipping = None;
+            }
+            if see_also.is_some_and(|section| level <= section) {
+                see_also = None;
+            }
+            let title: String = element
+                .select(&HEADLINE_SELECTOR)
+                .next()
+                .unwrap_or(element)
+                .text()
+                .collect();
+            let title = title.trim();
+            if skipping.is_none()
+                && skip_sections
+                    .iter()
+                    .any(|section| section.eq_ignore_ascii_case(title))
+            {
+                skipping = Some(level);
+            }
+            if title.eq_ignore_ascii_case(SEE_ALSO) {
+                see_also = Some(level);
+            }
+            continue;
+        }
+
+        if let Some(href) = element.value().attr("href") {
+            if let Some(path) = article_href(href, wiki) {
+                // Escaped differently in the source, it stays at the last
+                // offset found
+                if let Some(pos) = html[searched..].find(href) {
+                    offset = searched + pos;
+                    searched = offset + href.len();
+                }
+
+                if skipping.is_some() {
+                    continue;
+                }
+                let Some(name) = linked_article(path, wiki) else {
+                    continue;
+                };
+
+                let infobox = infobox_first && in_infobox(element);
+                let link = DocumentLink {
+                    title: name,
+                    offset: if infobox { 0 } else { offset },
+                    anchor: element.text().collect::<String>().trim().to_string(),
+                    see_also: see_also.is_some(),
+                    section_depth: section_depth(element),
+                };
+                if infobox {
+                    infobox_links.push(link);
+                } else {
+                    links.push(link);
+                }
+            }
+        }
+    }
+
+    infobox_links.extend(links);
+    infobox_links
+}
+
+/// What follows the article path in `href`, if it leads to a page of the
+/// wiki. The mobile HTML links relative to the article path.
+fn article_href<'a>(href: &'a str, wiki: &Wiki) -> Option<&'a str> {
+    href.strip_prefix(wiki.article_path())
+        .or_else(|| href.strip_prefix("./"))
+}
+
+/// The article linked to by `path`, as `article_href` gives it, unless
+/// it's missing, the main page or in another namespace
+fn linked_article(mut path: &str, wiki: &Wiki) -> Option<String> {
+    // Remove #fragments
+    if let Some(idx) = path.find('#') {
+        path = &path[..idx];
+    }
+    // Missing articles have no links in the HTML but are edited from the
+    // mobile HTML, which titles escape the ? of
+    if let Some((title, query)) = path.split_once('?') {
+        if query.contains("redlink=1") {
+            return None;
+        }
+        path = title;
+    }
+    let name = normalize_title(&decode_title(path));
+    // Exclude the main page or Special: / Talk: etc
+    if name.is_empty() || wiki.is_main_page(&name) || !wiki.follows(&name) {
+        return None;
+    }
+    Some(name)
+}
+
+/// Whether `element` is part of the article text
+fn in_content(element: sc::ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(sc::ElementRef::wrap)
+        .any(|ancestor| {
+            ancestor
+                .value()
+                .has_class("mw-parser-output", sc::CaseSensitivity::CaseSensitive)
+        })
+}
+
+/// Whether `element` is inside the infobox table of an article
+fn in_infobox(element: sc::ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(sc::ElementRef::wrap)
+        .any(|ancestor| {
+            ancestor.value().name() == "table"
+                && ancestor
+                    .value()
+                    .has_class("infobox", sc::CaseSensitivity::AsciiCaseInsensitive)
+        })
+}
+
+/// Depth of the section `element` is in, from the level of the closest
+/// heading among the earlier siblings of it and its ancestors in the
+/// article text: 0 in the lead, 1 under an h2, 2 under an h3 and so on up
+/// to `MAX_SECTION_DEPTH`. Headings of the newer layout are wrapped in a
+/// `div.mw-heading`, those of the mobile HTML start a `section` around
+/// the links.
+fn section_depth(element: sc::ElementRef) -> u8 {
+    let nodes = iter::once(element).chain(element.ancestors().filter_map(sc::ElementRef::wrap));
+    for node in nodes {
+        if node
+            .value()
+            .has_class("mw-parser-output", sc::CaseSensitivity::CaseSensitive)
+        {
+            break;
+        }
+        for sibling in node.prev_siblings().filter_map(sc::ElementRef::wrap) {
+            let heading = if sibling
+                .value()
+                .has_class("mw-heading", sc::CaseSensitivity::CaseSensitive)
+            {
+                sibling
+                    .children()
+                    .filter_map(sc::ElementRef::wrap)
+                    .find_map(|child| heading_level(child.value().name()))
+            } else {
+                heading_level(sibling.value().name())
+            };
+            if let Some(level) = heading {
+                return level - 1;
+            }
+        }
+    }
+    0
+}
+
+/// Level of the heading element `name`, if it's one
+fn heading_level(name: &str) -> Option<u8> {
+    match name {
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
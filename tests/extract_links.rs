@@ -0,0 +1,150 @@
+//! Link extraction from the fixtures and from HTML no article has, like
+//! the fuzz target feeds it
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
+
+use bzip2::{write::BzEncoder, Compression};
+use wiki_path::{
+    extract_links, extract_links_streaming, index_dump, BfsSearcher, SearchError, LANGUAGES,
+};
+use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+/// Reads a byte at a time, cutting every character and tag
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some((&byte, rest)) = self.0.split_first() else {
+            return Ok(0);
+        };
+        buf[0] = byte;
+        self.0 = rest;
+        Ok(1)
+    }
+}
+
+#[test]
+fn extracts_article_links() {
+    let html = include_bytes!("fixtures/Rust.html");
+    // The talk page is in another namespace
+    assert_eq!(extract_links(html), ["Iron", "Fungus", "Mercury"]);
+}
+
+#[test]
+fn handles_malformed_html() {
+    let long_href = format!(r#"<a href="/wiki/{}">x</a>"#, "A".repeat(1 << 16));
+    let nested = "<div>".repeat(1000) + r#"<a href="/wiki/Deep">x</a>"#;
+
+    assert_eq!(extract_links(long_href.as_bytes()).len(), 1);
+    assert_eq!(extract_links(nested.as_bytes()), ["Deep"]);
+    assert_eq!(
+        extract_links(b"<a href=\"/wiki/Caf\xe9\">\xff</a><h2><a href=\"/wiki/#x\">"),
+        ["Caf\u{fffd}"]
+    );
+}
+
+#[tokio::test]
+async fn tells_namespaces_from_colons_in_titles() {
+    let html = br#"<a href="/wiki/Doctor_Who:_The_Movie">x</a>
+        <a href="/wiki/Category:Metals">x</a><a href="/wiki/user_talk:Someone">x</a>"#;
+    assert_eq!(extract_links(html), ["Doctor_Who:_The_Movie"]);
+
+    // Book is only a namespace as the dump lists it
+    let xml = r#"<mediawiki><siteinfo><namespaces>
+        <namespace key="0" case="first-letter" />
+        <namespace key="108" case="first-letter">Book</namespace>
+        </namespaces></siteinfo>
+        <page><title>Tardis</title><ns>0</ns><revision><text>
+        [[Doctor Who: The Movie]] [[Category:Metals]] [[Book:Doctor Who]]
+        </text></revision></page></mediawiki>"#;
+    let dir = env::temp_dir();
+    let dump = dir.join(format!("wiki-path-test-dump-{}.xml.bz2", process::id()));
+    let index = dir.join(format!("wiki-path-test-dump-{}.db", process::id()));
+    let mut encoder = BzEncoder::new(fs::File::create(&dump).unwrap(), Compression::fast());
+    encoder.write_all(xml.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+    index_dump(&dump, &index, false).unwrap();
+    let conn = rusqlite::Connection::open(&index).unwrap();
+    let links: String = conn
+        .query_row(
+            "SELECT links FROM pages WHERE title = 'Tardis'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    drop(conn);
+    fs::remove_file(&dump).unwrap();
+    fs::remove_file(&index).unwrap();
+    assert_eq!(links, "Doctor_Who:_The_Movie");
+
+    // Language links, to an edition whose namespaces are known
+    let server = MockServer::start().await;
+    let html = r#"<html><body><div class="mw-parser-output"><p>Tardis</p></div>
+        <a href="https://de.wikipedia.org/wiki/Kategorie:Film">x</a>
+        <a href="https://de.wikipedia.org/wiki/Doctor_Who:_Der_Film">x</a>
+        </body></html>"#;
+    Mock::given(path("/wiki/Tardis"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+        .mount(&server)
+        .await;
+    // Titles are looked up before searching
+    Mock::given(path("/w/api.php"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "query": { "pages": [{ "title": "Tardis" }] } }),
+            ),
+        )
+        .mount(&server)
+        .await;
+    let search = |end: &str| {
+        BfsSearcher::builder(["Tardis"], [end])
+            .base_url(server.uri())
+            .request_wait(Duration::ZERO)
+            .interwiki(["de"])
+            .max_depth(1)
+            .build()
+            .unwrap()
+    };
+    assert!(matches!(
+        search("[de]Kategorie:Film").search().await,
+        Err(SearchError::DepthExceeded)
+    ));
+    let paths = search("[de]Doctor_Who:_Der_Film").search().await.unwrap();
+    assert_eq!(paths[0].articles, ["Tardis", "[de]Doctor_Who:_Der_Film"]);
+}
+
+#[test]
+fn lists_the_namespaces_of_editions() {
+    let de = &LANGUAGES["de"];
+    assert_eq!(de.main_page, "Wikipedia:Hauptseite");
+    assert_eq!(de.namespaces.as_ref().unwrap()["Diskussion"], 1);
+    // The canonical names are enough on the English Wikipedia
+    assert!(LANGUAGES["en"].namespaces.as_ref().unwrap().is_empty());
+    assert!(LANGUAGES
+        .values()
+        .all(|language| !language.main_page.is_empty()));
+}
+
+#[test]
+fn streams_the_same_links() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for entry in fs::read_dir(fixtures).unwrap() {
+        let html = fs::read(entry.unwrap().path()).unwrap();
+        assert_eq!(
+            extract_links_streaming(html.as_slice()).unwrap(),
+            extract_links(&html)
+        );
+    }
+
+    let html = b"<script>'<a href=\"/wiki/Hidden\">'</script><a href=\"/wiki/Caf\xc3\xa9_\xff\">";
+    assert_eq!(
+        extract_links_streaming(Trickle(html)).unwrap(),
+        extract_links(html)
+    );
+}
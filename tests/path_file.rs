@@ -0,0 +1,54 @@
+//! Paths saved to a file and read back
+
+use std::{env, fs, process, time::Duration};
+
+use wiki_path::{Path, PathFile, Traffic, PATH_FILE_VERSION};
+
+fn temp_file(name: &str) -> std::path::PathBuf {
+    env::temp_dir().join(format!("wiki-path-test-{}-{}.json", name, process::id()))
+}
+
+#[test]
+fn loads_saved_paths() {
+    let file = temp_file("saved");
+    let path = Path {
+        articles: vec!["Rust".to_string(), "Iron".to_string()],
+        elapsed: Duration::from_millis(1500),
+        traffic: Traffic {
+            requests: 3,
+            bytes: 2048,
+        },
+        frontier_sizes: vec![1, 3],
+        weight: None,
+    };
+    PathFile::new(path, "en").save(&file).unwrap();
+    let loaded = PathFile::load(&file).unwrap();
+    fs::remove_file(&file).unwrap();
+
+    assert_eq!(loaded.version, PATH_FILE_VERSION);
+    assert_eq!(loaded.lang, "en");
+    assert_eq!(loaded.path.articles, ["Rust", "Iron"]);
+    assert_eq!(loaded.path.elapsed, Duration::from_millis(1500));
+    assert_eq!(loaded.path.traffic.bytes, 2048);
+}
+
+#[test]
+fn refuses_files_from_newer_versions() {
+    let file = temp_file("newer");
+    let newer = serde_json::json!({
+        "version": PATH_FILE_VERSION + 1,
+        "lang": "en",
+        "saved": 0,
+        "path": {
+            "articles": ["Rust"],
+            "elapsed": { "secs": 0, "nanos": 0 },
+            "traffic": { "requests": 0, "bytes": 0 },
+            "frontier_sizes": [],
+        },
+    });
+    fs::write(&file, newer.to_string()).unwrap();
+    let res = PathFile::load(&file);
+    fs::remove_file(&file).unwrap();
+
+    assert!(res.is_err());
+}
@@ -0,0 +1,1187 @@
+//! Searches against a mock wiki serving the articles in tests/fixtures
+//!
+//! Rust links to Iron, Fungus and the disambiguation page Mercury. Iron
+//! reaches Gold through either Metal or Oxygen, and Metal and Mercury both
+//! link to Planet. Fungus links to Fungi, a redirect to Mushroom.
+//! Corrosion links to Ferrum, a soft redirect to Iron.
+
+use std::{
+    env, fs,
+    ops::ControlFlow,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use tokio::{
+    io,
+    net::{TcpListener, TcpStream},
+};
+use wiki_path::{
+    decode_title, extract_links, normalize_display, normalize_title, parse_namespace, Algorithm,
+    BfsSearcher, BfsSearcherBuilder, SearchError, SearchEvent, TitleStatus, WeightFn,
+    MAIN_NAMESPACE,
+};
+use wiremock::{
+    matchers::{any, path},
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+const MOBILE_HTML_PATH: &str = "/api/rest_v1/page/mobile-html/";
+
+/// Titles the mock wiki redirects, with their targets
+const REDIRECTS: &[(&str, &str)] = &[("Fungi", "Mushroom")];
+
+/// Answers like Wikipedia would with the fixtures, for article pages and
+/// the API lookups of titles
+struct Fixtures;
+
+impl Fixtures {
+    fn resolve(title: &str) -> String {
+        let title = normalize_title(title);
+        REDIRECTS
+            .iter()
+            .find(|(from, _)| *from == title)
+            .map_or(title, |(_, to)| to.to_string())
+    }
+
+    fn param(req: &Request, name: &str) -> Option<String> {
+        req.url
+            .query_pairs()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Answers a `links` query with the links of the title's fixture, one
+    /// per batch so that every further one needs a continuation
+    fn links(req: &Request) -> ResponseTemplate {
+        let title = Self::resolve(&Self::param(req, "titles").unwrap_or_default());
+        let from: usize = Self::param(req, "plcontinue").map_or(0, |idx| idx.parse().unwrap());
+        let linked = match fs::read(Self::file(&title)) {
+            Ok(html) => extract_links(&html),
+            Err(_) => return ResponseTemplate::new(404),
+        };
+
+        let links: Vec<_> = linked
+            .iter()
+            .skip(from)
+            .take(1)
+            .map(|title| json!({ "title": normalize_display(title) }))
+            .collect();
+        let mut body = json!({
+            "query": { "pages": [{ "title": normalize_display(&title), "links": links }] }
+        });
+        if from + 1 < linked.len() {
+            body["continue"] = json!({ "plcontinue": (from + 1).to_string(), "continue": "||" });
+        }
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+
+    /// Answers a `linkshere` query with the fixtures linking to the title,
+    /// one per batch so that every further one needs a continuation
+    fn backlinks(req: &Request) -> ResponseTemplate {
+        let title = Self::resolve(&Self::param(req, "titles").unwrap_or_default());
+        let from: usize = Self::param(req, "lhcontinue").map_or(0, |idx| idx.parse().unwrap());
+
+        let mut linking: Vec<_> = fs::read_dir(Self::file("").parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|file| extract_links(&fs::read(file).unwrap()).contains(&title))
+            .map(|file| file.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect();
+        linking.sort();
+
+        let links: Vec<_> = linking
+            .iter()
+            .skip(from)
+            .take(1)
+            .map(|title| json!({ "title": normalize_display(title) }))
+            .collect();
+        let mut body = json!({
+            "query": { "pages": [{ "title": normalize_display(&title), "linkshere": links }] }
+        });
+        if from + 1 < linking.len() {
+            body["continue"] = json!({ "lhcontinue": (from + 1).to_string(), "continue": "||" });
+        }
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+
+    fn file(title: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(format!("{}.html", title))
+    }
+}
+
+impl Respond for Fixtures {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        // The REST API redirects where the article pages don't, and links
+        // relative to the article path
+        if let Some(title) = req.url.path().strip_prefix(MOBILE_HTML_PATH) {
+            let title = decode_title(title);
+            let resolved = Self::resolve(&title);
+            if resolved != title {
+                return ResponseTemplate::new(302)
+                    .insert_header("Location", format!("{}{}", MOBILE_HTML_PATH, resolved));
+            }
+            return match fs::read_to_string(Self::file(&title)) {
+                Ok(html) => ResponseTemplate::new(200)
+                    .set_body_raw(html.replace(r#"href="/wiki/"#, r#"href="./"#), "text/html"),
+                Err(_) => ResponseTemplate::new(404),
+            };
+        }
+
+        if let Some(title) = req.url.path().strip_prefix("/wiki/") {
+            let title = Self::resolve(&decode_title(title));
+            // Fixtures never change
+            let etag = format!("\"{}\"", title);
+            if req
+                .headers
+                .get("If-None-Match")
+                .is_some_and(|tag| *tag == *etag)
+            {
+                return ResponseTemplate::new(304);
+            }
+            return match fs::read_to_string(Self::file(&title)) {
+                Ok(html) => ResponseTemplate::new(200)
+                    .insert_header("ETag", etag.as_str())
+                    .set_body_raw(html, "text/html"),
+                Err(_) => ResponseTemplate::new(404),
+            };
+        }
+
+        match Self::param(req, "prop").as_deref() {
+            Some("linkshere") => return Self::backlinks(req),
+            Some(prop) if prop.split('|').any(|prop| prop == "links") => return Self::links(req),
+            _ => {}
+        }
+
+        let titles = req
+            .url
+            .query_pairs()
+            .find(|(param, _)| param == "titles")
+            .map(|(_, titles)| titles.into_owned())
+            .unwrap_or_default();
+        let pages: Vec<_> = titles
+            .split('|')
+            .map(|title| {
+                let resolved = Self::resolve(title);
+                if Self::file(&resolved).exists() {
+                    json!({ "title": normalize_display(&resolved) })
+                } else {
+                    json!({ "title": normalize_display(title), "missing": true })
+                }
+            })
+            .collect();
+        let redirects: Vec<_> = titles
+            .split('|')
+            .filter_map(|title| {
+                let resolved = Self::resolve(title);
+                (resolved != normalize_title(title)).then(|| {
+                    json!({ "from": normalize_display(title), "to": normalize_display(&resolved) })
+                })
+            })
+            .collect();
+        ResponseTemplate::new(200)
+            .set_body_json(json!({ "query": { "pages": pages, "redirects": redirects } }))
+    }
+}
+
+async fn mock_wiki() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(Fixtures)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn searcher(server: &MockServer, start: &str, end: &str) -> BfsSearcherBuilder {
+    BfsSearcher::builder([start], [end])
+        .base_url(server.uri())
+        .request_wait(Duration::ZERO)
+}
+
+/// Runs the binary against the mock wiki with `args`, returning what it
+/// printed with --format json
+async fn run(server: &MockServer, args: &[&str]) -> serde_json::Value {
+    // Missing, so that no config file is read
+    let config_home = env::temp_dir().join(format!("wiki-path-test-config-{}", process::id()));
+    let mut command = process::Command::new(env!("CARGO_BIN_EXE_wiki-path"));
+    command
+        .args(["--base-url", &server.uri(), "--format", "json"])
+        .args(args)
+        .env("XDG_CONFIG_HOME", &config_home);
+    let output = tokio::task::spawn_blocking(move || command.output())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[tokio::test]
+async fn finds_a_path_of_one_hop() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Iron")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].articles, ["Rust", "Iron"]);
+}
+
+#[tokio::test]
+async fn finds_a_path_of_three_hops() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Gold")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].articles.len(), 4);
+    assert_eq!(paths[0].articles[..2], ["Rust", "Iron"]);
+    assert_eq!(paths[0].articles[3], "Gold");
+}
+
+#[tokio::test]
+async fn queues_each_article_once() {
+    let server = mock_wiki().await;
+    let discovered = Arc::new(Mutex::new(Vec::new()));
+    let events = discovered.clone();
+    // Planet is linked from Mercury and again from Metal, which is expanded
+    // to the end with all
+    let paths = searcher(&server, "Rust", "Gold")
+        .all(true)
+        .on_event(move |event| {
+            if let SearchEvent::Discovered { article, .. } = event {
+                events.lock().unwrap().push(article.to_string());
+            }
+            ControlFlow::Continue(())
+        })
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    let discovered = discovered.lock().unwrap();
+    let mut unique = discovered.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), discovered.len());
+    assert!(discovered.contains(&"Planet".to_string()));
+    assert_eq!(paths.len(), 2);
+}
+
+#[tokio::test]
+async fn reuses_one_connection_for_every_request() {
+    let server = mock_wiki().await;
+    // Counts the connections opened through it to the mock wiki
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let opened = connections.clone();
+    let wiki = *server.address();
+    tokio::spawn(async move {
+        loop {
+            let (mut client, _) = proxy.accept().await.unwrap();
+            opened.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(async move {
+                let mut upstream = TcpStream::connect(wiki).await.unwrap();
+                let _ = io::copy_bidirectional(&mut client, &mut upstream).await;
+            });
+        }
+    });
+
+    BfsSearcher::builder(["Rust"], ["Gold"])
+        .base_url(proxy_url)
+        .request_wait(Duration::ZERO)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert!(server.received_requests().await.unwrap().len() > 1);
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn prints_paths_as_json() {
+    let server = mock_wiki().await;
+    let path = run(&server, &["Rust", "Iron"]).await;
+
+    assert_eq!(path["path"], json!(["Rust", "Iron"]));
+    assert_eq!(path["length"], 2);
+    assert!(path["elapsed_ms"].is_u64());
+}
+
+#[tokio::test]
+async fn finds_no_path_within_max_depth() {
+    let server = mock_wiki().await;
+    let result = searcher(&server, "Rust", "Gold")
+        .max_depth(1)
+        .build()
+        .unwrap()
+        .search()
+        .await;
+
+    assert!(matches!(result, Err(SearchError::DepthExceeded)));
+}
+
+#[tokio::test]
+async fn finds_all_shortest_paths() {
+    let server = mock_wiki().await;
+    let mut paths: Vec<_> = searcher(&server, "Rust", "Gold")
+        .all(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|path| path.articles)
+        .collect();
+    paths.sort();
+
+    assert_eq!(
+        paths,
+        [
+            ["Rust", "Iron", "Metal", "Gold"],
+            ["Rust", "Iron", "Oxygen", "Gold"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_each_shortest_path_as_it_is_found() {
+    let server = mock_wiki().await;
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let paths = reported.clone();
+    // Metal and Oxygen are both expanded at the depth Gold is first found
+    searcher(&server, "Rust", "Gold")
+        .all(true)
+        .on_path(move |path| paths.lock().unwrap().push(path.articles.clone()))
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    let mut reported = reported.lock().unwrap().clone();
+    reported.sort();
+    assert_eq!(
+        reported,
+        [
+            ["Rust", "Iron", "Metal", "Gold"],
+            ["Rust", "Iron", "Oxygen", "Gold"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn finds_the_same_paths_with_articles_on_disk() {
+    let server = mock_wiki().await;
+    // Ferrum is renamed to Iron once on disk
+    for (start, end, all) in [
+        ("Rust", "Gold", true),
+        ("Rust", "Planet", false),
+        ("Corrosion", "Oxygen", false),
+    ] {
+        let mut paths = Vec::new();
+        for max_memory in [None, Some(0)] {
+            let builder = searcher(&server, start, end).all(all);
+            let builder = match max_memory {
+                Some(bytes) => builder.max_memory(bytes),
+                None => builder,
+            };
+            let mut found: Vec<_> = builder
+                .build()
+                .unwrap()
+                .search()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|path| path.articles)
+                .collect();
+            found.sort();
+            paths.push(found);
+        }
+        assert_eq!(paths[0], paths[1]);
+    }
+}
+
+/// A wiki where each article down to `depth` links to two others, titled
+/// after the way to them from the root and padded to be `pad` longer
+struct Tree {
+    pad: usize,
+    depth: usize,
+}
+
+impl Tree {
+    fn title(&self, way: &str) -> String {
+        format!("Node{}{}", "o".repeat(self.pad), way)
+    }
+}
+
+impl Respond for Tree {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        let Some(title) = req.url.path().strip_prefix("/wiki/") else {
+            // Every title looked up exists
+            let titles = Fixtures::param(req, "titles").unwrap_or_default();
+            let pages: Vec<_> = titles
+                .split('|')
+                .map(|title| json!({ "title": title }))
+                .collect();
+            return ResponseTemplate::new(200)
+                .set_body_json(json!({ "query": { "pages": pages } }));
+        };
+
+        let way = title.trim_start_matches("Node").trim_start_matches('o');
+        let links: String = if way.len() < self.depth {
+            ["0", "1"]
+                .iter()
+                .map(|next| {
+                    let title = self.title(&format!("{}{}", way, next));
+                    format!(r#"<a href="/wiki/{0}" title="{0}">{0}</a> "#, title)
+                })
+                .collect()
+        } else {
+            String::new()
+        };
+        let html = format!(
+            r#"<html><body><div id="mw-content-text"><div class="mw-parser-output"><p>{}</p></div></div></body></html>"#,
+            links
+        );
+        ResponseTemplate::new(200).set_body_raw(html, "text/html")
+    }
+}
+
+#[tokio::test]
+async fn keeps_titles_out_of_memory_once_on_disk() {
+    let mut peaks = Vec::new();
+    for pad in [0, 1000] {
+        let tree = Tree { pad, depth: 7 };
+        let (start, end) = (tree.title(""), tree.title("1111111"));
+        let server = MockServer::start().await;
+        Mock::given(any()).respond_with(tree).mount(&server).await;
+
+        let mut searcher = searcher(&server, &start, &end)
+            .max_memory(0)
+            .build()
+            .unwrap();
+        searcher.search().await.unwrap();
+        peaks.push(searcher.peak_memory());
+    }
+    // Only the last few of the 255 articles found are in memory at once
+    assert!(peaks[1] - peaks[0] < 10 * 1000, "{:?}", peaks);
+}
+
+#[tokio::test]
+async fn follows_redirects() {
+    let server = mock_wiki().await;
+    // The end article is looked up by the title it redirects to
+    let paths = searcher(&server, "Rust", "Fungi")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Fungus", "Mushroom"]);
+}
+
+#[tokio::test]
+async fn finds_the_end_however_its_title_is_typed() {
+    let server = mock_wiki().await;
+    for end in ["mercury_(element)", "Mercury (element)"] {
+        let path = run(&server, &["rust", end]).await;
+        assert_eq!(
+            path["path"],
+            json!(["Rust", "Mercury", "Mercury_(element)"])
+        );
+    }
+}
+
+#[tokio::test]
+async fn validates_titles_in_one_request() {
+    let server = mock_wiki().await;
+    let titles = ["Rust", "Fungi", "Tin"].map(String::from);
+    let statuses = searcher(&server, "Rust", "Gold")
+        .build()
+        .unwrap()
+        .validate_titles(&titles)
+        .await
+        .unwrap();
+
+    assert_eq!(statuses["Rust"], TitleStatus::Valid);
+    assert_eq!(
+        statuses["Fungi"],
+        TitleStatus::Redirect("Mushroom".to_string())
+    );
+    assert_eq!(statuses["Tin"], TitleStatus::Missing);
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn follows_soft_redirects() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Corrosion", "Oxygen")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Corrosion", "Iron", "Oxygen"]);
+}
+
+#[tokio::test]
+async fn fetches_backlinks_across_batches() {
+    let server = mock_wiki().await;
+    let searcher = searcher(&server, "Rust", "Gold").build().unwrap();
+
+    assert_eq!(
+        searcher.backlinks("Gold").await.unwrap(),
+        ["Metal", "Oxygen"]
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn fetches_links_across_batches_with_the_api() {
+    let server = mock_wiki().await;
+    // Mercury comes in the last of the three batches of Rust's links
+    let paths = searcher(&server, "Rust", "Mercury")
+        .api(true)
+        .max_depth(1)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Mercury"]);
+    let batches = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|req| Fixtures::param(req, "plcontinue").is_some())
+        .count();
+    assert_eq!(batches, 2);
+}
+
+#[tokio::test]
+async fn finds_valid_paths_bidirectionally() {
+    let server = mock_wiki().await;
+    let mut searcher = searcher(&server, "Rust", "Gold")
+        .bidirectional(true)
+        .build()
+        .unwrap();
+    let paths = searcher.search().await.unwrap();
+
+    // Through Metal or Oxygen, whichever the trees meet at
+    assert_eq!(paths[0].articles.len(), 4);
+    assert_eq!(paths[0].articles[1], "Iron");
+    assert_eq!(
+        searcher.verify_path(&paths[0].articles).await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn records_what_expanded_articles_are() {
+    let server = mock_wiki().await;
+    let db = env::temp_dir().join(format!("wiki-path-test-articles-{}.db", process::id()));
+    let mut searcher = searcher(&server, "Rust", "Metal").db(&db).build().unwrap();
+    searcher.search().await.unwrap();
+
+    let iron = &searcher.articles()["Iron"];
+    assert!(iron.byte_length.is_some());
+    assert_eq!(
+        iron.categories.as_deref().unwrap(),
+        ["Chemical_elements", "Transition_metals"]
+    );
+    // The fixture shows no categories
+    assert_eq!(searcher.articles()["Rust"].categories, None);
+
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let (length, categories): (Option<usize>, Option<String>) = conn
+        .query_row(
+            "SELECT byte_length, categories FROM articles WHERE title = 'Iron'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    drop(conn);
+    fs::remove_file(&db).unwrap();
+
+    assert_eq!(length, iron.byte_length);
+    assert_eq!(
+        categories.as_deref(),
+        Some("Chemical_elements\nTransition_metals")
+    );
+}
+
+#[tokio::test]
+async fn reports_what_each_article_was_reached_from() {
+    let server = mock_wiki().await;
+    let expanded = Arc::new(Mutex::new(Vec::new()));
+    let events = expanded.clone();
+    searcher(&server, "Rust", "Gold")
+        .on_event(move |event| {
+            if let SearchEvent::Expanded {
+                article,
+                parent,
+                fetch_time,
+                ..
+            } = event
+            {
+                assert!(fetch_time.is_some());
+                let parent = parent.map(str::to_string);
+                events.lock().unwrap().push((article.to_string(), parent));
+            }
+            ControlFlow::Continue(())
+        })
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    let expanded = expanded.lock().unwrap();
+    assert_eq!(expanded[0], ("Rust".to_string(), None));
+    assert!(expanded.contains(&("Metal".to_string(), Some("Iron".to_string()))));
+}
+
+#[tokio::test]
+async fn skips_paths_shorter_than_the_minimum() {
+    let server = mock_wiki().await;
+    // Rust reaches Planet in two links through Mercury, and in three
+    // through Iron and Metal
+    let paths = searcher(&server, "Rust", "Planet")
+        .min_length(3)
+        .all(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Metal", "Planet"]);
+}
+
+#[tokio::test]
+async fn expands_at_most_the_limit_of_each_depth() {
+    let server = mock_wiki().await;
+    let expanded = Arc::new(Mutex::new(Vec::new()));
+    let events = expanded.clone();
+    // Mercury links to Planet, but only Iron is expanded after Rust
+    let paths = searcher(&server, "Rust", "Planet")
+        .level_limit(1)
+        .on_event(move |event| {
+            if let SearchEvent::Expanded { article, .. } = event {
+                events.lock().unwrap().push(article.to_string());
+            }
+            ControlFlow::Continue(())
+        })
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Metal", "Planet"]);
+    assert_eq!(*expanded.lock().unwrap(), ["Rust", "Iron", "Metal"]);
+}
+
+#[tokio::test]
+async fn finds_the_lightest_path_with_dijkstra() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Gold")
+        .algorithm(Algorithm::Dijkstra)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles.len(), 4);
+    assert_eq!(paths[0].weight, Some(3.0));
+
+    // Metal has two links and Oxygen one, so those of Metal weigh less
+    let paths = searcher(&server, "Rust", "Gold")
+        .algorithm(Algorithm::Dijkstra)
+        .weight_fns([WeightFn::Uniform, WeightFn::InverseDegree])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Metal", "Gold"]);
+    let weight = paths[0].weight.unwrap();
+    assert!((weight - (1.0 / 3.0 + 1.0 / 2.0 + 1.0 / 2.0)).abs() < 1e-5);
+}
+
+#[tokio::test]
+async fn finds_the_shortest_path_of_earliest_links() {
+    let server = mock_wiki().await;
+    // Mercury comes after Iron in Rust, but is one link from Planet
+    let paths = searcher(&server, "Rust", "Planet")
+        .weighted(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Mercury", "Planet"]);
+    assert_eq!(paths[0].weight, None);
+
+    // Metal comes before Oxygen in Iron
+    let paths = searcher(&server, "Rust", "Gold")
+        .weighted(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Metal", "Gold"]);
+}
+
+#[tokio::test]
+async fn weighs_links_by_the_depth_of_their_section() {
+    let server = mock_wiki().await;
+    // Metal is in a subsection of Iron and Oxygen in a section
+    let paths = searcher(&server, "Rust", "Gold")
+        .algorithm(Algorithm::Dijkstra)
+        .weight_fns([WeightFn::SectionDepth])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen", "Gold"]);
+    assert_eq!(paths[0].weight, Some(1.0 + 2.0 + 1.0));
+}
+
+#[tokio::test]
+async fn skips_missing_articles_looked_up_first() {
+    let server = mock_wiki().await;
+    let paths = BfsSearcher::builder(["Rust", "Tin"], ["Metal"])
+        .base_url(server.uri())
+        .request_wait(Duration::ZERO)
+        .prevalidate(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Metal"]);
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|req| req.url.path() != "/wiki/Tin"));
+}
+
+#[tokio::test]
+async fn follows_links_to_the_namespaces_given() {
+    let server = mock_wiki().await;
+    let result = searcher(&server, "Rust", "Talk:Rust")
+        .max_depth(1)
+        .build()
+        .unwrap()
+        .search()
+        .await;
+    assert!(matches!(result, Err(SearchError::DepthExceeded)));
+
+    let paths = searcher(&server, "Rust", "Talk:Rust")
+        .max_depth(1)
+        .namespaces([MAIN_NAMESPACE, parse_namespace("Talk").unwrap()])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Talk:Rust"]);
+}
+
+#[tokio::test]
+async fn skips_disambiguation_pages() {
+    let server = mock_wiki().await;
+    let through_disambig = searcher(&server, "Rust", "Planet")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    let skipping_disambig = searcher(&server, "Rust", "Planet")
+        .no_disambig(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(through_disambig[0].articles, ["Rust", "Mercury", "Planet"]);
+    assert_eq!(
+        skipping_disambig[0].articles,
+        ["Rust", "Iron", "Metal", "Planet"]
+    );
+}
+
+#[tokio::test]
+async fn skips_articles_matching_a_pattern() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Gold")
+        .skip_patterns(["^Met"])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen", "Gold"]);
+    assert!(matches!(
+        searcher(&server, "Rust", "Gold")
+            .skip_patterns(["("])
+            .build(),
+        Err(SearchError::InvalidPattern(_))
+    ));
+}
+
+#[tokio::test]
+async fn never_enters_forbidden_articles() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Gold")
+        .forbidden(["Metal"])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen", "Gold"]);
+
+    // Every path to Gold goes through Iron
+    let result = searcher(&server, "Rust", "Gold")
+        .forbidden(["Iron"])
+        .max_depth(3)
+        .build()
+        .unwrap()
+        .search()
+        .await;
+    assert!(matches!(result, Err(SearchError::DepthExceeded)));
+}
+
+#[tokio::test]
+async fn only_enters_articles_matching_a_pattern() {
+    let server = mock_wiki().await;
+    // The end article is entered all the same
+    let paths = searcher(&server, "Rust", "Gold")
+        .allow_patterns(["^I", "^O"])
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen", "Gold"]);
+}
+
+#[tokio::test]
+async fn waits_between_requests() {
+    let server = mock_wiki().await;
+    let wait = Duration::from_millis(100);
+    let start = Instant::now();
+    searcher(&server, "Rust", "Gold")
+        .request_wait(wait)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap().len();
+    assert!(requests > 1);
+    assert!(start.elapsed() >= wait * (requests - 1) as u32);
+}
+
+#[tokio::test]
+async fn retries_after_being_rate_limited() {
+    let server = MockServer::start().await;
+    Mock::given(path("/wiki/Rust"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(any())
+        .respond_with(Fixtures)
+        .mount(&server)
+        .await;
+
+    let start = Instant::now();
+    let paths = searcher(&server, "Rust", "Iron")
+        .max_retries(1)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(paths[0].articles, ["Rust", "Iron"]);
+}
+
+#[tokio::test]
+async fn caps_the_wait_asked_for_by_the_server() {
+    let server = MockServer::start().await;
+    Mock::given(path("/wiki/Rust"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "86400"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(any())
+        .respond_with(Fixtures)
+        .mount(&server)
+        .await;
+
+    let start = Instant::now();
+    let paths = searcher(&server, "Rust", "Iron")
+        .max_retries(1)
+        .max_retry_wait(Duration::from_millis(100))
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(paths[0].articles, ["Rust", "Iron"]);
+}
+
+#[tokio::test]
+async fn skips_articles_that_time_out() {
+    let server = MockServer::start().await;
+    Mock::given(path("/wiki/Metal"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(any())
+        .respond_with(Fixtures)
+        .mount(&server)
+        .await;
+
+    let errors = Arc::new(Mutex::new(0));
+    let events = errors.clone();
+    let start = Instant::now();
+    let paths = searcher(&server, "Rust", "Gold")
+        .timeout(Some(Duration::from_millis(200)))
+        .on_event(move |event| {
+            if let SearchEvent::Error(_) = event {
+                *events.lock().unwrap() += 1;
+            }
+            ControlFlow::Continue(())
+        })
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(*errors.lock().unwrap(), 1);
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen", "Gold"]);
+}
+
+#[tokio::test]
+async fn revalidates_cached_pages() {
+    let server = mock_wiki().await;
+    let dir = env::temp_dir().join(format!("wiki-path-test-cache-{}", process::id()));
+    // Every page is stale by the second search
+    let search = || {
+        searcher(&server, "Rust", "Gold")
+            .cache(&dir, Duration::ZERO)
+            .build()
+            .unwrap()
+    };
+    let first = search().search().await.unwrap();
+    let second = search().search().await.unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let revalidated = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|req| req.headers.contains_key("If-None-Match"))
+        .count();
+    assert!(revalidated > 0);
+    assert_eq!(first[0].articles, second[0].articles);
+}
+
+#[tokio::test]
+async fn searches_the_mobile_html() {
+    let server = mock_wiki().await;
+    let paths = searcher(&server, "Rust", "Fungi")
+        .mobile_api(true)
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Fungus", "Mushroom"]);
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|req| !req.url.path().starts_with("/wiki/")));
+}
+
+#[tokio::test]
+async fn iddfs_skips_articles_already_expanded() {
+    // Gold is reached from Iron through both Metal and Oxygen, and nothing
+    // leads back to Rust
+    let mut article_requests = Vec::new();
+    for transposition_table in [false, true] {
+        let server = mock_wiki().await;
+        let result = searcher(&server, "Iron", "Rust")
+            .algorithm(Algorithm::Iddfs)
+            .transposition_table(transposition_table)
+            .max_depth(2)
+            .build()
+            .unwrap()
+            .search()
+            .await;
+        assert!(matches!(result, Err(SearchError::DepthExceeded)));
+
+        let requests = server.received_requests().await.unwrap();
+        article_requests.push(
+            requests
+                .iter()
+                .filter(|req| req.url.path().starts_with("/wiki/"))
+                .count(),
+        );
+    }
+
+    assert!(article_requests[1] < article_requests[0]);
+}
+
+/// A wiki where Hub links to every letter in `order`, and each letter links
+/// to End
+struct Letters {
+    order: Vec<char>,
+}
+
+impl Respond for Letters {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        if let Some(article) = req.url.path().strip_prefix("/wiki/") {
+            let links: Vec<String> = match article {
+                "Hub" => self.order.iter().map(char::to_string).collect(),
+                "End" => Vec::new(),
+                _ => vec!["End".to_string()],
+            };
+            let links: String = links
+                .iter()
+                .map(|link| format!(r#"<a href="/wiki/{}">{}</a> "#, link, link))
+                .collect();
+            let html = format!(
+                r#"<html><body><div class="mw-parser-output"><p>{}</p></div></body></html>"#,
+                links
+            );
+            return ResponseTemplate::new(200).set_body_raw(html, "text/html");
+        }
+
+        let pages: Vec<_> = req
+            .url
+            .query_pairs()
+            .filter(|(param, _)| param == "titles")
+            .flat_map(|(_, titles)| {
+                titles
+                    .split('|')
+                    .map(|title| json!({ "title": title }))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "query": { "pages": pages } }))
+    }
+}
+
+#[tokio::test]
+async fn seed_orders_links_regardless_of_the_article() {
+    let mut found = Vec::new();
+    for order in [('A'..='Z').collect::<Vec<_>>(), ('A'..='Z').rev().collect()] {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(Letters { order })
+            .mount(&server)
+            .await;
+
+        let mut paths = Vec::new();
+        for seed in [None, Some(7)] {
+            let builder = searcher(&server, "Hub", "End");
+            let builder = match seed {
+                Some(seed) => builder.seed(seed),
+                None => builder,
+            };
+            paths.push(builder.build().unwrap().search().await.unwrap()[0].articles[1].clone());
+        }
+        found.push(paths);
+    }
+
+    // Unseeded, the first letter of the article is followed first
+    assert_eq!(found[0][0], "A");
+    assert_eq!(found[1][0], "Z");
+    assert_eq!(found[0][1], found[1][1]);
+}
+
+/// The fixtures served under another article path than Wikipedia's
+struct Prefixed(&'static str);
+
+impl Respond for Prefixed {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        let Some(title) = req.url.path().strip_prefix(self.0) else {
+            if req.url.path().starts_with("/wiki/") {
+                return ResponseTemplate::new(404);
+            }
+            return Fixtures.respond(req);
+        };
+        match fs::read_to_string(Fixtures::file(&Fixtures::resolve(&decode_title(title)))) {
+            Ok(html) => {
+                ResponseTemplate::new(200).set_body_raw(html.replace("/wiki/", self.0), "text/html")
+            }
+            Err(_) => ResponseTemplate::new(404),
+        }
+    }
+}
+
+#[tokio::test]
+async fn searches_under_another_article_path() {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(Prefixed("/index.php/"))
+        .mount(&server)
+        .await;
+    let paths = searcher(&server, "Rust", "Oxygen")
+        .wiki_prefix("index.php")
+        .build()
+        .unwrap()
+        .search()
+        .await
+        .unwrap();
+
+    assert_eq!(paths[0].articles, ["Rust", "Iron", "Oxygen"]);
+}
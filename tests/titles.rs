@@ -0,0 +1,51 @@
+//! Titles compared as typed in any case and spacing, written into URLs
+//! and read back from them
+
+use wiki_path::{decode_title, normalize_display, normalize_title, Wiki};
+
+#[test]
+fn encodes_titles_in_urls() {
+    let wiki = Wiki::new("en");
+    assert_eq!(
+        wiki.url("C_(programming_language)"),
+        "https://en.wikipedia.org/wiki/C_%28programming_language%29"
+    );
+    assert_eq!(
+        wiki.url("Ancient philosophy"),
+        "https://en.wikipedia.org/wiki/Ancient%20philosophy"
+    );
+
+    let wiki = Wiki::new("de");
+    assert_eq!(wiki.url("Köln"), "https://de.wikipedia.org/wiki/K%C3%B6ln");
+}
+
+#[test]
+fn decodes_the_titles_it_encodes() {
+    let wiki = Wiki::new("ja");
+    for title in ["C_(programming_language)", "Ancient philosophy", "東京"] {
+        let url = wiki.url(title);
+        let encoded = url.strip_prefix("https://ja.wikipedia.org/wiki/").unwrap();
+        assert!(encoded.is_ascii());
+        assert_eq!(decode_title(encoded), title);
+    }
+}
+
+#[test]
+fn compares_titles_however_they_are_typed() {
+    for title in ["philosophy", "Philosophy", " Philosophy "] {
+        assert_eq!(normalize_title(title), "Philosophy");
+    }
+    // Only the first letter is case-insensitive
+    assert_eq!(
+        normalize_title("ancient philosophy"),
+        normalize_title("Ancient_philosophy")
+    );
+    assert_ne!(
+        normalize_title("ancient philosophy"),
+        normalize_title("Ancient_Philosophy")
+    );
+    assert_eq!(
+        normalize_display("ancient_philosophy"),
+        "Ancient philosophy"
+    );
+}